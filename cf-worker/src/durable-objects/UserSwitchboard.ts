@@ -3,7 +3,8 @@
  *
  * Architecture: Dumb relay pattern
  * - Accepts WebSocket connections from both desktop and web-viewer clients
- * - Relays messages between them without processing
+ * - Relays messages between them, only stamping viewer messages with a viewer_id
+ *   so the desktop can track each viewer's handshake
  * - One instance per user for isolation
  */
 
@@ -39,6 +40,7 @@ export class UserSwitchboard {
   private handleWebSocket(request: Request, deviceType: string): Response {
     const pair = new WebSocketPair();
     const [client, server] = Object.values(pair);
+    const viewerId = crypto.randomUUID();
 
     server.accept();
 
@@ -59,7 +61,7 @@ export class UserSwitchboard {
       try {
         // Relay: web-viewer → desktop
         if (deviceType === 'web-viewer' && this.desktop) {
-          this.desktop.send(event.data);
+          this.desktop.send(withViewerId(event.data, viewerId));
         }
 
         // Relay: desktop → all web-viewers
@@ -97,3 +99,15 @@ export class UserSwitchboard {
     });
   }
 }
+
+/** Overwrite the viewer_id of a JSON message so viewers can't impersonate each other */
+function withViewerId(data: string | ArrayBuffer, viewerId: string): string | ArrayBuffer {
+  if (typeof data !== 'string') return data;
+  try {
+    const message = JSON.parse(data);
+    if (message === null || typeof message !== 'object' || Array.isArray(message)) return data;
+    return JSON.stringify({ ...message, viewer_id: viewerId });
+  } catch {
+    return data;
+  }
+}
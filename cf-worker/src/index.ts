@@ -13,6 +13,39 @@ interface Env {
 	AI: Ai;
 	SWITCHBOARD: DurableObjectNamespace;
 	ENVIRONMENT: string;
+	DESKTOP_TOKEN?: string; // secret; when set the desktop must present it as a bearer token
+}
+
+/** Optional /api/llm fields passed through to Workers AI */
+interface LLMOptions {
+	max_tokens?: number | null;
+	temperature?: number | null;
+	top_p?: number | null;
+	stop?: string[] | null;
+	seed?: number | null;
+	response_format?: { type: string; json_schema?: unknown } | null;
+}
+
+const LLM_OPTION_KEYS: Array<keyof LLMOptions> = [
+	'max_tokens',
+	'temperature',
+	'top_p',
+	'stop',
+	'seed',
+	'response_format',
+];
+
+/** Copy every set option onto `aiRequest` and return the names that were copied */
+function forwardOptions(aiRequest: Record<string, unknown>, options: LLMOptions): string[] {
+	const forwarded: string[] = [];
+	for (const key of LLM_OPTION_KEYS) {
+		const value = options[key];
+		if (value === undefined || value === null) continue;
+		if (Array.isArray(value) && value.length === 0) continue;
+		aiRequest[key] = value;
+		forwarded.push(key);
+	}
+	return forwarded;
 }
 
 export default {
@@ -32,6 +65,14 @@ export default {
 
 		// Route: WebSocket connection (relay to Durable Object)
 		if (url.pathname === '/connect') {
+			const device = url.searchParams.get('device') || 'desktop';
+			if (device === 'desktop' && env.DESKTOP_TOKEN) {
+				const auth = request.headers.get('Authorization');
+				if (auth !== `Bearer ${env.DESKTOP_TOKEN}`) {
+					return new Response('Unauthorized', { status: 401 });
+				}
+			}
+
 			const id = env.SWITCHBOARD.idFromName('default'); // TODO: use user ID for multi-user
 			const stub = env.SWITCHBOARD.get(id);
 			return stub.fetch(request);
@@ -40,11 +81,11 @@ export default {
 		// Route: LLM API with native tool calling support
 		if (url.pathname === '/api/llm' && request.method === 'POST') {
 			try {
-				const { messages, model, tools } = await request.json() as {
+				const { messages, model, tools, ...options } = await request.json() as {
 					messages: Array<{ role: string; content: string }>;
 					model: string;
 					tools?: Array<any>;
-				};
+				} & LLMOptions;
 
 				if (!model) {
 					return new Response(JSON.stringify({ error: 'Model ID required' }), {
@@ -59,6 +100,12 @@ export default {
 					stream: false,
 				};
 
+				// Forward the sampling parameters and response format the app set
+				const forwarded = forwardOptions(aiRequest, options);
+				if (forwarded.length > 0) {
+					console.log('[LLM API] Forwarding', forwarded.join(', '));
+				}
+
 				// Add tools if provided - Cloudflare Workers AI native tool calling
 				if (tools && tools.length > 0) {
 					aiRequest.tools = tools;
@@ -350,7 +350,7 @@ export const DEFAULT_AGENTS: Record<string, AgentPreset> = {
       purpose: 'Friendly conversation and high-level progress updates',
       systemPrompt: DEFAULT_PROMPTS['conversational'].content,
       tools: [
-        { toolId: 'take_screenshot', enabled: true },
+        { toolId: 'screen_screenshot', enabled: true },
       ],
       modelId: '@cf/meta/llama-3.3-70b-instruct-fp8-fast',
       maxIterations: 3,
@@ -375,10 +375,10 @@ export const DEFAULT_AGENTS: Record<string, AgentPreset> = {
   //     tools: [
   //       { toolId: 'mouse_move', enabled: true },
   //       { toolId: 'mouse_click', enabled: true },
-  //       { toolId: 'keyboard_input', enabled: true },
-  //       { toolId: 'keyboard_command', enabled: true },
-  //       { toolId: 'get_mouse_position', enabled: true },
-  //       { toolId: 'take_screenshot', enabled: true },
+  //       { toolId: 'keyboard_type', enabled: true },
+  //       { toolId: 'keyboard_hotkey', enabled: true },
+  //       { toolId: 'screen_get_position', enabled: true },
+  //       { toolId: 'screen_screenshot', enabled: true },
   //     ],
   //     modelId: '@cf/meta/llama-3.3-70b-instruct-fp8-fast',
   //     maxIterations: 5,
@@ -425,8 +425,8 @@ export const DEFAULT_AGENTS: Record<string, AgentPreset> = {
       tools: [
         { toolId: 'mouse_move', enabled: true },
         { toolId: 'mouse_click', enabled: true },
-        { toolId: 'keyboard_input', enabled: true },
-        { toolId: 'get_mouse_position', enabled: true },
+        { toolId: 'keyboard_type', enabled: true },
+        { toolId: 'screen_get_position', enabled: true },
       ],
       modelId: '@cf/meta/llama-3.3-70b-instruct-fp8-fast',
       maxIterations: 3,
@@ -448,8 +448,8 @@ export const DEFAULT_AGENTS: Record<string, AgentPreset> = {
       purpose: 'Code analysis, generation, and debugging assistance',
       systemPrompt: DEFAULT_PROMPTS['cot-standard'].content,
       tools: [
-        { toolId: 'keyboard_input', enabled: true },
-        { toolId: 'take_screenshot', enabled: true },
+        { toolId: 'keyboard_type', enabled: true },
+        { toolId: 'screen_screenshot', enabled: true },
         { toolId: 'mouse_move', enabled: true },
         { toolId: 'mouse_click', enabled: true },
       ],
@@ -475,10 +475,10 @@ export const DEFAULT_AGENTS: Record<string, AgentPreset> = {
       tools: [
         { toolId: 'mouse_move', enabled: true },
         { toolId: 'mouse_click', enabled: true },
-        { toolId: 'keyboard_input', enabled: true },
-        { toolId: 'keyboard_command', enabled: true },
-        { toolId: 'get_mouse_position', enabled: true },
-        { toolId: 'take_screenshot', enabled: true },
+        { toolId: 'keyboard_type', enabled: true },
+        { toolId: 'keyboard_hotkey', enabled: true },
+        { toolId: 'screen_get_position', enabled: true },
+        { toolId: 'screen_screenshot', enabled: true },
         { toolId: 'mouse_scroll', enabled: true },
       ],
       modelId: '@cf/meta/llama-3.3-70b-instruct-fp8-fast',
@@ -0,0 +1,20 @@
+"""Example tool plugin: reads a JSON request on stdin, writes a JSON result on stdout."""
+
+import json
+import sys
+
+request = json.load(sys.stdin)
+text = request["args"].get("text", "")
+
+json.dump(
+    {
+        "success": True,
+        "message": f"{len(text.split())} words",
+        "data": {
+            "words": len(text.split()),
+            "lines": len(text.splitlines()),
+            "characters": len(text),
+        },
+    },
+    sys.stdout,
+)
@@ -0,0 +1,12 @@
+//! Print the JSON Schema of the WebSocket protocol
+//!
+//! Run with `cargo run --example protocol_schema > web-viewer/src/types/protocol.schema.json`
+//! and check the viewer's message types against the result.
+
+fn main() {
+    let schema = cf_ai_local_tools::websocket::protocol::json_schema();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).expect("schema serializes")
+    );
+}
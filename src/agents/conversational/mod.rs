@@ -4,4 +4,4 @@
 
 pub mod agent;
 
-pub use agent::ConversationalAgent;
+pub use agent::respond;
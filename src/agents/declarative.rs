@@ -0,0 +1,416 @@
+//! Agents defined by TOML or JSON files
+//!
+//! A definition names the agent, its tools, its prompt (inline or in a file next
+//! to the definition), its `ReasoningConfig` and the keywords used to route
+//! tasks to it. The built-in agents are defined this way too.
+
+use crate::agents::conversation::ConversationManager;
+use crate::core::{
+    Agent, AgentContext, AgentResult, AppError, LLMClient, ReasoningConfig, Result, Tool,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// How a config-driven agent runs a task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentEngine {
+    /// Reason, act and observe until the task is done
+    #[default]
+    Thinking,
+    /// Answer with a single LLM call, running any tools it asks for
+    Conversational,
+}
+
+/// How well an agent fits a task, used by `can_handle_task`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskRouting {
+    /// Words or phrases that mark a task as a good fit
+    pub keywords: Vec<String>,
+    /// Sample tasks; a task sharing most of an example's words is a good fit
+    pub examples: Vec<String>,
+    /// Score for a task that matches a keyword or example
+    pub match_score: f32,
+    /// Score for any other task
+    pub default_score: f32,
+}
+
+impl Default for TaskRouting {
+    fn default() -> Self {
+        Self {
+            keywords: Vec::new(),
+            examples: Vec::new(),
+            match_score: 0.9,
+            default_score: 0.1,
+        }
+    }
+}
+
+impl TaskRouting {
+    /// Score `task` between 0 and 1
+    pub fn score(&self, task: &str) -> f32 {
+        let task = task.to_lowercase();
+        let keyword_match = self
+            .keywords
+            .iter()
+            .any(|keyword| task.contains(&keyword.to_lowercase()));
+        if keyword_match || self.examples.iter().any(|e| resembles(&task, e)) {
+            self.match_score
+        } else {
+            self.default_score
+        }
+    }
+}
+
+/// Whether `task` contains at least half of the significant words of `example`
+fn resembles(task: &str, example: &str) -> bool {
+    let task_words: Vec<&str> = task.split(|c: char| !c.is_alphanumeric()).collect();
+    let example = example.to_lowercase();
+    let words: Vec<&str> = example
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 3)
+        .collect();
+    let shared = words.iter().filter(|w| task_words.contains(w)).count();
+    !words.is_empty() && shared * 2 >= words.len()
+}
+
+fn default_version() -> String {
+    "0.1.0".to_string()
+}
+
+/// An agent as written in a definition file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// IDs of the tools the agent uses
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// System prompt text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// File holding the system prompt, relative to the definition file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_file: Option<PathBuf>,
+    #[serde(default)]
+    pub engine: AgentEngine,
+    #[serde(default)]
+    pub reasoning: ReasoningConfig,
+    #[serde(default)]
+    pub routing: TaskRouting,
+}
+
+impl AgentDefinition {
+    /// Parse a TOML definition
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text)
+            .map_err(|e| AppError::Configuration(format!("Invalid agent definition: {}", e)))
+    }
+
+    /// Parse a JSON definition
+    pub fn from_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// Read a `.toml` or `.json` definition, loading its prompt file if it names one
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut definition = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&text)?,
+            _ => Self::from_toml(&text)?,
+        };
+
+        if definition.prompt.is_none() {
+            if let Some(file) = &definition.prompt_file {
+                let prompt_path = path.parent().unwrap_or(Path::new(".")).join(file);
+                let prompt = std::fs::read_to_string(&prompt_path).map_err(|e| {
+                    AppError::Configuration(format!(
+                        "Agent '{}' prompt file {}: {}",
+                        definition.id,
+                        prompt_path.display(),
+                        e
+                    ))
+                })?;
+                definition.prompt = Some(prompt);
+            }
+        }
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Check the fields the registry and LLM rely on
+    pub fn validate(&self) -> Result<()> {
+        let valid_id = !self.id.is_empty()
+            && self
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_id {
+            return Err(AppError::Configuration(format!(
+                "Invalid agent ID '{}': use letters, digits, '_' and '-'",
+                self.id
+            )));
+        }
+        if self.name.trim().is_empty() {
+            return Err(AppError::Configuration(format!(
+                "Agent '{}' has no name",
+                self.id
+            )));
+        }
+        if self.prompt.as_deref().is_none_or(|p| p.trim().is_empty()) {
+            return Err(AppError::Configuration(format!(
+                "Agent '{}' has no prompt or prompt_file",
+                self.id
+            )));
+        }
+        if self.reasoning.max_iterations == 0 {
+            return Err(AppError::Configuration(format!(
+                "Agent '{}' needs max_iterations of at least 1",
+                self.id
+            )));
+        }
+        for score in [self.routing.match_score, self.routing.default_score] {
+            if !(0.0..=1.0).contains(&score) {
+                return Err(AppError::Configuration(format!(
+                    "Agent '{}' routing scores must be between 0 and 1, got {}",
+                    self.id, score
+                )));
+            }
+        }
+        self.reasoning.sampling.validate()
+    }
+}
+
+/// An agent whose behaviour comes entirely from an `AgentDefinition`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigAgent {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub system_prompt: String,
+    pub reasoning_config: ReasoningConfig,
+    pub capabilities: Vec<String>,
+    pub tool_dependencies: Vec<String>,
+    pub engine: AgentEngine,
+    pub routing: TaskRouting,
+}
+
+impl ConfigAgent {
+    /// Build an agent from a validated definition
+    pub fn from_definition(definition: AgentDefinition) -> Result<Self> {
+        definition.validate()?;
+        Ok(Self {
+            id: definition.id,
+            name: definition.name,
+            description: definition.description,
+            version: definition.version,
+            system_prompt: definition.prompt.unwrap_or_default(),
+            reasoning_config: definition.reasoning,
+            capabilities: definition.capabilities,
+            tool_dependencies: definition.tools,
+            engine: definition.engine,
+            routing: definition.routing,
+        })
+    }
+
+    /// Load an agent from a definition file
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_definition(AgentDefinition::load(path)?)
+    }
+}
+
+/// Build a built-in agent from its embedded definition and prompt
+pub(crate) fn builtin(definition: &str, prompt: &str) -> ConfigAgent {
+    let mut definition =
+        AgentDefinition::from_toml(definition).expect("built-in agent definition is valid TOML");
+    definition.prompt = Some(prompt.to_string());
+    ConfigAgent::from_definition(definition).expect("built-in agent definition is valid")
+}
+
+#[async_trait]
+impl Agent for ConfigAgent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    fn tool_dependencies(&self) -> &[String] {
+        &self.tool_dependencies
+    }
+
+    fn system_prompt(&self) -> &str {
+        &self.system_prompt
+    }
+
+    fn reasoning_config(&self) -> &ReasoningConfig {
+        &self.reasoning_config
+    }
+
+    async fn execute(
+        &self,
+        task: &str,
+        context: &AgentContext,
+        llm: &dyn LLMClient,
+        conversation_manager: Option<Arc<dyn ConversationManager>>,
+        available_tools: &[Box<dyn Tool>],
+    ) -> Result<AgentResult> {
+        match self.engine {
+            AgentEngine::Thinking => {
+                crate::agents::thinking::ThinkingEngine::for_agent(self)
+                    .process(
+                        self,
+                        task,
+                        context,
+                        llm,
+                        conversation_manager,
+                        available_tools,
+                    )
+                    .await
+            }
+            AgentEngine::Conversational => {
+                crate::agents::conversational::respond(
+                    self,
+                    task,
+                    context,
+                    llm,
+                    conversation_manager,
+                    available_tools,
+                )
+                .await
+            }
+        }
+    }
+
+    fn can_handle_task(&self, task: &str) -> f32 {
+        self.routing.score(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = r#"
+id = "release-notes"
+name = "Release Notes"
+description = "Writes release notes"
+tools = ["fetch_url"]
+prompt_file = "prompt.md"
+
+[reasoning]
+max_iterations = 3
+
+[reasoning.sampling]
+temperature = 0.2
+
+[routing]
+keywords = ["changelog"]
+examples = ["write release notes for version 2"]
+"#;
+
+    #[test]
+    fn test_load_resolves_prompt_file_and_defaults() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("notes.toml"), DEFINITION).unwrap();
+        std::fs::write(dir.join("prompt.md"), "You write release notes.").unwrap();
+
+        let agent = ConfigAgent::load(&dir.join("notes.toml")).unwrap();
+        assert_eq!(agent.id(), "release-notes");
+        assert_eq!(agent.system_prompt(), "You write release notes.");
+        assert_eq!(agent.version(), "0.1.0");
+        assert_eq!(agent.engine, AgentEngine::Thinking);
+        assert_eq!(agent.reasoning_config.max_iterations, 3);
+        assert_eq!(agent.reasoning_config.sampling.temperature, Some(0.2));
+        assert_eq!(
+            agent.reasoning_config.model_id,
+            ReasoningConfig::default().model_id
+        );
+
+        std::fs::remove_file(dir.join("prompt.md")).unwrap();
+        assert!(ConfigAgent::load(&dir.join("notes.toml")).is_err());
+    }
+
+    #[test]
+    fn test_json_definition_and_validation() {
+        let definition = AgentDefinition::from_json(
+            r#"{"id": "helper", "name": "Helper", "description": "Helps",
+                "prompt": "Be helpful.", "engine": "conversational"}"#,
+        )
+        .unwrap();
+        assert_eq!(definition.engine, AgentEngine::Conversational);
+        assert!(definition.validate().is_ok());
+
+        let no_prompt = AgentDefinition {
+            prompt: None,
+            ..definition.clone()
+        };
+        assert!(no_prompt.validate().is_err());
+
+        let bad_id = AgentDefinition {
+            id: "two words".to_string(),
+            ..definition
+        };
+        assert!(bad_id.validate().is_err());
+    }
+
+    #[test]
+    fn test_routing_uses_keywords_and_examples() {
+        let mut definition = AgentDefinition::from_toml(DEFINITION).unwrap();
+        definition.prompt = Some("prompt".to_string());
+        let agent = ConfigAgent::from_definition(definition).unwrap();
+
+        assert_eq!(agent.can_handle_task("Update the CHANGELOG"), 0.9);
+        assert_eq!(
+            agent.can_handle_task("Please write notes for the release"),
+            0.9
+        );
+        assert_eq!(agent.can_handle_task("What's the weather?"), 0.1);
+    }
+
+    #[test]
+    fn test_builtin_definitions_match_previous_agents() {
+        let desktop = ConfigAgent::desktop_automation();
+        assert_eq!(desktop.id, "desktop-automation-agent");
+        assert!(desktop.reasoning_config.attach_screenshots);
+        assert_eq!(
+            desktop.reasoning_config.sampling,
+            crate::core::SamplingParams::deterministic()
+        );
+        assert_eq!(desktop.can_handle_task("click the button"), 0.9);
+
+        let web = ConfigAgent::web_research();
+        assert_eq!(web.tool_dependencies, vec!["web_search", "fetch_url"]);
+        assert_eq!(web.can_handle_task("search for rust news"), 0.9);
+        assert_eq!(web.can_handle_task("hello"), 0.1);
+
+        let chat = ConfigAgent::conversational();
+        assert_eq!(chat.engine, AgentEngine::Conversational);
+        assert_eq!(chat.can_handle_task("anything"), 0.5);
+        assert!(chat.tool_dependencies.is_empty());
+    }
+}
@@ -3,5 +3,3 @@
 //! Specializes in controlling mouse and keyboard for desktop automation tasks.
 
 pub mod agent;
-
-pub use agent::DesktopAutomationAgent;
@@ -4,11 +4,10 @@ pub mod web_research;
 
 pub mod conversation;
 pub mod conversational;
+pub mod declarative;
 pub mod delegation;
 pub mod registry;
 pub mod thinking;
 
-// Re-export all built-in agents
-pub use conversational::ConversationalAgent;
-pub use desktop_automation::DesktopAutomationAgent;
-pub use web_research::WebResearchAgent;
+// Built-in agents are constructed with `ConfigAgent::conversational()` and friends
+pub use declarative::{AgentDefinition, AgentEngine, ConfigAgent, TaskRouting};
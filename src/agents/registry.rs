@@ -1,16 +1,37 @@
 //! Agent registry for managing agent components
 
 use crate::core::Agent;
+use crate::registry::events::{emit, EventSender, RegistryEvent};
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+/// Whether a registered agent can run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Availability {
+    Available,
+    /// Not run until the reason is resolved, e.g. a missing tool
+    Disabled {
+        reason: String,
+    },
+}
+
+impl Availability {
+    pub fn is_available(&self) -> bool {
+        matches!(self, Availability::Available)
+    }
+}
 
-/// Trait for agent registries
+/// Trait for agent registries; implementations lock internally so they can be shared
 #[async_trait]
 pub trait AgentRegistry: Send + Sync {
     /// Register an agent
-    async fn register(&mut self, agent: Box<dyn Agent>) -> crate::core::Result<()>;
+    async fn register(&self, agent: Box<dyn Agent>) -> crate::core::Result<()>;
 
     /// Unregister an agent by ID
-    async fn unregister(&mut self, id: &str) -> crate::core::Result<()>;
+    async fn unregister(&self, id: &str) -> crate::core::Result<()>;
 
     /// Get an agent by ID
     async fn get(&self, id: &str) -> crate::core::Result<Option<Box<dyn Agent>>>;
@@ -45,24 +66,50 @@ pub trait AgentRegistry: Send + Sync {
         &self,
         agent_id: &str,
     ) -> crate::core::Result<Option<crate::registry::ComponentMetadata>>;
+
+    /// Whether an agent can run; `None` if it isn't registered
+    async fn availability(&self, id: &str) -> crate::core::Result<Option<Availability>>;
+
+    /// Enable or disable a registered agent
+    async fn set_availability(
+        &self,
+        id: &str,
+        availability: Availability,
+    ) -> crate::core::Result<()>;
 }
 
 /// Default implementation of AgentRegistry
 pub struct DefaultAgentRegistry {
+    state: RwLock<AgentState>,
+    events: Option<EventSender>,
+}
+
+/// Registered agents and their indexes, guarded by one lock
+#[derive(Default)]
+struct AgentState {
     agents: std::collections::HashMap<String, Box<dyn Agent>>,
     capability_index: std::collections::HashMap<String, Vec<String>>,
     tool_dependency_index: std::collections::HashMap<String, Vec<String>>,
+    /// Reasons for the agents that are disabled
+    disabled: std::collections::HashMap<String, String>,
 }
 
 impl DefaultAgentRegistry {
     pub fn new() -> Self {
         Self {
-            agents: std::collections::HashMap::new(),
-            capability_index: std::collections::HashMap::new(),
-            tool_dependency_index: std::collections::HashMap::new(),
+            state: RwLock::new(AgentState::default()),
+            events: None,
         }
     }
 
+    /// Publish agent added, updated and removed events on `events`
+    pub fn with_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+}
+
+impl AgentState {
     /// Rebuild capability and dependency indexes
     fn rebuild_indexes(&mut self) {
         self.capability_index.clear();
@@ -86,47 +133,72 @@ impl DefaultAgentRegistry {
             }
         }
     }
+
+    /// Clones of the agents listed in an index entry
+    fn lookup(&self, agent_ids: Option<&Vec<String>>) -> Vec<Box<dyn Agent>> {
+        agent_ids
+            .into_iter()
+            .flatten()
+            .filter_map(|agent_id| self.agents.get(agent_id))
+            .map(|agent| dyn_clone::clone_box(agent.as_ref()))
+            .collect()
+    }
 }
 
 #[async_trait]
 impl AgentRegistry for DefaultAgentRegistry {
-    async fn register(&mut self, agent: Box<dyn Agent>) -> crate::core::Result<()> {
+    async fn register(&self, agent: Box<dyn Agent>) -> crate::core::Result<()> {
         let agent_id = agent.id().to_string();
+        {
+            let mut state = self.state.write().await;
+            if state.agents.contains_key(&agent_id) {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Agent '{}' already registered",
+                    agent_id
+                )));
+            }
 
-        if self.agents.contains_key(&agent_id) {
-            return Err(crate::core::AppError::Registry(format!(
-                "Agent '{}' already registered",
-                agent_id
-            )));
+            state.agents.insert(agent_id.clone(), agent);
+            state.rebuild_indexes();
         }
 
-        self.agents.insert(agent_id, agent);
-        self.rebuild_indexes();
-
+        emit(&self.events, RegistryEvent::AgentAdded { agent_id });
         Ok(())
     }
 
-    async fn unregister(&mut self, id: &str) -> crate::core::Result<()> {
-        if self.agents.remove(id).is_none() {
-            return Err(crate::core::AppError::Registry(format!(
-                "Agent '{}' not found",
-                id
-            )));
+    async fn unregister(&self, id: &str) -> crate::core::Result<()> {
+        {
+            let mut state = self.state.write().await;
+            if state.agents.remove(id).is_none() {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Agent '{}' not found",
+                    id
+                )));
+            }
+            state.disabled.remove(id);
+            state.rebuild_indexes();
         }
 
-        self.rebuild_indexes();
+        emit(
+            &self.events,
+            RegistryEvent::AgentRemoved {
+                agent_id: id.to_string(),
+            },
+        );
         Ok(())
     }
 
     async fn get(&self, id: &str) -> crate::core::Result<Option<Box<dyn Agent>>> {
-        Ok(self
+        let state = self.state.read().await;
+        Ok(state
             .agents
             .get(id)
             .map(|a| dyn_clone::clone_box(a.as_ref())))
     }
 
     async fn list(&self) -> crate::core::Result<Vec<Box<dyn Agent>>> {
-        Ok(self
+        let state = self.state.read().await;
+        Ok(state
             .agents
             .values()
             .map(|a| dyn_clone::clone_box(a.as_ref()))
@@ -134,47 +206,23 @@ impl AgentRegistry for DefaultAgentRegistry {
     }
 
     async fn count(&self) -> crate::core::Result<usize> {
-        Ok(self.agents.len())
+        Ok(self.state.read().await.agents.len())
     }
 
     async fn find_by_capability(
         &self,
         capability: &str,
     ) -> crate::core::Result<Vec<Box<dyn Agent>>> {
-        let agent_ids = self
-            .capability_index
-            .get(capability)
-            .cloned()
-            .unwrap_or_default();
-
-        let mut results = Vec::new();
-        for agent_id in agent_ids {
-            if let Some(agent) = self.agents.get(&agent_id) {
-                results.push(dyn_clone::clone_box(agent.as_ref()));
-            }
-        }
-
-        Ok(results)
+        let state = self.state.read().await;
+        Ok(state.lookup(state.capability_index.get(capability)))
     }
 
     async fn find_by_tool_dependency(
         &self,
         tool_id: &str,
     ) -> crate::core::Result<Vec<Box<dyn Agent>>> {
-        let agent_ids = self
-            .tool_dependency_index
-            .get(tool_id)
-            .cloned()
-            .unwrap_or_default();
-
-        let mut results = Vec::new();
-        for agent_id in agent_ids {
-            if let Some(agent) = self.agents.get(&agent_id) {
-                results.push(dyn_clone::clone_box(agent.as_ref()));
-            }
-        }
-
-        Ok(results)
+        let state = self.state.read().await;
+        Ok(state.lookup(state.tool_dependency_index.get(tool_id)))
     }
 
     async fn validate_dependencies(
@@ -182,17 +230,20 @@ impl AgentRegistry for DefaultAgentRegistry {
         agent: &dyn Agent,
         available_tools: &[String],
     ) -> crate::core::Result<()> {
-        let required_tools = agent.tool_dependencies();
         let available_set: std::collections::HashSet<_> = available_tools.iter().collect();
-
-        for tool_id in required_tools {
-            if !available_set.contains(tool_id) {
-                return Err(crate::core::AppError::Registry(format!(
-                    "Agent '{}' requires tool '{}' which is not available",
-                    agent.id(),
-                    tool_id
-                )));
-            }
+        let missing: Vec<&str> = agent
+            .tool_dependencies()
+            .iter()
+            .filter(|tool_id| !available_set.contains(tool_id))
+            .map(String::as_str)
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(crate::core::AppError::Registry(format!(
+                "Agent '{}' requires tools that are not available: {}",
+                agent.id(),
+                missing.join(", ")
+            )));
         }
 
         Ok(())
@@ -202,7 +253,8 @@ impl AgentRegistry for DefaultAgentRegistry {
         &self,
         agent_id: &str,
     ) -> crate::core::Result<Option<crate::registry::ComponentMetadata>> {
-        if let Some(agent) = self.agents.get(agent_id) {
+        let state = self.state.read().await;
+        if let Some(agent) = state.agents.get(agent_id) {
             Ok(Some(crate::registry::ComponentMetadata {
                 id: agent.id().to_string(),
                 name: agent.name().to_string(),
@@ -216,4 +268,54 @@ impl AgentRegistry for DefaultAgentRegistry {
             Ok(None)
         }
     }
+
+    async fn availability(&self, id: &str) -> crate::core::Result<Option<Availability>> {
+        let state = self.state.read().await;
+        if !state.agents.contains_key(id) {
+            return Ok(None);
+        }
+        Ok(Some(match state.disabled.get(id) {
+            Some(reason) => Availability::Disabled {
+                reason: reason.clone(),
+            },
+            None => Availability::Available,
+        }))
+    }
+
+    async fn set_availability(
+        &self,
+        id: &str,
+        availability: Availability,
+    ) -> crate::core::Result<()> {
+        let changed = {
+            let mut state = self.state.write().await;
+            if !state.agents.contains_key(id) {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Agent '{}' not found",
+                    id
+                )));
+            }
+            let previous = match &availability {
+                Availability::Available => state.disabled.remove(id),
+                Availability::Disabled { reason } => {
+                    state.disabled.insert(id.to_string(), reason.clone())
+                }
+            };
+            match &availability {
+                Availability::Available => previous.is_some(),
+                Availability::Disabled { reason } => previous.as_ref() != Some(reason),
+            }
+        };
+
+        if changed {
+            emit(
+                &self.events,
+                RegistryEvent::AgentUpdated {
+                    agent_id: id.to_string(),
+                    availability,
+                },
+            );
+        }
+        Ok(())
+    }
 }
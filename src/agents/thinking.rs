@@ -1,28 +1,530 @@
-use crate::agents::conversation::{ConversationManager, ProgressType};
-use crate::core::Result;
+//! Shared ReAct loop used by the tool-driven agents
+//!
+//! Each iteration manages the transcript size, asks the LLM for the next
+//! action, executes the requested tools and feeds the observations back until
+//! the model answers without calling a tool or the iteration limit is reached.
+
+use crate::agents::conversation::ConversationManager;
+use crate::core::{ContentPart, Result};
+use crate::llm::context::ContextManager;
+use crate::llm::structured;
+use crate::llm::usage::ExecutionUsage;
 use crate::{
-    Agent, AgentContext, AgentResult, ConversationMessage, ExecutionStep, LLMClient, LLMMessage,
-    LLMTool, StepType, Tool, ToolCall, ToolExecutionState, ToolObservation,
+    Agent, AgentContext, AgentResult, ExecutionStep, LLMClient, LLMMessage, LLMTool, StepType,
+    Tool, ToolCall, ToolExecutionState, ToolObservation,
 };
 use std::sync::Arc;
 
 pub struct ThinkingEngine {
-    // Add fields as needed
+    context_manager: ContextManager,
 }
 
 impl ThinkingEngine {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            context_manager: ContextManager::default(),
+        }
+    }
+
+    /// Create an engine using the agent's context management settings
+    pub fn for_agent(agent: &dyn Agent) -> Self {
+        Self {
+            context_manager: ContextManager::new(agent.reasoning_config().context.clone()),
+        }
     }
 
-    pub async fn process(&self, _task: &str, _context: &AgentContext) -> Result<AgentResult> {
-        // Placeholder implementation
+    /// Run the ReAct loop for `agent` on `task`
+    pub async fn process(
+        &self,
+        agent: &dyn Agent,
+        task: &str,
+        context: &AgentContext,
+        llm: &dyn LLMClient,
+        conversation_manager: Option<Arc<dyn ConversationManager>>,
+        available_tools: &[Box<dyn Tool>],
+    ) -> Result<AgentResult> {
+        let start_time = std::time::Instant::now();
+        let config = agent.reasoning_config();
+        let mut steps = StepRecorder::new(conversation_manager.clone());
+
+        steps
+            .record(
+                StepType::Thinking,
+                format!("Analyzing task: \"{}\"", task),
+                None,
+                None,
+            )
+            .await;
+
+        let llm_tools = to_llm_tools(available_tools);
+        let mut messages = initial_messages(agent.system_prompt(), context, task);
+
+        let mut llm_options = context.llm_options.clone();
+        llm_options
+            .sampling
+            .get_or_insert_with(|| config.sampling.clone());
+
+        let execution_state = Arc::new(tokio::sync::RwLock::new(ToolExecutionState::default()));
+        let mut loop_detector = crate::core::LoopDetector::new(6);
+        let attach_images =
+            config.attach_screenshots && crate::llm::client::supports_vision(&config.model_id);
+        let mut usage = ExecutionUsage::default();
+        let mut final_response = None;
+        let mut abort_reason = None;
+
+        for iteration in 0..config.max_iterations {
+            execution_state.write().await.current_iteration = iteration;
+
+            if let Some(report) = self
+                .context_manager
+                .prepare(&mut messages, &config.model_id, llm)
+                .await?
+            {
+                if let Some(summary_usage) = &report.llm_usage {
+                    usage.record(&config.model_id, Some(summary_usage));
+                }
+                steps
+                    .record(StepType::Thinking, report.describe(), None, None)
+                    .await;
+            }
+
+            let response = llm
+                .chat_with_options(
+                    &messages,
+                    &config.model_id,
+                    Some(llm_tools.clone()),
+                    &llm_options,
+                )
+                .await?;
+            usage.record(&response.model, response.usage.as_ref());
+
+            if let Some(cache) = response.cache {
+                steps
+                    .record(StepType::Thinking, cache.describe().to_string(), None, None)
+                    .await;
+            }
+
+            if let Err(e) = config.budget.check(&usage.total) {
+                steps
+                    .record(StepType::Reflection, format!("Aborting: {}", e), None, None)
+                    .await;
+                abort_reason = Some(e.to_string());
+                break;
+            }
+
+            let tool_calls = match response.tool_calls {
+                Some(calls) if !calls.is_empty() => calls,
+                _ => {
+                    final_response = Some(response.response);
+                    break;
+                }
+            };
+
+            let tool_names: Vec<String> = tool_calls.iter().map(|c| c.name.clone()).collect();
+            steps
+                .record(
+                    StepType::Planning,
+                    format!("Planning to use tool(s): {}", tool_names.join(", ")),
+                    None,
+                    None,
+                )
+                .await;
+
+            messages.push(LLMMessage {
+                role: "assistant".to_string(),
+                content: response.response.into(),
+                tool_calls: Some(tool_calls.clone()),
+            });
+
+            let mut latest_screenshot = None;
+            for call in tool_calls {
+                steps
+                    .record(
+                        StepType::Action,
+                        format!("Executing tool: {}", call.name),
+                        Some(ToolCall {
+                            tool_name: call.name.clone(),
+                            arguments: call.arguments.clone(),
+                            execution_time: std::time::Duration::from_millis(0),
+                        }),
+                        None,
+                    )
+                    .await;
+
+                let observation = if loop_detector.check_loop(&call.name, &call.arguments) {
+                    ToolObservation {
+                        success: false,
+                        message: format!(
+                            "Repeated call to '{}' with the same arguments; try a different approach",
+                            call.name
+                        ),
+                        data: None,
+                        error: Some("Loop detected".to_string()),
+                    }
+                } else {
+                    execute_tool(
+                        agent.id(),
+                        &call.name,
+                        &call.arguments,
+                        available_tools,
+                        &conversation_manager,
+                        &execution_state,
+                    )
+                    .await
+                };
+
+                // Raw image data never goes into the transcript as text
+                let (observation, image) = take_screenshot_image(observation, attach_images);
+                if image.is_some() {
+                    latest_screenshot = image;
+                }
+
+                messages.push(LLMMessage {
+                    role: "tool".to_string(),
+                    content: serde_json::to_string(&observation)
+                        .unwrap_or_default()
+                        .into(),
+                    tool_calls: None,
+                });
+
+                steps
+                    .record(
+                        StepType::Observation,
+                        observation.message.clone(),
+                        None,
+                        Some(observation),
+                    )
+                    .await;
+            }
+
+            if let Some(image) = latest_screenshot {
+                // Only the newest screenshot is worth its tokens
+                for message in messages.iter_mut() {
+                    if message.content.image_count() > 0 {
+                        message.content = message.content.without_images();
+                    }
+                }
+                messages.push(LLMMessage {
+                    role: "user".to_string(),
+                    content: vec![ContentPart::text("Latest screenshot:"), image].into(),
+                    tool_calls: None,
+                });
+            }
+        }
+
+        let mut success = final_response.is_some();
+        let mut structured_output = None;
+        if let (Some(answer), Some(schema)) = (&final_response, &config.output_schema) {
+            steps
+                .record(
+                    StepType::Thinking,
+                    "Formatting the final answer as structured output".to_string(),
+                    None,
+                    None,
+                )
+                .await;
+
+            messages.push(LLMMessage {
+                role: "assistant".to_string(),
+                content: answer.as_str().into(),
+                tool_calls: None,
+            });
+            messages.push(LLMMessage {
+                role: "user".to_string(),
+                content: "Restate your final answer as JSON matching the required schema.".into(),
+                tool_calls: None,
+            });
+
+            match structured::generate(
+                llm,
+                &messages,
+                &config.model_id,
+                schema,
+                &llm_options,
+                structured::DEFAULT_STRUCTURED_RETRIES,
+            )
+            .await
+            {
+                Ok(output) => {
+                    for attempt in &output.usage {
+                        usage.record(&output.model, attempt.as_ref());
+                    }
+                    structured_output = Some(output.value);
+                }
+                Err(e) => {
+                    steps
+                        .record(
+                            StepType::Reflection,
+                            format!("Structured output failed: {}", e),
+                            None,
+                            None,
+                        )
+                        .await;
+                    success = false;
+                }
+            }
+        }
+
+        let response = match (final_response, abort_reason) {
+            (Some(response), _) => response,
+            (None, Some(reason)) => format!("Execution aborted: {}", reason),
+            (None, None) => format!(
+                "Stopped after reaching the maximum of {} iterations without a final answer.",
+                config.max_iterations
+            ),
+        };
+
+        steps
+            .record(StepType::Completion, response.clone(), None, None)
+            .await;
+
         Ok(AgentResult {
-            success: true,
-            response: "Thinking engine processed the task".to_string(),
-            steps: vec![],
-            execution_time: std::time::Duration::from_secs(0),
-            final_context: _context.clone(),
+            success,
+            response,
+            steps: steps.into_steps(),
+            execution_time: start_time.elapsed(),
+            final_context: context.clone(),
+            usage,
+            structured_output,
         })
     }
 }
+
+impl Default for ThinkingEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records execution steps and streams them to the conversation manager
+struct StepRecorder {
+    steps: Vec<ExecutionStep>,
+    manager: Option<Arc<dyn ConversationManager>>,
+}
+
+impl StepRecorder {
+    fn new(manager: Option<Arc<dyn ConversationManager>>) -> Self {
+        Self {
+            steps: Vec::new(),
+            manager,
+        }
+    }
+
+    async fn record(
+        &mut self,
+        step_type: StepType,
+        content: String,
+        tool_call: Option<ToolCall>,
+        tool_observation: Option<ToolObservation>,
+    ) {
+        let step = ExecutionStep {
+            step_number: self.steps.len(),
+            step_type,
+            content,
+            tool_call,
+            tool_observation,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+
+        if let Some(manager) = &self.manager {
+            let _ = manager
+                .send_thinking_update(
+                    "",
+                    step.step_number,
+                    &serde_json::to_string(&step).unwrap_or_default(),
+                )
+                .await;
+        }
+
+        self.steps.push(step);
+    }
+
+    fn into_steps(self) -> Vec<ExecutionStep> {
+        self.steps
+    }
+}
+
+/// Convert tools into LLM function definitions, keyed by tool ID
+pub fn to_llm_tools(tools: &[Box<dyn Tool>]) -> Vec<LLMTool> {
+    tools
+        .iter()
+        .map(|t| LLMTool {
+            name: t.id().to_string(),
+            description: t.description().to_string(),
+            parameters: crate::core::parameters_to_json_schema(t.parameters()),
+        })
+        .collect()
+}
+
+fn initial_messages(system_prompt: &str, context: &AgentContext, task: &str) -> Vec<LLMMessage> {
+    let mut messages = vec![LLMMessage {
+        role: "system".to_string(),
+        content: system_prompt.into(),
+        tool_calls: None,
+    }];
+
+    for msg in &context.messages {
+        messages.push(LLMMessage {
+            role: msg.role.clone(),
+            content: msg.content.clone().into(),
+            tool_calls: None,
+        });
+    }
+
+    messages.push(LLMMessage {
+        role: "user".to_string(),
+        content: task.into(),
+        tool_calls: None,
+    });
+
+    messages
+}
+
+/// Move a base64 screenshot out of an observation so it can be sent as an image,
+/// or drop it when the model can't be shown images
+fn take_screenshot_image(
+    mut observation: ToolObservation,
+    attach: bool,
+) -> (ToolObservation, Option<ContentPart>) {
+    let Some(data) = observation.data.as_mut().and_then(|d| d.as_object_mut()) else {
+        return (observation, None);
+    };
+
+    let mime_type = match data.get("format").and_then(|f| f.as_str()) {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        _ => "image/png",
+    };
+    let image = match data.get("data_base64").and_then(|d| d.as_str()) {
+        Some(encoded) if looks_like_base64(encoded) => {
+            ContentPart::image_base64(mime_type, encoded)
+        }
+        _ => return (observation, None),
+    };
+
+    if !attach {
+        data.insert(
+            "data_base64".to_string(),
+            serde_json::json!("[screenshot omitted; this model cannot view images]"),
+        );
+        return (observation, None);
+    }
+    data.insert(
+        "data_base64".to_string(),
+        serde_json::json!("[attached to the next message as an image]"),
+    );
+    (observation, Some(image))
+}
+
+fn looks_like_base64(data: &str) -> bool {
+    !data.is_empty()
+        && data.len().is_multiple_of(4)
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'=')
+}
+
+/// Run one tool call the way agents do, reporting failures as observations
+pub(crate) async fn execute_tool(
+    agent_id: &str,
+    name: &str,
+    arguments: &serde_json::Value,
+    available_tools: &[Box<dyn Tool>],
+    conversation_manager: &Option<Arc<dyn ConversationManager>>,
+    execution_state: &Arc<tokio::sync::RwLock<ToolExecutionState>>,
+) -> ToolObservation {
+    let Some(tool) = available_tools
+        .iter()
+        .find(|t| t.id() == name || t.name() == name)
+    else {
+        return ToolObservation {
+            success: false,
+            message: format!("Tool not found: {}", name),
+            data: None,
+            error: Some("Tool not found".to_string()),
+        };
+    };
+
+    execution_state
+        .write()
+        .await
+        .executed_tools
+        .push(tool.id().to_string());
+
+    let tool_context = crate::core::ToolContext {
+        agent_id: agent_id.to_string(),
+        conversation_manager: conversation_manager.clone(),
+        execution_state: execution_state.clone(),
+    };
+
+    match tool.execute(arguments, &tool_context).await {
+        Ok(result) => ToolObservation {
+            success: result.success,
+            message: result.message,
+            data: result.data,
+            error: None,
+        },
+        Err(e) => ToolObservation {
+            success: false,
+            message: format!("Tool execution failed: {}", e),
+            data: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screenshot_observation(data_base64: &str) -> ToolObservation {
+        ToolObservation {
+            success: true,
+            message: "Successfully captured full screen screenshot".to_string(),
+            data: Some(serde_json::json!({"data_base64": data_base64, "format": "jpg"})),
+            error: None,
+        }
+    }
+
+    /// Base64 of a real encoded 2x2 JPEG, as the screenshot tool returns it
+    fn real_jpeg_base64() -> String {
+        use base64::Engine;
+        let pixels = image::RgbaImage::from_pixel(2, 2, image::Rgba([10, 120, 240, 255]));
+        let bytes =
+            crate::tools::desktop_automation::screen::screenshot::encode(pixels, "jpg").unwrap();
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    #[test]
+    fn test_screenshot_is_moved_into_image_part() {
+        let encoded = real_jpeg_base64();
+        let (observation, image) = take_screenshot_image(screenshot_observation(&encoded), true);
+
+        assert_eq!(
+            image,
+            Some(ContentPart::image_base64("image/jpeg", &encoded))
+        );
+        let data = observation.data.unwrap();
+        assert_eq!(
+            data["data_base64"],
+            "[attached to the next message as an image]"
+        );
+    }
+
+    #[test]
+    fn test_screenshot_is_dropped_for_text_models() {
+        let (observation, image) =
+            take_screenshot_image(screenshot_observation(&real_jpeg_base64()), false);
+
+        assert!(image.is_none());
+        let data = observation.data.unwrap();
+        assert!(data["data_base64"].as_str().unwrap().contains("omitted"));
+    }
+
+    #[test]
+    fn test_non_image_data_is_left_alone() {
+        let (observation, image) =
+            take_screenshot_image(screenshot_observation("placeholder_data"), true);
+        assert!(image.is_none());
+        assert_eq!(observation.data.unwrap()["data_base64"], "placeholder_data");
+    }
+}
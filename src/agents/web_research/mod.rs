@@ -3,5 +3,3 @@
 //! Specializes in web search and content extraction for research tasks.
 
 pub mod agent;
-
-pub use agent::WebResearchAgent;
@@ -0,0 +1,390 @@
+//! Bookkeeping for executions started through the HTTP API
+
+use crate::agents::conversation::{ConversationManager, ProgressType};
+use crate::core::{AgentResult, ExecutionStep, StepType};
+use crate::llm::usage::ExecutionUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::task::AbortHandle;
+
+/// Lifecycle state of an execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Everything known about one execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub agent_id: String,
+    pub session_id: String,
+    pub message: String,
+    pub status: ExecutionStatus,
+    pub started_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// Steps reported so far; replaced by the agent's full list on completion
+    pub steps: Vec<ExecutionStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_output: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ExecutionUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Finished executions kept in memory unless told otherwise
+pub const DEFAULT_FINISHED_RETAINED: usize = 200;
+
+/// Shared store of API executions and the tasks running them
+#[derive(Debug, Clone)]
+pub struct ExecutionStore {
+    next_id: Arc<AtomicU64>,
+    records: Arc<Mutex<HashMap<String, ExecutionRecord>>>,
+    tasks: Arc<Mutex<HashMap<String, AbortHandle>>>,
+    /// IDs of finished executions, oldest first
+    finished: Arc<Mutex<VecDeque<String>>>,
+    max_finished: usize,
+}
+
+impl Default for ExecutionStore {
+    fn default() -> Self {
+        Self {
+            next_id: Arc::default(),
+            records: Arc::default(),
+            tasks: Arc::default(),
+            finished: Arc::default(),
+            max_finished: DEFAULT_FINISHED_RETAINED,
+        }
+    }
+}
+
+impl ExecutionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep only the last `max_finished` finished executions; running ones are never dropped
+    pub fn with_max_finished(mut self, max_finished: usize) -> Self {
+        self.max_finished = max_finished;
+        self
+    }
+
+    /// Register a new running execution and return its record
+    pub fn create(&self, agent_id: &str, session_id: &str, message: &str) -> ExecutionRecord {
+        // The timestamp keeps IDs unique across processes sharing a session log
+        let id = format!(
+            "run-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let record = ExecutionRecord {
+            id: id.clone(),
+            agent_id: agent_id.to_string(),
+            session_id: session_id.to_string(),
+            message: message.to_string(),
+            status: ExecutionStatus::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            steps: Vec::new(),
+            response: None,
+            structured_output: None,
+            usage: None,
+            error: None,
+        };
+        self.lock_records().insert(id, record.clone());
+        record
+    }
+
+    pub fn get(&self, id: &str) -> Option<ExecutionRecord> {
+        self.lock_records().get(id).cloned()
+    }
+
+    /// Executions of a session, oldest first
+    pub fn session(&self, session_id: &str) -> Vec<ExecutionRecord> {
+        let mut records: Vec<_> = self
+            .lock_records()
+            .values()
+            .filter(|r| r.session_id == session_id)
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        records
+    }
+
+    /// Remember the task running an execution so it can be cancelled
+    pub fn attach_task(&self, id: &str, task: AbortHandle) {
+        // The task may already be done, or cancelled before we got here; holding
+        // the records lock keeps a concurrent cancel from slipping in between
+        let records = self.lock_records();
+        match records.get(id).map(|r| r.status) {
+            Some(ExecutionStatus::Running) => {
+                self.lock_tasks().insert(id.to_string(), task);
+            }
+            Some(ExecutionStatus::Cancelled) => task.abort(),
+            _ => {}
+        }
+    }
+
+    pub fn push_step(&self, id: &str, step: ExecutionStep) {
+        if let Some(record) = self.lock_records().get_mut(id) {
+            record.steps.push(step);
+        }
+    }
+
+    /// Record the outcome of a finished execution, unless it was cancelled
+    pub fn finish(&self, id: &str, outcome: crate::core::Result<AgentResult>) {
+        self.lock_tasks().remove(id);
+        let mut records = self.lock_records();
+        let Some(record) = records.get_mut(id) else {
+            return;
+        };
+        if record.status != ExecutionStatus::Running {
+            return;
+        }
+
+        record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        match outcome {
+            Ok(result) => {
+                record.status = if result.success {
+                    ExecutionStatus::Completed
+                } else {
+                    ExecutionStatus::Failed
+                };
+                record.steps = result.steps;
+                record.response = Some(result.response);
+                record.structured_output = result.structured_output;
+                record.usage = Some(result.usage);
+            }
+            Err(e) => {
+                record.status = ExecutionStatus::Failed;
+                record.error = Some(e.to_string());
+            }
+        }
+        self.retire(&mut records, id);
+    }
+
+    /// Abort a running execution; returns false if it was not running
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut records = self.lock_records();
+        let Some(record) = records.get_mut(id) else {
+            return false;
+        };
+        if record.status != ExecutionStatus::Running {
+            return false;
+        }
+
+        record.status = ExecutionStatus::Cancelled;
+        record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        if let Some(task) = self.lock_tasks().remove(id) {
+            task.abort();
+        }
+        self.retire(&mut records, id);
+        true
+    }
+
+    /// Note that `id` finished, dropping the oldest finished records over the cap
+    fn retire(&self, records: &mut HashMap<String, ExecutionRecord>, id: &str) {
+        let mut finished = self.finished.lock().unwrap_or_else(|e| e.into_inner());
+        finished.push_back(id.to_string());
+        while finished.len() > self.max_finished {
+            if let Some(oldest) = finished.pop_front() {
+                records.remove(&oldest);
+            }
+        }
+    }
+
+    fn lock_records(&self) -> std::sync::MutexGuard<'_, HashMap<String, ExecutionRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_tasks(&self) -> std::sync::MutexGuard<'_, HashMap<String, AbortHandle>> {
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Conversation manager that records streamed steps into the store
+#[derive(Debug)]
+pub struct StepRecorder {
+    store: ExecutionStore,
+    execution_id: String,
+}
+
+impl StepRecorder {
+    pub fn new(store: ExecutionStore, execution_id: impl Into<String>) -> Self {
+        Self {
+            store,
+            execution_id: execution_id.into(),
+        }
+    }
+
+    fn record(&self, step_number: usize, step_type: StepType, content: &str) {
+        self.store.push_step(
+            &self.execution_id,
+            ExecutionStep {
+                step_number,
+                step_type,
+                content: content.to_string(),
+                tool_call: None,
+                tool_observation: None,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationManager for StepRecorder {
+    async fn send_thinking_update(
+        &self,
+        _agent_id: &str,
+        step_number: usize,
+        thought: &str,
+    ) -> crate::core::Result<()> {
+        // The thinking engine streams whole steps as JSON
+        match serde_json::from_str::<ExecutionStep>(thought) {
+            Ok(step) => self.store.push_step(&self.execution_id, step),
+            Err(_) => self.record(step_number, StepType::Thinking, thought),
+        }
+        Ok(())
+    }
+
+    async fn send_progress_update(
+        &self,
+        _agent_id: &str,
+        progress_type: ProgressType,
+        message: &str,
+        _progress: Option<f32>,
+    ) -> crate::core::Result<()> {
+        let step_type = match progress_type {
+            ProgressType::Thinking => StepType::Thinking,
+            ProgressType::Planning => StepType::Planning,
+            ProgressType::Executing => StepType::Action,
+            ProgressType::Observing => StepType::Observation,
+            ProgressType::Reflecting => StepType::Reflection,
+            ProgressType::Completing => StepType::Completion,
+        };
+        self.record(0, step_type, message);
+        Ok(())
+    }
+
+    async fn send_error_update(
+        &self,
+        _agent_id: &str,
+        error: &str,
+        _recovery_suggestions: Vec<String>,
+    ) -> crate::core::Result<()> {
+        self.record(0, StepType::Reflection, error);
+        Ok(())
+    }
+
+    async fn send_completion_update(
+        &self,
+        _agent_id: &str,
+        _final_response: &str,
+        _success: bool,
+    ) -> crate::core::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AppError;
+
+    #[test]
+    fn test_finish_and_session_history() {
+        let store = ExecutionStore::new();
+        let first = store.create("agent", "s1", "one");
+        let second = store.create("agent", "s1", "two");
+        store.create("agent", "s2", "other");
+
+        store.finish(&first.id, Err(AppError::Agent("boom".to_string())));
+        let failed = store.get(&first.id).unwrap();
+        assert_eq!(failed.status, ExecutionStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("Agent error: boom"));
+
+        let history = store.session("s1");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].id, second.id);
+    }
+
+    #[test]
+    fn test_cancel_only_running() {
+        let store = ExecutionStore::new();
+        let record = store.create("agent", "s1", "task");
+
+        assert!(store.cancel(&record.id));
+        assert_eq!(
+            store.get(&record.id).unwrap().status,
+            ExecutionStatus::Cancelled
+        );
+        assert!(!store.cancel(&record.id));
+        assert!(!store.cancel("run-missing"));
+
+        // A late result must not overwrite the cancellation
+        store.finish(&record.id, Err(AppError::Agent("late".to_string())));
+        assert_eq!(
+            store.get(&record.id).unwrap().status,
+            ExecutionStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn test_evicts_oldest_finished_executions() {
+        let store = ExecutionStore::new().with_max_finished(2);
+        let running = store.create("agent", "s1", "still going");
+        let runs: Vec<_> = (0..3)
+            .map(|i| store.create("agent", "s1", &format!("run {}", i)))
+            .collect();
+
+        store.finish(&runs[0].id, Err(AppError::Agent("one".to_string())));
+        store.cancel(&runs[1].id);
+        assert!(store.get(&runs[0].id).is_some());
+
+        store.finish(&runs[2].id, Err(AppError::Agent("three".to_string())));
+        assert!(store.get(&runs[0].id).is_none());
+        assert!(store.get(&runs[1].id).is_some());
+        assert!(store.get(&runs[2].id).is_some());
+        assert!(store.get(&running.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_recorder_parses_streamed_steps() {
+        let store = ExecutionStore::new();
+        let record = store.create("agent", "s1", "task");
+        let recorder = StepRecorder::new(store.clone(), &record.id);
+
+        let step = ExecutionStep {
+            step_number: 3,
+            step_type: StepType::Action,
+            content: "clicking".to_string(),
+            tool_call: None,
+            tool_observation: None,
+            timestamp: String::new(),
+        };
+        recorder
+            .send_thinking_update("", 3, &serde_json::to_string(&step).unwrap())
+            .await
+            .unwrap();
+        recorder
+            .send_thinking_update("", 4, "plain thought")
+            .await
+            .unwrap();
+
+        let steps = store.get(&record.id).unwrap().steps;
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0].step_type, StepType::Action));
+        assert_eq!(steps[1].content, "plain thought");
+    }
+}
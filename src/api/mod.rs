@@ -0,0 +1,12 @@
+//! Local HTTP API
+//!
+//! A REST alternative to the WebSocket protocol for scripts and other services,
+//! backed by the same registry and agent engine.
+
+pub mod executions;
+pub mod server;
+pub mod sessions;
+
+pub use executions::{ExecutionRecord, ExecutionStatus, ExecutionStore, StepRecorder};
+pub use server::{ApiServer, DEFAULT_API_ADDR};
+pub use sessions::{SessionLog, SessionSummary};
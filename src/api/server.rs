@@ -0,0 +1,441 @@
+//! Routes of the embedded HTTP API
+
+use crate::api::executions::{ExecutionRecord, ExecutionStatus, ExecutionStore, StepRecorder};
+use crate::api::sessions::SessionLog;
+use crate::core::{AppError, SamplingParams};
+use crate::registry::{CentralRegistry, DependencyGraph};
+use crate::websocket::auth::RelayAuth;
+use crate::websocket::handler::{
+    ChatExecution, MessageHandler, DEFAULT_AGENT_ID, DEFAULT_SESSION_ID,
+};
+use axum::extract::{Path, Request, State};
+use axum::http::{header::AUTHORIZATION, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Address the API binds to unless told otherwise
+pub const DEFAULT_API_ADDR: &str = "127.0.0.1:8789";
+
+#[derive(Clone)]
+struct ApiState {
+    handler: MessageHandler,
+    executions: ExecutionStore,
+    sessions: Option<SessionLog>,
+}
+
+impl ApiState {
+    /// Persist a finished execution, if a session log is configured
+    fn persist(&self, id: &str) {
+        let (Some(log), Some(record)) = (&self.sessions, self.executions.get(id)) else {
+            return;
+        };
+        if let Err(e) = log.append(&record) {
+            eprintln!("Failed to save execution {}: {}", id, e);
+        }
+    }
+}
+
+/// Body of `POST /api/executions`
+#[derive(Debug, Deserialize)]
+struct StartExecution {
+    message: String,
+    #[serde(default)]
+    agent_id: Option<String>,
+    /// Tool IDs; defaults to the agent's own tool dependencies
+    #[serde(default)]
+    tools: Option<Vec<String>>,
+    #[serde(default)]
+    sampling: Option<SamplingParams>,
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    bypass_cache: bool,
+    /// Block until the execution finishes instead of returning immediately
+    #[serde(default)]
+    wait: bool,
+}
+
+/// JSON error body with a matching status code
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(what: &str, id: &str) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("{} not found: {}", what, id))
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        let status = match error {
+            AppError::Configuration(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// HTTP API for running agents without a WebSocket
+pub struct ApiServer {
+    addr: String,
+    state: ApiState,
+}
+
+impl ApiServer {
+    pub fn new(
+        addr: impl Into<String>,
+        registry: Arc<CentralRegistry>,
+        llm: Arc<dyn crate::core::LLMClient>,
+    ) -> Self {
+        Self {
+            addr: addr.into(),
+            state: ApiState {
+                handler: MessageHandler::new(registry, llm),
+                executions: ExecutionStore::new(),
+                sessions: None,
+            },
+        }
+    }
+
+    /// Require one of the viewer tokens as a bearer token on every request
+    pub fn with_auth(mut self, auth: RelayAuth) -> Self {
+        self.state.handler = self.state.handler.with_auth(auth);
+        self
+    }
+
+    /// Save finished executions so they outlive the process
+    pub fn with_session_log(mut self, sessions: SessionLog) -> Self {
+        self.state.sessions = Some(sessions);
+        self
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/api/agents", get(list_agents))
+            .route("/api/tools", get(list_tools))
+            .route("/api/dependencies", get(get_dependencies))
+            .route("/api/executions", post(start_execution))
+            .route("/api/executions/:id", get(get_execution))
+            .route("/api/executions/:id/steps", get(get_steps))
+            .route("/api/executions/:id/cancel", post(cancel_execution))
+            .route("/api/sessions/:id", get(get_session))
+            .layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                require_token,
+            ))
+            .with_state(self.state.clone())
+    }
+
+    /// Bind the configured address and serve until the listener fails
+    pub async fn run(&self) -> crate::core::Result<()> {
+        let listener = TcpListener::bind(&self.addr)
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to bind {}: {}", self.addr, e)))?;
+        self.serve(listener).await
+    }
+
+    /// Serve requests on an already bound listener
+    pub async fn serve(&self, listener: TcpListener) -> crate::core::Result<()> {
+        let local = listener.local_addr()?;
+        println!("HTTP API listening on http://{}/api", local);
+        if !local.ip().is_loopback() && !self.state.handler.auth().requires_viewer_token() {
+            eprintln!(
+                "Warning: serving the API on {} without tokens; anyone on the network can run agents",
+                local
+            );
+        }
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+}
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let auth = state.handler.auth();
+    if !auth.requires_viewer_token() {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if auth.accepts_viewer_token(token) => next.run(request).await,
+        _ => ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token")
+            .into_response(),
+    }
+}
+
+async fn list_agents(State(state): State<ApiState>) -> ApiResult<Json<Value>> {
+    let registry = state.handler.registry();
+    let mut agents = Vec::new();
+    for agent in registry.agents.list().await? {
+        agents.push(json!({
+            "id": agent.id(),
+            "name": agent.name(),
+            "description": agent.description(),
+            "version": agent.version(),
+            "capabilities": agent.capabilities(),
+            "tools": agent.tool_dependencies(),
+            "availability": registry.agents.availability(agent.id()).await?,
+        }));
+    }
+    Ok(Json(Value::Array(agents)))
+}
+
+async fn get_dependencies(State(state): State<ApiState>) -> ApiResult<Json<DependencyGraph>> {
+    Ok(Json(state.handler.registry().dependency_graph().await?))
+}
+
+async fn list_tools(State(state): State<ApiState>) -> ApiResult<Json<Value>> {
+    let registry = state.handler.registry();
+    let mut tools = Vec::new();
+    for tool in registry.tools.list().await? {
+        tools.push(json!({
+            "id": tool.id(),
+            "name": tool.name(),
+            "description": tool.description(),
+            "parameters": tool.parameters(),
+            "enabled": registry.tools.is_enabled(tool.id()).await? == Some(true),
+            "config": tool.config(),
+        }));
+    }
+    Ok(Json(Value::Array(tools)))
+}
+
+async fn start_execution(
+    State(state): State<ApiState>,
+    Json(body): Json<StartExecution>,
+) -> ApiResult<(StatusCode, Json<ExecutionRecord>)> {
+    let agent_id = body
+        .agent_id
+        .unwrap_or_else(|| DEFAULT_AGENT_ID.to_string());
+    let agent = state
+        .handler
+        .registry()
+        .agents
+        .get(&agent_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Agent", &agent_id))?;
+    if let Some(sampling) = &body.sampling {
+        sampling.validate()?;
+    }
+
+    let chat = ChatExecution {
+        tools: body
+            .tools
+            .unwrap_or_else(|| agent.tool_dependencies().to_vec()),
+        agent_id,
+        message: body.message,
+        sampling: body.sampling,
+        bypass_cache: body.bypass_cache,
+        session_id: body
+            .session_id
+            .unwrap_or_else(|| DEFAULT_SESSION_ID.to_string()),
+        history: Vec::new(),
+    };
+    let record = state
+        .executions
+        .create(&chat.agent_id, &chat.session_id, &chat.message);
+
+    let id = record.id.clone();
+    let task_state = state.clone();
+    let task = tokio::spawn(async move {
+        let recorder = Arc::new(StepRecorder::new(task_state.executions.clone(), &id));
+        let outcome = task_state
+            .handler
+            .run_chat(&chat, recorder)
+            .await
+            .map(|(result, _)| result);
+        task_state.executions.finish(&id, outcome);
+        task_state.persist(&id);
+    });
+    state
+        .executions
+        .attach_task(&record.id, task.abort_handle());
+
+    if !body.wait {
+        return Ok((StatusCode::ACCEPTED, Json(record)));
+    }
+
+    // A cancel request aborts the task; the record already says so
+    let _ = task.await;
+    let finished = state
+        .executions
+        .get(&record.id)
+        .ok_or_else(|| ApiError::not_found("Execution", &record.id))?;
+    Ok((StatusCode::OK, Json(finished)))
+}
+
+async fn get_execution(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<ExecutionRecord>> {
+    state
+        .executions
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Execution", &id))
+}
+
+async fn get_steps(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let record = state
+        .executions
+        .get(&id)
+        .ok_or_else(|| ApiError::not_found("Execution", &id))?;
+    Ok(Json(json!({
+        "id": record.id,
+        "status": record.status,
+        "steps": record.steps,
+    })))
+}
+
+async fn cancel_execution(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<ExecutionRecord>> {
+    let record = state
+        .executions
+        .get(&id)
+        .ok_or_else(|| ApiError::not_found("Execution", &id))?;
+    if !state.executions.cancel(&id) {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Execution {} is already {:?}", id, record.status).to_lowercase(),
+        ));
+    }
+    state.persist(&id);
+    state
+        .executions
+        .get(&id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Execution", &id))
+}
+
+async fn get_session(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let mut executions = state.executions.session(&id);
+    if let Some(log) = &state.sessions {
+        // Saved history first, then whatever is still running in this process
+        let mut saved = log.load(&id)?;
+        saved.extend(
+            executions
+                .into_iter()
+                .filter(|r| r.status == ExecutionStatus::Running),
+        );
+        executions = saved;
+    }
+    Ok(Json(json!({
+        "session_id": id,
+        "executions": executions,
+        "usage": state.handler.session_usage(&id).await.unwrap_or_default(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::ConfigAgent;
+    use crate::llm::mock::MockLLMClient;
+    use std::net::SocketAddr;
+
+    async fn start_api(llm: MockLLMClient) -> SocketAddr {
+        let registry = CentralRegistry::new();
+        registry
+            .agents
+            .register(Box::new(ConfigAgent::conversational()))
+            .await
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = ApiServer::new(addr.to_string(), Arc::new(registry), Arc::new(llm)).with_auth(
+            RelayAuth {
+                allow_anonymous_viewers: true,
+                ..Default::default()
+            },
+        );
+        tokio::spawn(async move { server.serve(listener).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_sync_execution_and_session_history() {
+        let mut llm = MockLLMClient::new();
+        llm.add_response("Hello from the API".to_string());
+        let addr = start_api(llm).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("http://{}/api/executions", addr))
+            .json(&json!({"message": "hi", "session_id": "s1", "wait": true}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let record: Value = response.json().await.unwrap();
+        assert_eq!(record["status"], "completed");
+        assert_eq!(record["response"], "Hello from the API");
+
+        let session: Value = client
+            .get(format!("http://{}/api/sessions/s1", addr))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(session["executions"].as_array().unwrap().len(), 1);
+        assert_eq!(session["usage"]["llm_calls"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_agent_and_execution() {
+        let addr = start_api(MockLLMClient::new()).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("http://{}/api/executions", addr))
+            .json(&json!({"message": "hi", "agent_id": "nope"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = client
+            .get(format!("http://{}/api/executions/run-404", addr))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,235 @@
+//! On-disk history of finished executions, grouped by session
+
+use crate::api::executions::ExecutionRecord;
+use crate::core::{AppError, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Directory holding one JSON Lines file per session
+pub const SESSIONS_DIR_ENV: &str = "CF_AI_SESSIONS_DIR";
+
+/// Short description of a stored session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub executions: usize,
+    pub last_message: String,
+    pub updated_at: String,
+}
+
+/// Append-only store of execution records
+#[derive(Debug, Clone)]
+pub struct SessionLog {
+    directory: PathBuf,
+    /// Serialises appends from the tasks sharing this log
+    writing: Arc<Mutex<()>>,
+}
+
+impl Default for SessionLog {
+    fn default() -> Self {
+        Self::new(
+            dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cf_ai_local_tools")
+                .join("sessions"),
+        )
+    }
+}
+
+impl SessionLog {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            writing: Arc::default(),
+        }
+    }
+
+    /// Log in `CF_AI_SESSIONS_DIR`, or the user's data directory
+    pub fn from_env() -> Self {
+        match std::env::var(SESSIONS_DIR_ENV) {
+            Ok(directory) if !directory.trim().is_empty() => Self::new(directory),
+            _ => Self::default(),
+        }
+    }
+
+    pub fn directory(&self) -> &PathBuf {
+        &self.directory
+    }
+
+    /// Add a record to the end of its session as one line, leaving earlier records untouched
+    pub fn append(&self, record: &ExecutionRecord) -> Result<()> {
+        let path = self.session_path(&record.session_id)?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Records of a session, oldest first; empty if it doesn't exist
+    pub fn load(&self, session_id: &str) -> Result<Vec<ExecutionRecord>> {
+        let path = self.session_path(session_id)?;
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        // A line cut short by a crash is skipped rather than losing the whole session
+        let mut records = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => eprintln!(
+                    "Skipping unreadable record at {}:{}: {}",
+                    path.display(),
+                    number + 1,
+                    e
+                ),
+            }
+        }
+        Ok(records)
+    }
+
+    /// All stored sessions, most recently updated first
+    pub fn list(&self) -> Result<Vec<SessionSummary>> {
+        let entries = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut sessions = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(session_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let records = match self.load(session_id) {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("Skipping unreadable session {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if let Some(last) = records.last() {
+                sessions.push(SessionSummary {
+                    session_id: session_id.to_string(),
+                    executions: records.len(),
+                    last_message: last.message.clone(),
+                    updated_at: last
+                        .finished_at
+                        .clone()
+                        .unwrap_or_else(|| last.started_at.clone()),
+                });
+            }
+        }
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(sessions)
+    }
+
+    fn session_path(&self, session_id: &str) -> Result<PathBuf> {
+        let valid = !session_id.is_empty()
+            && session_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(AppError::Configuration(format!(
+                "Invalid session ID '{}': use letters, digits, '-' and '_'",
+                session_id
+            )));
+        }
+        Ok(self.directory.join(format!("{}.jsonl", session_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::executions::ExecutionStore;
+
+    #[test]
+    fn test_append_load_and_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::new(dir.path());
+        let store = ExecutionStore::new();
+        log.append(&store.create("agent", "s1", "first")).unwrap();
+        log.append(&store.create("agent", "s1", "second")).unwrap();
+        log.append(&store.create("agent", "s2", "other")).unwrap();
+
+        let s1 = log.load("s1").unwrap();
+        assert_eq!(s1.len(), 2);
+        assert_eq!(s1[1].message, "second");
+        assert!(log.load("missing").unwrap().is_empty());
+
+        let sessions = log.list().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let s1 = sessions.iter().find(|s| s.session_id == "s1").unwrap();
+        assert_eq!(s1.executions, 2);
+        assert_eq!(s1.last_message, "second");
+    }
+
+    #[test]
+    fn test_concurrent_appends_keep_every_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::new(dir.path());
+        let store = ExecutionStore::new();
+
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let log = log.clone();
+                let record = store.create("agent", "shared", &format!("message {}", i));
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        log.append(&record).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(log.load("shared").unwrap().len(), 80);
+    }
+
+    #[test]
+    fn test_skips_a_truncated_last_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::new(dir.path());
+        let store = ExecutionStore::new();
+        log.append(&store.create("agent", "s1", "kept")).unwrap();
+
+        let path = dir.path().join("s1.jsonl");
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("{\"id\": \"run-");
+        std::fs::write(&path, text).unwrap();
+
+        let records = log.load("s1").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message, "kept");
+    }
+
+    #[test]
+    fn test_rejects_path_like_session_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::new(dir.path());
+        assert!(log.load("../etc/passwd").is_err());
+        assert!(log.load("").is_err());
+        assert!(log.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,784 @@
+//! Command-line interface
+//!
+//! `serve` keeps the long-running relay behaviour; the other subcommands run
+//! agents and tools headlessly against the same registry.
+
+pub mod output;
+pub mod repl;
+
+use crate::agents::conversation::ConversationManager;
+use crate::agents::registry::Availability;
+use crate::agents::AgentDefinition;
+use crate::api::{ApiServer, ExecutionStatus, ExecutionStore, SessionLog, StepRecorder};
+use crate::core::{AppError, LLMClient, Result, Tool, ToolContext};
+use crate::mcp::{McpConfig, McpServer};
+use crate::registry::{CentralRegistry, ComponentLoader};
+use crate::websocket::handler::{ChatExecution, DEFAULT_AGENT_ID, DEFAULT_SESSION_ID};
+use crate::websocket::{
+    MessageHandler, RelayAuth, WebSocketRelayClient, WebSocketServer, DEFAULT_SERVER_ADDR,
+};
+use clap::{Args, Parser, Subcommand};
+use output::StepPrinter;
+use repl::Repl;
+use serde_json::json;
+use std::sync::Arc;
+
+/// Relay endpoint the desktop connects to by default
+pub const DEFAULT_RELAY_URL: &str = "ws://localhost:8787/connect?device=desktop";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "cf_ai_local_tools",
+    version,
+    about = "Run local agents and tools"
+)]
+pub struct Cli {
+    /// Print JSON instead of human-readable text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Defaults to `serve`
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Connect to the relay, or serve viewers locally, and wait for commands
+    Serve(ServeArgs),
+    /// Run one task and print its steps as they happen
+    Run(RunArgs),
+    /// Chat with an agent interactively in the terminal
+    Chat(ChatArgs),
+    /// Inspect registered agents
+    #[command(subcommand)]
+    Agents(AgentsCommand),
+    /// Inspect and call registered tools
+    #[command(subcommand)]
+    Tools(ToolsCommand),
+    /// Browse saved execution history
+    #[command(subcommand)]
+    Sessions(SessionsCommand),
+    /// Inspect tool plugins on disk
+    #[command(subcommand)]
+    Plugins(PluginsCommand),
+    /// Work with MCP servers
+    #[command(subcommand)]
+    Mcp(McpCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Relay WebSocket URL
+    #[arg(long, env = "CF_AI_RELAY_URL", default_value = DEFAULT_RELAY_URL)]
+    pub relay_url: String,
+
+    /// Host the WebSocket endpoint on ADDR instead of dialing the relay
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = DEFAULT_SERVER_ADDR)]
+    pub local: Option<String>,
+
+    /// Also serve the HTTP API on ADDR
+    #[arg(long, value_name = "ADDR", env = "CF_AI_API_ADDR")]
+    pub api: Option<String>,
+
+    /// Run commands from viewers that send no token when no viewer tokens are configured
+    /// (relay only; `--local` always requires tokens)
+    #[arg(long)]
+    pub allow_anonymous_viewers: bool,
+}
+
+impl ServeArgs {
+    /// Settings used when no subcommand is given
+    pub fn from_env() -> Self {
+        Self {
+            relay_url: std::env::var("CF_AI_RELAY_URL")
+                .unwrap_or_else(|_| DEFAULT_RELAY_URL.to_string()),
+            local: None,
+            api: std::env::var("CF_AI_API_ADDR").ok(),
+            allow_anonymous_viewers: false,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Agent to run
+    #[arg(long, short, default_value = DEFAULT_AGENT_ID)]
+    pub agent: String,
+
+    /// Tool to offer the agent (repeatable); defaults to its own dependencies
+    #[arg(long = "tool", value_name = "ID")]
+    pub tools: Vec<String>,
+
+    /// Session the run is saved under
+    #[arg(long, default_value = DEFAULT_SESSION_ID)]
+    pub session: String,
+
+    /// Don't save the run to the session history
+    #[arg(long)]
+    pub no_save: bool,
+
+    /// Skip the LLM response cache
+    #[arg(long)]
+    pub bypass_cache: bool,
+
+    /// What the agent should do
+    #[arg(required = true, num_args = 1..)]
+    pub task: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ChatArgs {
+    /// Agent to start with; `/agent` switches later
+    #[arg(long, short, default_value = DEFAULT_AGENT_ID)]
+    pub agent: String,
+
+    /// Session the turns are saved under
+    #[arg(long, default_value = DEFAULT_SESSION_ID)]
+    pub session: String,
+
+    /// Don't save the turns to the session history
+    #[arg(long)]
+    pub no_save: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AgentsCommand {
+    /// List registered agents
+    List,
+    /// Check an agent definition file and show what it defines
+    Validate {
+        /// Path to a `.toml` or `.json` definition
+        path: std::path::PathBuf,
+    },
+    /// Show the tools agents, tools and presets depend on, and what is missing
+    Deps {
+        /// Only show this component and what depends on it
+        id: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ToolsCommand {
+    /// List registered tools
+    List,
+    /// Call a tool directly
+    Call {
+        /// Tool ID
+        id: String,
+        /// Arguments as a JSON object
+        #[arg(long, default_value = "{}")]
+        args: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionsCommand {
+    /// List saved sessions, most recent first
+    List,
+    /// Show every execution of a session
+    Show {
+        /// Session ID
+        id: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PluginsCommand {
+    /// List plugins in the plugins directory and any that failed to load
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum McpCommand {
+    /// List the configured MCP servers and the tools imported from them
+    List,
+    /// Serve the registered tools to an MCP client over stdio
+    Serve {
+        /// Only offer this tool (repeatable); all tools by default
+        #[arg(long = "tool")]
+        tools: Vec<String>,
+    },
+}
+
+impl Cli {
+    /// Run the selected subcommand
+    pub async fn execute(
+        self,
+        registry: Arc<CentralRegistry>,
+        llm: Arc<dyn LLMClient>,
+    ) -> Result<()> {
+        let json = self.json;
+        match self.command {
+            None => serve(ServeArgs::from_env(), registry, llm).await,
+            Some(Command::Serve(args)) => serve(args, registry, llm).await,
+            Some(Command::Run(args)) => run(args, json, registry, llm).await,
+            Some(Command::Chat(args)) => chat(args, registry, llm).await,
+            Some(Command::Agents(AgentsCommand::List)) => list_agents(json, &registry).await,
+            Some(Command::Agents(AgentsCommand::Validate { path })) => {
+                validate_agent(&path, json, &registry).await
+            }
+            Some(Command::Agents(AgentsCommand::Deps { id })) => {
+                show_dependencies(id.as_deref(), json, &registry).await
+            }
+            Some(Command::Tools(ToolsCommand::List)) => list_tools(json, &registry).await,
+            Some(Command::Tools(ToolsCommand::Call { id, args })) => {
+                call_tool(&id, &args, json, &registry).await
+            }
+            Some(Command::Sessions(command)) => sessions(command, json),
+            Some(Command::Plugins(PluginsCommand::List)) => list_plugins(json),
+            Some(Command::Mcp(McpCommand::List)) => list_mcp_servers(json, &registry).await,
+            Some(Command::Mcp(McpCommand::Serve { tools })) => {
+                McpServer::from_registry(&registry, &tools)
+                    .await?
+                    .serve_stdio()
+                    .await
+            }
+        }
+    }
+}
+
+/// Rescan the plugins directory whenever the process gets SIGHUP
+#[cfg(unix)]
+async fn rescan_plugins_on_hangup(registry: Arc<CentralRegistry>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let Ok(mut hangups) = signal(SignalKind::hangup()) else {
+        return;
+    };
+    while hangups.recv().await.is_some() {
+        match registry.rescan_plugins().await {
+            Ok(scan) => {
+                for (path, error) in &scan.errors {
+                    eprintln!("⚠️  Skipping plugin {}: {}", path.display(), error);
+                }
+                println!(
+                    "🔌 Rescanned plugins: {} loaded, {} removed",
+                    scan.loaded.len(),
+                    scan.removed.len()
+                );
+            }
+            Err(e) => eprintln!("❌ Plugin rescan failed: {}", e),
+        }
+    }
+}
+
+async fn serve(
+    args: ServeArgs,
+    registry: Arc<CentralRegistry>,
+    llm: Arc<dyn LLMClient>,
+) -> Result<()> {
+    let mut auth = RelayAuth::from_env()?;
+    auth.allow_anonymous_viewers |= args.allow_anonymous_viewers;
+    auth.check_viewer_tokens()?;
+    if auth.allow_anonymous_viewers && auth.viewer_tokens.is_empty() {
+        eprintln!("⚠️  Anonymous viewers allowed; commands from any viewer will run");
+    }
+
+    #[cfg(unix)]
+    tokio::spawn(rescan_plugins_on_hangup(registry.clone()));
+
+    if let Some(api_addr) = args.api {
+        let api = ApiServer::new(api_addr, registry.clone(), llm.clone())
+            .with_auth(auth.clone())
+            .with_session_log(SessionLog::from_env());
+        tokio::spawn(async move {
+            if let Err(e) = api.run().await {
+                eprintln!("❌ HTTP API error: {}", e);
+            }
+        });
+    }
+
+    if let Some(addr) = args.local {
+        println!("🏠 Serving viewers locally on {}...", addr);
+        return WebSocketServer::new(addr, registry, llm)
+            .with_auth(auth)
+            .with_allowed_origins(WebSocketServer::allowed_origins_from_env())
+            .run()
+            .await;
+    }
+
+    if !auth.pinned_certs.is_empty() {
+        println!(
+            "🔒 Pinning {} relay certificate(s)",
+            auth.pinned_certs.len()
+        );
+    }
+    // Connects to the Worker relay as the 'desktop' device
+    println!("🌐 Connecting to relay at {}...", args.relay_url);
+    WebSocketRelayClient::new(args.relay_url, registry, llm)
+        .with_auth(auth)
+        .run()
+        .await
+}
+
+async fn run(
+    args: RunArgs,
+    json: bool,
+    registry: Arc<CentralRegistry>,
+    llm: Arc<dyn LLMClient>,
+) -> Result<()> {
+    let agent = registry
+        .agents
+        .get(&args.agent)
+        .await?
+        .ok_or_else(|| AppError::Registry(format!("Agent not found: {}", args.agent)))?;
+    let chat = ChatExecution {
+        tools: if args.tools.is_empty() {
+            agent.tool_dependencies().to_vec()
+        } else {
+            args.tools
+        },
+        agent_id: args.agent,
+        message: args.task.join(" "),
+        sampling: None,
+        bypass_cache: args.bypass_cache,
+        session_id: args.session,
+        history: Vec::new(),
+    };
+
+    let store = ExecutionStore::new();
+    let record = store.create(&chat.agent_id, &chat.session_id, &chat.message);
+    let printer = Arc::new(StepPrinter::new());
+    // JSON output is printed once at the end, so steps are only collected
+    let manager: Arc<dyn ConversationManager> = if json {
+        Arc::new(StepRecorder::new(store.clone(), &record.id))
+    } else {
+        printer.clone()
+    };
+
+    let outcome = MessageHandler::new(registry, llm)
+        .run_chat(&chat, manager)
+        .await
+        .map(|(result, _)| result);
+    if let Ok(result) = &outcome {
+        // Agents that don't stream still report their steps at the end
+        if !json && printer.printed() == 0 {
+            result.steps.iter().for_each(|step| printer.print(step));
+        }
+    }
+    store.finish(&record.id, outcome);
+    let record = store
+        .get(&record.id)
+        .ok_or_else(|| AppError::Agent(format!("Execution {} disappeared", record.id)))?;
+
+    if !args.no_save {
+        SessionLog::from_env().append(&record)?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&record)?);
+    } else {
+        if let Some(response) = &record.response {
+            println!("\n{}", response);
+        }
+        if let Some(usage) = &record.usage {
+            println!(
+                "\n{} LLM call(s), {} tokens, ~${:.4}",
+                usage.total.llm_calls, usage.total.total_tokens, usage.total.cost_usd
+            );
+        }
+    }
+
+    match record.status {
+        ExecutionStatus::Completed => Ok(()),
+        _ => Err(AppError::Agent(record.error.unwrap_or_else(|| {
+            "Agent did not complete the task".to_string()
+        }))),
+    }
+}
+
+async fn chat(
+    args: ChatArgs,
+    registry: Arc<CentralRegistry>,
+    llm: Arc<dyn LLMClient>,
+) -> Result<()> {
+    let mut repl = Repl::new(registry, llm, &args.agent, args.session).await?;
+    if !args.no_save {
+        repl = repl.with_session_log(SessionLog::from_env());
+    }
+    repl.run().await
+}
+
+async fn list_agents(json: bool, registry: &CentralRegistry) -> Result<()> {
+    let agents = registry.agents.list().await?;
+    if json {
+        let mut listed = Vec::new();
+        for agent in &agents {
+            listed.push(json!({
+                "id": agent.id(),
+                "name": agent.name(),
+                "description": agent.description(),
+                "version": agent.version(),
+                "capabilities": agent.capabilities(),
+                "tools": agent.tool_dependencies(),
+                "availability": registry.agents.availability(agent.id()).await?,
+            }));
+        }
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+
+    for agent in agents {
+        println!("{:<28} {}", agent.id(), agent.description());
+        if !agent.tool_dependencies().is_empty() {
+            println!("{:<28} tools: {}", "", agent.tool_dependencies().join(", "));
+        }
+        if let Some(Availability::Disabled { reason }) =
+            registry.agents.availability(agent.id()).await?
+        {
+            println!("{:<28} disabled: {}", "", reason);
+        }
+    }
+    Ok(())
+}
+
+async fn show_dependencies(id: Option<&str>, json: bool, registry: &CentralRegistry) -> Result<()> {
+    let mut graph = registry.dependency_graph().await?;
+    if let Some(id) = id {
+        graph.components.retain(|c| c.id == id);
+        graph.dependencies.retain(|d| d.id == id || d.tool == id);
+        if graph.components.is_empty() && graph.dependencies.is_empty() {
+            return Err(AppError::Registry(format!(
+                "Nothing named '{}' is registered",
+                id
+            )));
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&graph)?);
+        return Ok(());
+    }
+
+    for component in &graph.components {
+        let status = if component.available {
+            ""
+        } else {
+            "  (unavailable)"
+        };
+        println!("{:<8} {}{}", component.kind, component.id, status);
+        if !component.capabilities.is_empty() {
+            println!("{:<8} provides: {}", "", component.capabilities.join(", "));
+        }
+        for dependency in graph.dependencies_of(component.kind, &component.id) {
+            let mark = if dependency.satisfied {
+                ""
+            } else {
+                "  (missing)"
+            };
+            println!("{:<8} requires: {}{}", "", dependency.tool, mark);
+        }
+    }
+    if let Some(id) = id {
+        for dependent in graph.dependents_of(id) {
+            println!("required by {} {}", dependent.kind, dependent.id);
+        }
+    }
+    Ok(())
+}
+
+async fn validate_agent(
+    path: &std::path::Path,
+    json: bool,
+    registry: &CentralRegistry,
+) -> Result<()> {
+    let definition = AgentDefinition::load(path)?;
+    let mut missing = Vec::new();
+    for tool in &definition.tools {
+        if registry.tools.get(tool).await?.is_none() {
+            missing.push(tool.clone());
+        }
+    }
+
+    if json {
+        let report = json!({"definition": definition, "missing_tools": missing});
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("{} ({}) is valid", definition.id, definition.name);
+    println!("engine: {:?}", definition.engine);
+    println!("model:  {}", definition.reasoning.model_id);
+    println!("tools:  {}", definition.tools.join(", "));
+    if !missing.is_empty() {
+        eprintln!("warning: unknown tools: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+async fn list_tools(json: bool, registry: &CentralRegistry) -> Result<()> {
+    let tools = registry.tools.list().await?;
+    if json {
+        let mut listed = Vec::new();
+        for tool in &tools {
+            listed.push(json!({
+                "id": tool.id(),
+                "name": tool.name(),
+                "category": tool.category(),
+                "description": tool.description(),
+                "parameters": tool.parameters(),
+                "enabled": registry.tools.is_enabled(tool.id()).await? == Some(true),
+                "config": tool.config(),
+            }));
+        }
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(());
+    }
+
+    for tool in tools {
+        println!(
+            "{:<24} {:<12} {}",
+            tool.id(),
+            tool.category(),
+            tool.description()
+        );
+        if registry.tools.is_enabled(tool.id()).await? == Some(false) {
+            println!("{:<24} disabled", "");
+        }
+    }
+    Ok(())
+}
+
+async fn call_tool(id: &str, args: &str, json: bool, registry: &CentralRegistry) -> Result<()> {
+    let tool = registry
+        .tools
+        .get(id)
+        .await?
+        .ok_or_else(|| AppError::Registry(format!("Tool not found: {}", id)))?;
+    let args: serde_json::Value = serde_json::from_str(args)
+        .map_err(|e| AppError::Configuration(format!("--args must be a JSON object: {}", e)))?;
+    tool.validate_args(&args)?;
+
+    let context = ToolContext {
+        agent_id: "cli".to_string(),
+        conversation_manager: None,
+        execution_state: Default::default(),
+    };
+    let result = tool.execute(&args, &context).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("{}", result.message);
+        if let Some(data) = &result.data {
+            println!("{}", serde_json::to_string_pretty(data)?);
+        }
+    }
+
+    if result.success {
+        Ok(())
+    } else {
+        Err(AppError::Tool(format!("{} failed: {}", id, result.message)))
+    }
+}
+
+async fn list_mcp_servers(json: bool, registry: &CentralRegistry) -> Result<()> {
+    let path = McpConfig::default_path();
+    let config = McpConfig::load(&path)?;
+    let mut servers = Vec::new();
+    for server in &config.servers {
+        let tools = registry.tools.find_by_category(&server.category()).await?;
+        let ids: Vec<String> = tools.iter().map(|t| t.id().to_string()).collect();
+        servers.push((server, ids));
+    }
+
+    if json {
+        let servers: Vec<_> = servers
+            .iter()
+            .map(|(server, tools)| json!({"server": server, "tools": tools}))
+            .collect();
+        let report = json!({"config": path, "servers": servers});
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("MCP config: {}", path.display());
+    for (server, tools) in &servers {
+        let endpoint = server
+            .url
+            .clone()
+            .or_else(|| server.command.clone())
+            .unwrap_or_default();
+        println!("{:<20} {}", server.name, endpoint);
+        if tools.is_empty() {
+            println!("{:<20} no tools imported", "");
+        } else {
+            println!("{:<20} tools: {}", "", tools.join(", "));
+        }
+    }
+    if servers.is_empty() {
+        println!("No MCP servers configured.");
+    }
+    Ok(())
+}
+
+fn list_plugins(json: bool) -> Result<()> {
+    let loader = ComponentLoader::new();
+    let (plugins, errors) = loader.load_plugins()?;
+
+    if json {
+        let plugins: Vec<_> = plugins
+            .iter()
+            .map(|plugin| {
+                json!({
+                    "directory": plugin.directory(),
+                    "manifest": plugin.manifest(),
+                })
+            })
+            .collect();
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|(path, error)| json!({"directory": path, "error": error}))
+            .collect();
+        let report = json!({
+            "directory": loader.plugins_path(),
+            "plugins": plugins,
+            "errors": errors,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Plugins directory: {}", loader.plugins_path().display());
+    for plugin in &plugins {
+        println!(
+            "{:<24} {:<12} {}",
+            plugin.id(),
+            plugin.category(),
+            plugin.manifest().command
+        );
+    }
+    for (path, error) in &errors {
+        eprintln!("{}: {}", path.display(), error);
+    }
+    if plugins.is_empty() && errors.is_empty() {
+        println!("No plugins found.");
+    }
+    Ok(())
+}
+
+fn sessions(command: SessionsCommand, json: bool) -> Result<()> {
+    let log = SessionLog::from_env();
+    match command {
+        SessionsCommand::List => {
+            let sessions = log.list()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&sessions)?);
+                return Ok(());
+            }
+            if sessions.is_empty() {
+                println!("No saved sessions in {}", log.directory().display());
+            }
+            for session in sessions {
+                println!(
+                    "{:<20} {:>3} run(s)  {}  {}",
+                    session.session_id,
+                    session.executions,
+                    session.updated_at,
+                    session.last_message
+                );
+            }
+        }
+        SessionsCommand::Show { id } => {
+            let records = log.load(&id)?;
+            if records.is_empty() {
+                return Err(AppError::Registry(format!("Session not found: {}", id)));
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&records)?);
+                return Ok(());
+            }
+            for record in records {
+                println!(
+                    "── {} [{:?}] {} · {}",
+                    record.id, record.status, record.agent_id, record.started_at
+                );
+                println!("> {}", record.message);
+                for step in &record.steps {
+                    println!("{}", output::format_step(step));
+                }
+                if let Some(response) = &record.response {
+                    println!("{}", response);
+                }
+                if let Some(error) = &record.error {
+                    println!("error: {}", error);
+                }
+                println!();
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        let cli = Cli::try_parse_from([
+            "app",
+            "run",
+            "--agent",
+            "web-research-agent",
+            "--tool",
+            "web_search",
+            "find",
+            "rust",
+        ])
+        .unwrap();
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(args.agent, "web-research-agent");
+        assert_eq!(args.tools, vec!["web_search"]);
+        assert_eq!(args.task.join(" "), "find rust");
+        assert_eq!(args.session, DEFAULT_SESSION_ID);
+
+        let cli = Cli::try_parse_from(["app", "--json", "tools", "call", "echo", "--args", "{}"])
+            .unwrap();
+        assert!(cli.json);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tools(ToolsCommand::Call { .. }))
+        ));
+
+        let cli = Cli::try_parse_from(["app", "chat", "-a", "web-research-agent"]).unwrap();
+        let Some(Command::Chat(args)) = cli.command else {
+            panic!("expected chat");
+        };
+        assert_eq!(args.agent, "web-research-agent");
+
+        let cli = Cli::try_parse_from(["app", "serve", "--local"]).unwrap();
+        let Some(Command::Serve(args)) = cli.command else {
+            panic!("expected serve");
+        };
+        assert_eq!(args.local.as_deref(), Some(DEFAULT_SERVER_ADDR));
+
+        let cli = Cli::try_parse_from(["app", "agents", "validate", "triage.toml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Agents(AgentsCommand::Validate { .. }))
+        ));
+
+        let cli = Cli::try_parse_from(["app", "agents", "deps", "fetch_url"]).unwrap();
+        let Some(Command::Agents(AgentsCommand::Deps { id })) = cli.command else {
+            panic!("expected agents deps");
+        };
+        assert_eq!(id.as_deref(), Some("fetch_url"));
+
+        let cli = Cli::try_parse_from(["app", "mcp", "serve", "--tool", "web_search"]).unwrap();
+        let Some(Command::Mcp(McpCommand::Serve { tools })) = cli.command else {
+            panic!("expected mcp serve");
+        };
+        assert_eq!(tools, vec!["web_search"]);
+
+        assert!(Cli::try_parse_from(["app"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from(["app", "run"]).is_err());
+    }
+}
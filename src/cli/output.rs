@@ -0,0 +1,215 @@
+//! Terminal rendering of execution steps
+
+use crate::agents::conversation::{ConversationManager, ProgressType};
+use crate::core::{ExecutionStep, StepType};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Longest tool result printed before it is cut off
+const MAX_OBSERVATION_CHARS: usize = 400;
+
+pub const RESET: &str = "\x1b[0m";
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+pub const BLUE: &str = "\x1b[34m";
+pub const MAGENTA: &str = "\x1b[35m";
+pub const CYAN: &str = "\x1b[36m";
+
+/// Whether stdout should get ANSI colours (a terminal, and `NO_COLOR` unset)
+pub fn use_color() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+/// Wrap `text` in an ANSI colour when `color` is on
+pub fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", code, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Colour used for a step
+pub fn step_color(step: &ExecutionStep) -> &'static str {
+    match step.step_type {
+        StepType::Thinking => DIM,
+        StepType::Planning => BLUE,
+        StepType::Action => YELLOW,
+        StepType::Observation => match &step.tool_observation {
+            Some(observation) if !observation.success => RED,
+            _ => GREEN,
+        },
+        StepType::Reflection => MAGENTA,
+        StepType::Completion => BOLD,
+    }
+}
+
+/// One line (or a few) describing a step
+pub fn format_step(step: &ExecutionStep) -> String {
+    let label = match step.step_type {
+        StepType::Thinking => "thinking",
+        StepType::Planning => "planning",
+        StepType::Action => "action",
+        StepType::Observation => "observation",
+        StepType::Reflection => "reflection",
+        StepType::Completion => "completion",
+    };
+    let mut line = format!("[{}] {}: {}", step.step_number, label, step.content.trim());
+
+    if let Some(call) = &step.tool_call {
+        line.push_str(&format!("\n    → {}({})", call.tool_name, call.arguments));
+    }
+    if let Some(observation) = &step.tool_observation {
+        let status = if observation.success { "ok" } else { "failed" };
+        let detail = observation.error.as_deref().unwrap_or(&observation.message);
+        line.push_str(&format!("\n    ← {}: {}", status, truncate(detail)));
+    }
+    line
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_OBSERVATION_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Conversation manager that prints steps to stdout as they arrive
+#[derive(Debug, Default)]
+pub struct StepPrinter {
+    printed: AtomicUsize,
+    color: bool,
+}
+
+impl StepPrinter {
+    /// Printer that colours its output when stdout is a terminal
+    pub fn new() -> Self {
+        Self::with_color(use_color())
+    }
+
+    pub fn with_color(color: bool) -> Self {
+        Self {
+            printed: AtomicUsize::new(0),
+            color,
+        }
+    }
+
+    /// Number of steps printed so far
+    pub fn printed(&self) -> usize {
+        self.printed.load(Ordering::Relaxed)
+    }
+
+    pub fn print(&self, step: &ExecutionStep) {
+        println!(
+            "{}",
+            paint(&format_step(step), step_color(step), self.color)
+        );
+        self.printed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationManager for StepPrinter {
+    async fn send_thinking_update(
+        &self,
+        _agent_id: &str,
+        step_number: usize,
+        thought: &str,
+    ) -> crate::core::Result<()> {
+        // The thinking engine streams whole steps as JSON
+        match serde_json::from_str::<ExecutionStep>(thought) {
+            Ok(step) => self.print(&step),
+            Err(_) => {
+                let line = format!("[{}] thinking: {}", step_number, thought.trim());
+                println!("{}", paint(&line, DIM, self.color));
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_progress_update(
+        &self,
+        _agent_id: &str,
+        _progress_type: ProgressType,
+        message: &str,
+        _progress: Option<f32>,
+    ) -> crate::core::Result<()> {
+        println!("{}", paint(&format!("… {}", message), CYAN, self.color));
+        Ok(())
+    }
+
+    async fn send_error_update(
+        &self,
+        _agent_id: &str,
+        error: &str,
+        recovery_suggestions: Vec<String>,
+    ) -> crate::core::Result<()> {
+        eprintln!("{}", paint(&format!("! {}", error), RED, self.color));
+        for suggestion in recovery_suggestions {
+            eprintln!("  - {}", suggestion);
+        }
+        Ok(())
+    }
+
+    async fn send_completion_update(
+        &self,
+        _agent_id: &str,
+        _final_response: &str,
+        _success: bool,
+    ) -> crate::core::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ToolCall, ToolObservation};
+
+    #[test]
+    fn test_format_tool_steps() {
+        let action = ExecutionStep {
+            step_number: 2,
+            step_type: StepType::Action,
+            content: "Typing".to_string(),
+            tool_call: Some(ToolCall {
+                tool_name: "keyboard_input".to_string(),
+                arguments: serde_json::json!({"text": "hi"}),
+                execution_time: std::time::Duration::ZERO,
+            }),
+            tool_observation: None,
+            timestamp: String::new(),
+        };
+        assert_eq!(
+            format_step(&action),
+            "[2] action: Typing\n    → keyboard_input({\"text\":\"hi\"})"
+        );
+
+        let observation = ExecutionStep {
+            step_number: 3,
+            step_type: StepType::Observation,
+            content: "Result".to_string(),
+            tool_call: None,
+            tool_observation: Some(ToolObservation {
+                success: false,
+                message: "ignored".to_string(),
+                data: None,
+                error: Some("x".repeat(MAX_OBSERVATION_CHARS + 10)),
+            }),
+            timestamp: String::new(),
+        };
+        let text = format_step(&observation);
+        assert!(text.contains("← failed: "));
+        assert!(text.ends_with('…'));
+        assert_eq!(step_color(&observation), RED);
+    }
+
+    #[test]
+    fn test_paint_only_when_enabled() {
+        assert_eq!(paint("hi", GREEN, false), "hi");
+        assert_eq!(paint("hi", GREEN, true), "\x1b[32mhi\x1b[0m");
+    }
+}
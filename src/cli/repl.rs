@@ -0,0 +1,412 @@
+//! Interactive terminal chat
+//!
+//! Each message runs the current agent with the conversation so far; steps are
+//! rendered as they stream in and a running turn can be cancelled.
+
+use crate::api::{ExecutionRecord, ExecutionStatus, ExecutionStore, SessionLog};
+use crate::cli::output::{self, paint, StepPrinter, BOLD, CYAN, DIM, RED, YELLOW};
+use crate::core::{AppError, ConversationMessage, LLMClient, Result};
+use crate::registry::CentralRegistry;
+use crate::websocket::handler::{ChatExecution, MessageHandler};
+use std::io::Write;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader, Lines, Stdin};
+use tokio::sync::mpsc;
+
+const HELP: &str = "\
+/agent [id]     show or switch the agent (resets tools and history)
+/agents         list agents
+/tools          list tools; enabled ones are marked with *
+/tool <id>      enable or disable a tool
+/plugins rescan load plugins added, changed or removed on disk
+/transcript     show this session's turns
+/clear          forget the conversation so far
+/cancel         stop the running turn (Ctrl-C works too)
+/help           show this help
+/quit           leave (Ctrl-D, or Ctrl-C at the prompt, works too)";
+
+/// A slash command typed at the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplCommand {
+    Agent(Option<String>),
+    Agents,
+    Tools,
+    ToggleTool(String),
+    RescanPlugins,
+    Transcript,
+    Clear,
+    Cancel,
+    Help,
+    Quit,
+    Unknown(String),
+}
+
+impl ReplCommand {
+    /// Parse a line starting with `/`; anything else is a chat message
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim().strip_prefix('/')?;
+        let mut parts = line.split_whitespace();
+        let name = parts.next().unwrap_or_default();
+        let arg = parts.next().map(str::to_string);
+
+        Some(match (name, arg) {
+            ("agent", arg) => Self::Agent(arg),
+            ("agents", _) => Self::Agents,
+            ("tools", _) => Self::Tools,
+            ("tool", Some(id)) => Self::ToggleTool(id),
+            ("plugins", Some(arg)) if arg == "rescan" => Self::RescanPlugins,
+            ("transcript", _) => Self::Transcript,
+            ("clear", _) => Self::Clear,
+            ("cancel", _) => Self::Cancel,
+            ("help", _) => Self::Help,
+            ("quit" | "exit", _) => Self::Quit,
+            _ => Self::Unknown(line.to_string()),
+        })
+    }
+}
+
+/// Forward every Ctrl-C to the REPL. Once tokio listens for SIGINT the default
+/// handler never comes back, so a single listener serves both the idle prompt
+/// (where Ctrl-C quits) and running turns (where it cancels).
+fn listen_for_interrupts() -> mpsc::UnboundedReceiver<()> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if tx.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// State of an interactive chat session
+pub struct Repl {
+    registry: Arc<CentralRegistry>,
+    handler: MessageHandler,
+    executions: ExecutionStore,
+    sessions: Option<SessionLog>,
+    session_id: String,
+    agent_id: String,
+    tools: Vec<String>,
+    history: Vec<ConversationMessage>,
+    color: bool,
+}
+
+impl Repl {
+    pub async fn new(
+        registry: Arc<CentralRegistry>,
+        llm: Arc<dyn LLMClient>,
+        agent_id: &str,
+        session_id: impl Into<String>,
+    ) -> Result<Self> {
+        let mut repl = Self {
+            handler: MessageHandler::new(registry.clone(), llm),
+            registry,
+            executions: ExecutionStore::new(),
+            sessions: None,
+            session_id: session_id.into(),
+            agent_id: String::new(),
+            tools: Vec::new(),
+            history: Vec::new(),
+            color: output::use_color(),
+        };
+        repl.switch_agent(agent_id).await?;
+        Ok(repl)
+    }
+
+    /// Save each turn to the session history
+    pub fn with_session_log(mut self, sessions: SessionLog) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Read and answer messages until `/quit` or end of input
+    pub async fn run(&mut self) -> Result<()> {
+        println!(
+            "Chatting with {} in session {}. Type /help for commands.",
+            paint(&self.agent_id, BOLD, self.color),
+            self.session_id
+        );
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut interrupts = listen_for_interrupts();
+
+        loop {
+            // Ctrl-C pressed while a turn was stopping shouldn't also quit
+            while interrupts.try_recv().is_ok() {}
+            print!("{}", paint("› ", CYAN, self.color));
+            std::io::stdout().flush()?;
+            let line = tokio::select! {
+                line = lines.next_line() => line?,
+                Some(()) = interrupts.recv() => {
+                    println!();
+                    None
+                }
+            };
+            let Some(line) = line else {
+                break;
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let result = match ReplCommand::parse(line) {
+                Some(ReplCommand::Quit) => break,
+                Some(command) => self.apply(command).await,
+                None => self.send(line, &mut lines, &mut interrupts).await,
+            };
+            if let Err(e) = result {
+                eprintln!("{}", paint(&e.to_string(), RED, self.color));
+            }
+        }
+        Ok(())
+    }
+
+    async fn apply(&mut self, command: ReplCommand) -> Result<()> {
+        match command {
+            ReplCommand::Agent(None) => println!("Current agent: {}", self.agent_id),
+            ReplCommand::Agent(Some(id)) => {
+                self.switch_agent(&id).await?;
+                println!("Switched to {}; tools: {}", id, self.tools.join(", "));
+            }
+            ReplCommand::Agents => {
+                for agent in self.registry.agents.list().await? {
+                    let marker = if agent.id() == self.agent_id {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{} {:<28} {}", marker, agent.id(), agent.description());
+                }
+            }
+            ReplCommand::Tools => {
+                for tool in self.registry.tools.list().await? {
+                    let enabled = self.tools.iter().any(|id| id == tool.id());
+                    let marker = if enabled { "*" } else { " " };
+                    println!("{} {:<24} {}", marker, tool.id(), tool.description());
+                }
+            }
+            ReplCommand::ToggleTool(id) => {
+                if let Some(index) = self.tools.iter().position(|t| t == &id) {
+                    self.tools.remove(index);
+                    println!("Disabled {}", id);
+                } else if self.registry.tools.get(&id).await?.is_some() {
+                    println!("Enabled {}", id);
+                    self.tools.push(id);
+                } else {
+                    return Err(AppError::Registry(format!("Tool not found: {}", id)));
+                }
+            }
+            ReplCommand::Transcript => {
+                let records = self.executions.session(&self.session_id);
+                if records.is_empty() {
+                    println!("Nothing yet.");
+                }
+                for record in &records {
+                    self.print_transcript_entry(record);
+                }
+            }
+            ReplCommand::RescanPlugins => {
+                let scan = self.registry.rescan_plugins().await?;
+                for (path, error) in &scan.errors {
+                    println!("Skipping plugin {}: {}", path.display(), error);
+                }
+                println!(
+                    "{} plugin tool(s) loaded, {} removed",
+                    scan.loaded.len(),
+                    scan.removed.len()
+                );
+            }
+            ReplCommand::Clear => {
+                self.history.clear();
+                println!("Conversation cleared.");
+            }
+            ReplCommand::Cancel => println!("Nothing is running."),
+            ReplCommand::Help => println!("{}", HELP),
+            ReplCommand::Quit => {}
+            ReplCommand::Unknown(command) => {
+                println!("Unknown command /{}; try /help", command)
+            }
+        }
+        Ok(())
+    }
+
+    async fn switch_agent(&mut self, agent_id: &str) -> Result<()> {
+        let agent = self
+            .registry
+            .agents
+            .get(agent_id)
+            .await?
+            .ok_or_else(|| AppError::Registry(format!("Agent not found: {}", agent_id)))?;
+        self.agent_id = agent_id.to_string();
+        self.tools = agent.tool_dependencies().to_vec();
+        self.history.clear();
+        Ok(())
+    }
+
+    /// Run one turn, watching the prompt and Ctrl-C for a cancel request
+    async fn send(
+        &mut self,
+        message: &str,
+        lines: &mut Lines<BufReader<Stdin>>,
+        interrupts: &mut mpsc::UnboundedReceiver<()>,
+    ) -> Result<()> {
+        let chat = ChatExecution {
+            agent_id: self.agent_id.clone(),
+            message: message.to_string(),
+            tools: self.tools.clone(),
+            sampling: None,
+            bypass_cache: false,
+            session_id: self.session_id.clone(),
+            history: self.history.clone(),
+        };
+        let record = self
+            .executions
+            .create(&chat.agent_id, &chat.session_id, &chat.message);
+        let id = record.id.clone();
+
+        let printer = Arc::new(StepPrinter::with_color(self.color));
+        let handler = self.handler.clone();
+        let executions = self.executions.clone();
+        let task_printer = printer.clone();
+        let task_id = id.clone();
+        let mut task = tokio::spawn(async move {
+            let outcome = handler
+                .run_chat(&chat, task_printer)
+                .await
+                .map(|(result, _)| result);
+            executions.finish(&task_id, outcome);
+        });
+        self.executions.attach_task(&id, task.abort_handle());
+
+        loop {
+            tokio::select! {
+                _ = &mut task => break,
+                Some(()) = interrupts.recv() => {
+                    self.executions.cancel(&id);
+                    break;
+                }
+                line = lines.next_line() => match line? {
+                    Some(line) if ReplCommand::parse(&line) == Some(ReplCommand::Cancel) => {
+                        self.executions.cancel(&id);
+                        break;
+                    }
+                    Some(line) if !line.trim().is_empty() => {
+                        println!("{}", paint("Still working; /cancel to stop", DIM, self.color));
+                    }
+                    Some(_) => {}
+                    // Input closed; there is no one left to answer
+                    None => {
+                        self.executions.cancel(&id);
+                        break;
+                    }
+                },
+            }
+        }
+
+        let record = self
+            .executions
+            .get(&id)
+            .ok_or_else(|| AppError::Agent(format!("Execution {} disappeared", id)))?;
+        if printer.printed() == 0 {
+            record.steps.iter().for_each(|step| printer.print(step));
+        }
+        match record.status {
+            ExecutionStatus::Completed => {
+                let response = record.response.clone().unwrap_or_default();
+                println!("\n{}\n", paint(&response, BOLD, self.color));
+                self.remember(message, &response);
+            }
+            ExecutionStatus::Cancelled => {
+                println!("{}", paint("Cancelled.", YELLOW, self.color));
+            }
+            _ => {
+                let error = record
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Agent did not complete the task".to_string());
+                println!("{}", paint(&error, RED, self.color));
+            }
+        }
+
+        if let Some(log) = &self.sessions {
+            log.append(&record)?;
+        }
+        Ok(())
+    }
+
+    fn remember(&mut self, message: &str, response: &str) {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.history.push(ConversationMessage {
+            role: "user".to_string(),
+            content: message.to_string(),
+            timestamp: timestamp.clone(),
+        });
+        self.history.push(ConversationMessage {
+            role: "assistant".to_string(),
+            content: response.to_string(),
+            timestamp,
+        });
+    }
+
+    fn print_transcript_entry(&self, record: &ExecutionRecord) {
+        println!(
+            "{} {}",
+            paint(&format!("[{}]", record.agent_id), DIM, self.color),
+            paint(&format!("› {}", record.message), CYAN, self.color)
+        );
+        for step in &record.steps {
+            println!(
+                "  {}",
+                paint(
+                    &output::format_step(step),
+                    output::step_color(step),
+                    self.color
+                )
+            );
+        }
+        match record.status {
+            ExecutionStatus::Completed => println!("{}", record.response.as_deref().unwrap_or("")),
+            ExecutionStatus::Cancelled => println!("{}", paint("(cancelled)", YELLOW, self.color)),
+            ExecutionStatus::Running => println!("{}", paint("(running)", DIM, self.color)),
+            ExecutionStatus::Failed => println!(
+                "{}",
+                paint(
+                    record.error.as_deref().unwrap_or("(failed)"),
+                    RED,
+                    self.color
+                )
+            ),
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(ReplCommand::parse("hello there"), None);
+        assert_eq!(ReplCommand::parse("/agent"), Some(ReplCommand::Agent(None)));
+        assert_eq!(
+            ReplCommand::parse(" /agent web-research-agent "),
+            Some(ReplCommand::Agent(Some("web-research-agent".to_string())))
+        );
+        assert_eq!(
+            ReplCommand::parse("/tool web_search"),
+            Some(ReplCommand::ToggleTool("web_search".to_string()))
+        );
+        assert_eq!(
+            ReplCommand::parse("/plugins rescan"),
+            Some(ReplCommand::RescanPlugins)
+        );
+        assert_eq!(ReplCommand::parse("/exit"), Some(ReplCommand::Quit));
+        assert_eq!(
+            ReplCommand::parse("/tool"),
+            Some(ReplCommand::Unknown("tool".to_string()))
+        );
+    }
+}
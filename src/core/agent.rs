@@ -2,6 +2,7 @@
 
 use crate::core::{AppError, Result};
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -53,8 +54,9 @@ pub trait Agent: DynClone + Send + Sync {
 
 dyn_clone::clone_trait_object!(Agent);
 
-/// Configuration for agent reasoning
+/// Configuration for agent reasoning; omitted fields take their defaults
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ReasoningConfig {
     /// Model ID to use for reasoning
     pub model_id: String,
@@ -64,6 +66,21 @@ pub struct ReasoningConfig {
     pub separate_reasoning_model: bool,
     /// Reasoning model ID (if different from main model)
     pub reasoning_model_id: Option<String>,
+    /// Context-window management settings
+    #[serde(default)]
+    pub context: crate::llm::context::ContextConfig,
+    /// Token and cost limits for a single execution
+    #[serde(default)]
+    pub budget: crate::llm::usage::UsageBudget,
+    /// Sampling parameters sent with every LLM call
+    #[serde(default)]
+    pub sampling: SamplingParams,
+    /// JSON schema the final answer must follow, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+    /// Show the latest screenshot observation to the model as an image
+    #[serde(default)]
+    pub attach_screenshots: bool,
 }
 
 impl Default for ReasoningConfig {
@@ -73,10 +90,102 @@ impl Default for ReasoningConfig {
             max_iterations: 10,
             separate_reasoning_model: false,
             reasoning_model_id: None,
+            context: crate::llm::context::ContextConfig::default(),
+            budget: crate::llm::usage::UsageBudget::default(),
+            sampling: SamplingParams::default(),
+            output_schema: None,
+            attach_screenshots: false,
         }
     }
 }
 
+/// Maximum number of stop sequences accepted by the backend
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Sampling parameters for LLM generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SamplingParams {
+    /// Sampling temperature (0.0 - 2.0); 0 is deterministic
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass (0.0 exclusive - 1.0)
+    #[serde(alias = "top_p")]
+    pub top_p: Option<f32>,
+    /// Maximum tokens to generate
+    #[serde(alias = "max_tokens")]
+    pub max_tokens: Option<u32>,
+    /// Sequences that stop generation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Seed for reproducible sampling
+    pub seed: Option<u64>,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: Some(0.7),
+            top_p: None,
+            max_tokens: Some(4096),
+            stop: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+impl SamplingParams {
+    /// Deterministic sampling for tasks that must not vary between runs
+    pub fn deterministic() -> Self {
+        Self {
+            temperature: Some(0.0),
+            ..Self::default()
+        }
+    }
+
+    /// Check that every value is within the range the backend accepts
+    pub fn validate(&self) -> Result<()> {
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(AppError::Configuration(format!(
+                    "temperature must be between 0 and 2, got {}",
+                    temperature
+                )));
+            }
+        }
+
+        if let Some(top_p) = self.top_p {
+            if !(top_p > 0.0 && top_p <= 1.0) {
+                return Err(AppError::Configuration(format!(
+                    "top_p must be greater than 0 and at most 1, got {}",
+                    top_p
+                )));
+            }
+        }
+
+        if self.max_tokens == Some(0) {
+            return Err(AppError::Configuration(
+                "max_tokens must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(AppError::Configuration(format!(
+                "at most {} stop sequences are allowed, got {}",
+                MAX_STOP_SEQUENCES,
+                self.stop.len()
+            )));
+        }
+
+        if self.stop.iter().any(|s| s.is_empty()) {
+            return Err(AppError::Configuration(
+                "stop sequences must not be empty".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 /// Context for agent execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentContext {
@@ -88,6 +197,9 @@ pub struct AgentContext {
     pub shared_state: HashMap<String, serde_json::Value>,
     /// Execution metadata
     pub metadata: ExecutionMetadata,
+    /// Options applied to every LLM request made for this execution
+    #[serde(default)]
+    pub llm_options: LLMRequestOptions,
 }
 
 impl AgentContext {
@@ -97,6 +209,7 @@ impl AgentContext {
             messages: Vec::new(),
             shared_state: HashMap::new(),
             metadata: ExecutionMetadata::default(),
+            llm_options: LLMRequestOptions::default(),
         }
     }
 }
@@ -136,10 +249,16 @@ pub struct AgentResult {
     pub execution_time: std::time::Duration,
     /// Final context state
     pub final_context: AgentContext,
+    /// Token usage and estimated cost of the execution
+    #[serde(default)]
+    pub usage: crate::llm::usage::ExecutionUsage,
+    /// Final answer in the shape of the agent's output schema, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_output: Option<serde_json::Value>,
 }
 
 /// A single execution step
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExecutionStep {
     /// Step number
     pub step_number: usize,
@@ -156,7 +275,7 @@ pub struct ExecutionStep {
 }
 
 /// Type of execution step
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum StepType {
     /// Thinking/reasoning phase
     Thinking,
@@ -173,7 +292,7 @@ pub enum StepType {
 }
 
 /// Tool call information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolCall {
     /// Tool name
     pub tool_name: String,
@@ -184,7 +303,7 @@ pub struct ToolCall {
 }
 
 /// Tool observation/result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolObservation {
     /// Whether tool execution was successful
     pub success: bool,
@@ -211,6 +330,83 @@ pub trait LLMClient: Send + Sync {
         model_id: &str,
         tools: Option<Vec<LLMTool>>,
     ) -> Result<LLMResponse>;
+
+    /// Chat with per-request options; options a client doesn't support are ignored
+    async fn chat_with_options(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+        _options: &LLMRequestOptions,
+    ) -> Result<LLMResponse> {
+        self.chat_with_tools(messages, model_id, tools).await
+    }
+
+    /// Ask for a JSON answer matching `schema`, retrying with the validation errors
+    /// until it matches
+    async fn chat_structured(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        schema: &serde_json::Value,
+        options: &LLMRequestOptions,
+    ) -> Result<serde_json::Value> {
+        let output = crate::llm::structured::generate(
+            self,
+            messages,
+            model_id,
+            schema,
+            options,
+            crate::llm::structured::DEFAULT_STRUCTURED_RETRIES,
+        )
+        .await?;
+        Ok(output.value)
+    }
+}
+
+/// Per-request options for LLM calls
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LLMRequestOptions {
+    /// Skip the response cache for this request
+    pub bypass_cache: bool,
+    /// Sampling parameters; the client's defaults are used when `None`
+    pub sampling: Option<SamplingParams>,
+    /// Constrain the response format where the backend supports it
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// Response format requested from the backend (JSON mode)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema { json_schema: serde_json::Value },
+}
+
+/// How a response cache handled a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// Served from the cache
+    Hit,
+    /// Fetched from the backend and stored
+    Miss,
+    /// Fetched from the backend without consulting the cache
+    Bypass,
+}
+
+impl CacheStatus {
+    /// Human-readable description for execution steps
+    pub fn describe(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "LLM cache hit: reused a stored response",
+            CacheStatus::Miss => "LLM cache miss: response fetched and stored",
+            CacheStatus::Bypass => "LLM cache bypassed for this request",
+        }
+    }
 }
 
 /// A message in LLM conversation
@@ -219,11 +415,144 @@ pub struct LLMMessage {
     /// Message role (system, user, assistant)
     pub role: String,
     /// Message content
-    pub content: String,
+    pub content: MessageContent,
     /// Optional tool calls (for assistant messages)
     pub tool_calls: Option<Vec<LLMToolCall>>,
 }
 
+/// Content of an LLM message: plain text, or text and image parts
+///
+/// Parts serialize in the OpenAI-compatible shape accepted by vision models.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+/// One part of a multimodal message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// Image reference, either a URL or a `data:` URL with base64 content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentPart::Text { text: text.into() }
+    }
+
+    /// Image from base64 data with the given MIME type (e.g. `image/png`)
+    pub fn image_base64(mime_type: &str, data: &str) -> Self {
+        ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: format!("data:{};base64,{}", mime_type, data),
+            },
+        }
+    }
+}
+
+impl MessageContent {
+    /// Text of the message, with images left out
+    pub fn as_text(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            MessageContent::Text(text) => std::borrow::Cow::Borrowed(text),
+            MessageContent::Parts(parts) => std::borrow::Cow::Owned(
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::ImageUrl { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+
+    /// Number of image parts
+    pub fn image_count(&self) -> usize {
+        match self {
+            MessageContent::Text(_) => 0,
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter(|p| matches!(p, ContentPart::ImageUrl { .. }))
+                .count(),
+        }
+    }
+
+    /// Append text, as a new part if the content already has parts
+    pub fn push_text(&mut self, text: &str) {
+        match self {
+            MessageContent::Text(existing) => {
+                existing.push_str("\n\n");
+                existing.push_str(text);
+            }
+            MessageContent::Parts(parts) => parts.push(ContentPart::text(text)),
+        }
+    }
+
+    /// Replace every image with a short text placeholder
+    pub fn without_images(&self) -> MessageContent {
+        match self {
+            MessageContent::Text(_) => self.clone(),
+            MessageContent::Parts(_) if self.image_count() == 0 => {
+                MessageContent::Text(self.as_text().into_owned())
+            }
+            MessageContent::Parts(parts) => MessageContent::Text(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        ContentPart::Text { text } => text.as_str(),
+                        ContentPart::ImageUrl { .. } => "[image omitted]",
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl std::fmt::Display for MessageContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.without_images() {
+            MessageContent::Text(text) => f.write_str(&text),
+            MessageContent::Parts(_) => Ok(()),
+        }
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
 /// LLM tool definition for function calling
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMTool {
@@ -259,10 +588,13 @@ pub struct LLMResponse {
     pub usage: Option<LLMUsage>,
     /// Response time
     pub response_time: std::time::Duration,
+    /// Cache status, when the response went through a response cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
 }
 
 /// LLM token usage information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LLMUsage {
     /// Input tokens used
     pub input_tokens: u32,
@@ -271,3 +603,81 @@ pub struct LLMUsage {
     /// Total tokens used
     pub total_tokens: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_validation() {
+        assert!(SamplingParams::default().validate().is_ok());
+        assert!(SamplingParams::deterministic().validate().is_ok());
+
+        let invalid = [
+            SamplingParams {
+                temperature: Some(2.5),
+                ..Default::default()
+            },
+            SamplingParams {
+                top_p: Some(0.0),
+                ..Default::default()
+            },
+            SamplingParams {
+                max_tokens: Some(0),
+                ..Default::default()
+            },
+            SamplingParams {
+                stop: vec!["a", "b", "c", "d", "e"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                ..Default::default()
+            },
+        ];
+        for params in invalid {
+            assert!(
+                params.validate().is_err(),
+                "{:?} should be rejected",
+                params
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_content_serialization() {
+        let text: MessageContent = "hello".into();
+        assert_eq!(
+            serde_json::to_value(&text).unwrap(),
+            serde_json::json!("hello")
+        );
+
+        let parts = MessageContent::from(vec![
+            ContentPart::text("What is on screen?"),
+            ContentPart::image_base64("image/png", "iVBORw0KGgo="),
+        ]);
+        let json = serde_json::to_value(&parts).unwrap();
+        assert_eq!(json[0]["type"], "text");
+        assert_eq!(json[1]["type"], "image_url");
+        assert_eq!(
+            json[1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+
+        let roundtrip: MessageContent = serde_json::from_value(json).unwrap();
+        assert_eq!(roundtrip, parts);
+        assert_eq!(parts.image_count(), 1);
+        assert_eq!(parts.as_text(), "What is on screen?");
+        assert_eq!(parts.to_string(), "What is on screen?\n[image omitted]");
+    }
+
+    #[test]
+    fn test_sampling_accepts_camel_and_snake_case() {
+        let camel: SamplingParams =
+            serde_json::from_str(r#"{"topP": 0.9, "maxTokens": 256, "seed": 7}"#).unwrap();
+        let snake: SamplingParams =
+            serde_json::from_str(r#"{"top_p": 0.9, "max_tokens": 256, "seed": 7}"#).unwrap();
+        assert_eq!(camel, snake);
+        // Unspecified values keep their defaults
+        assert_eq!(camel.temperature, Some(0.7));
+    }
+}
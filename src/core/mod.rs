@@ -8,9 +8,13 @@ pub mod tool;
 
 // Re-export key types for convenience
 pub use agent::{
-    Agent, AgentContext, AgentResult, ConversationMessage, ExecutionStep, LLMClient, LLMMessage,
-    LLMResponse, LLMTool, LLMToolCall, LLMUsage, ReasoningConfig, StepType, ToolCall,
+    Agent, AgentContext, AgentResult, CacheStatus, ContentPart, ConversationMessage, ExecutionStep,
+    ImageUrl, LLMClient, LLMMessage, LLMRequestOptions, LLMResponse, LLMTool, LLMToolCall,
+    LLMUsage, MessageContent, ReasoningConfig, ResponseFormat, SamplingParams, StepType, ToolCall,
     ToolObservation,
 };
 pub use error::{AppError, Result};
-pub use tool::{LoopDetector, Tool, ToolContext, ToolExecutionState, ToolParameter, ToolResult};
+pub use tool::{
+    parameters_to_json_schema, tool_id, LoopDetector, Tool, ToolContext, ToolExecutionState,
+    ToolParameter, ToolResult,
+};
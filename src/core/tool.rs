@@ -2,11 +2,12 @@
 
 use crate::core::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Tool parameter definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolParameter {
     pub name: String,
     pub param_type: String,
@@ -16,6 +17,53 @@ pub struct ToolParameter {
     pub enum_values: Option<Vec<String>>,
 }
 
+/// Convert tool parameters into a JSON Schema object for LLM function calling
+pub fn parameters_to_json_schema(params: &[ToolParameter]) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+
+    for param in params {
+        let mut param_schema = serde_json::Map::new();
+        param_schema.insert("type".to_string(), serde_json::json!(param.param_type));
+        param_schema.insert(
+            "description".to_string(),
+            serde_json::json!(param.description),
+        );
+
+        if let Some(enums) = &param.enum_values {
+            param_schema.insert("enum".to_string(), serde_json::json!(enums));
+        }
+
+        properties.insert(param.name.clone(), serde_json::Value::Object(param_schema));
+
+        if param.required {
+            required.push(serde_json::Value::String(param.name.clone()));
+        }
+    }
+
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    })
+}
+
+/// Registry ID for `name` imported from `source`: `<source>__<name>`, limited to
+/// safe characters. Used by MCP servers and OpenAPI specs alike.
+pub fn tool_id(source: &str, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}__{}", source, name)
+}
+
 /// Core trait that all tools must implement
 use dyn_clone::DynClone;
 
@@ -37,6 +85,34 @@ pub trait Tool: DynClone + Send + Sync {
     /// Get the parameters this tool accepts
     fn parameters(&self) -> &[ToolParameter];
 
+    /// Get the capabilities this tool provides
+    fn capabilities(&self) -> &[String] {
+        &[]
+    }
+
+    /// Get the tools this tool depends on
+    fn tool_dependencies(&self) -> &[String] {
+        &[]
+    }
+
+    /// JSON Schema of the tool's configuration block, if it has one
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Current configuration block
+    fn config(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Apply a configuration block that matches `config_schema`
+    fn configure(&mut self, _config: &serde_json::Value) -> Result<()> {
+        Err(crate::core::AppError::Tool(format!(
+            "Tool '{}' has no configuration",
+            self.id()
+        )))
+    }
+
     /// Execute the tool with given arguments
     async fn execute(&self, args: &serde_json::Value, context: &ToolContext) -> Result<ToolResult>;
 
@@ -103,7 +179,7 @@ impl LoopDetector {
         let count = self
             .recent_calls
             .iter()
-            .filter(|(name, args)| name == tool_name && args == args)
+            .filter(|(name, previous_args)| name == tool_name && previous_args == args)
             .count();
 
         // Add current call to history
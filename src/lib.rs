@@ -3,9 +3,12 @@
 //! A modular, dynamic agent system with plug-and-play tools and thinking capabilities.
 
 pub mod agents;
+pub mod api;
+pub mod cli;
 pub mod config;
 pub mod core;
 pub mod llm;
+pub mod mcp;
 pub mod registry;
 pub mod tools;
 pub mod utils;
@@ -14,10 +17,11 @@ pub mod websocket;
 // Re-export key types for convenience
 pub use agents::conversation::{ConversationManager, ProgressType};
 pub use agents::registry::AgentRegistry;
-pub use agents::{ConversationalAgent, DesktopAutomationAgent, WebResearchAgent};
+pub use agents::ConfigAgent;
 pub use core::agent::{
-    ConversationMessage, ExecutionStep, LLMClient, LLMMessage, LLMResponse, LLMTool, LLMToolCall,
-    LLMUsage, ReasoningConfig, StepType, ToolCall, ToolObservation,
+    CacheStatus, ContentPart, ConversationMessage, ExecutionStep, LLMClient, LLMMessage,
+    LLMRequestOptions, LLMResponse, LLMTool, LLMToolCall, LLMUsage, MessageContent,
+    ReasoningConfig, ResponseFormat, SamplingParams, StepType, ToolCall, ToolObservation,
 };
 pub use core::{
     Agent, AgentContext, AgentResult, Tool, ToolContext, ToolExecutionState, ToolResult,
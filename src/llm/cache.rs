@@ -0,0 +1,351 @@
+//! Opt-in on-disk response cache for LLM calls
+//!
+//! `CachingClient` wraps another client and stores each response in its own
+//! JSON file named by the request fingerprint, so iterating on a prompt against
+//! an identical transcript doesn't hit the backend again. Entries expire after
+//! a TTL and the oldest are evicted once the store exceeds its size cap.
+
+use crate::core::{
+    CacheStatus, LLMClient, LLMMessage, LLMRequestOptions, LLMResponse, LLMTool, Result,
+};
+use crate::llm::fingerprint;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Environment variable that enables the cache and names its directory
+pub const CACHE_DIR_ENV: &str = "CF_AI_LLM_CACHE_DIR";
+
+/// Environment variable overriding the cache TTL in seconds
+pub const CACHE_TTL_ENV: &str = "CF_AI_LLM_CACHE_TTL_SECS";
+
+/// Response cache settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Directory holding one JSON file per cached response
+    pub directory: PathBuf,
+    /// Seconds after which an entry expires (`None` keeps entries forever)
+    pub ttl_secs: Option<u64>,
+    /// Maximum total size of the store in bytes (`None` for no limit)
+    pub max_bytes: Option<u64>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            directory: dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cf_ai_local_tools")
+                .join("llm"),
+            ttl_secs: Some(24 * 60 * 60),
+            max_bytes: Some(100 * 1024 * 1024),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Settings from `CF_AI_LLM_CACHE_DIR`, or `None` when caching isn't enabled
+    pub fn from_env() -> Option<Self> {
+        let directory = std::env::var(CACHE_DIR_ENV).ok()?;
+        let mut config = Self {
+            directory: PathBuf::from(directory),
+            ..Self::default()
+        };
+        if let Some(ttl) = std::env::var(CACHE_TTL_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            config.ttl_secs = Some(ttl);
+        }
+        Some(config)
+    }
+}
+
+/// A cached response as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Unix timestamp of when the entry was written
+    created_at: i64,
+    /// Normalized request, kept so entries can be inspected by hand
+    request: serde_json::Value,
+    response: LLMResponse,
+}
+
+/// LLM client decorator that caches responses on disk
+pub struct CachingClient {
+    inner: Arc<dyn LLMClient>,
+    config: CacheConfig,
+}
+
+impl CachingClient {
+    pub fn new(inner: Arc<dyn LLMClient>, config: CacheConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Directory backing the cache
+    pub fn directory(&self) -> &Path {
+        &self.config.directory
+    }
+
+    /// Remove every cached response
+    pub fn clear(&self) -> Result<()> {
+        for (path, _, _) in self.entries()? {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.config.directory.join(format!("{}.json", key))
+    }
+
+    /// Load a fresh entry, deleting it if it has expired or can't be read
+    fn load(&self, key: &str) -> Option<LLMResponse> {
+        let path = self.entry_path(key);
+        let text = std::fs::read_to_string(&path).ok()?;
+
+        let entry = match serde_json::from_str::<CacheEntry>(&text) {
+            Ok(entry) => entry,
+            Err(_) => {
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+        };
+
+        if let Some(ttl) = self.config.ttl_secs {
+            let age = chrono::Utc::now().timestamp() - entry.created_at;
+            if age < 0 || age as u64 > ttl {
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+        }
+
+        Some(entry.response)
+    }
+
+    fn store(&self, key: &str, request: serde_json::Value, response: &LLMResponse) -> Result<()> {
+        std::fs::create_dir_all(&self.config.directory)?;
+
+        let entry = CacheEntry {
+            created_at: chrono::Utc::now().timestamp(),
+            request,
+            response: response.clone(),
+        };
+        std::fs::write(self.entry_path(key), serde_json::to_string(&entry)?)?;
+
+        self.enforce_size_cap()
+    }
+
+    /// Cache files with their size and modification time
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, std::time::SystemTime)>> {
+        let dir = match std::fs::read_dir(&self.config.directory) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for item in dir.flatten() {
+            let path = item.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(metadata) = item.metadata() {
+                let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+                entries.push((path, metadata.len(), modified));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Evict the oldest entries until the store fits within `max_bytes`
+    fn enforce_size_cap(&self) -> Result<()> {
+        let Some(max_bytes) = self.config.max_bytes else {
+            return Ok(());
+        };
+
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            let _ = std::fs::remove_file(path);
+            total = total.saturating_sub(size);
+        }
+        Ok(())
+    }
+
+    async fn fetch(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+        options: &LLMRequestOptions,
+    ) -> Result<LLMResponse> {
+        if options.bypass_cache {
+            let mut response = self
+                .inner
+                .chat_with_options(messages, model_id, tools, options)
+                .await?;
+            response.cache = Some(CacheStatus::Bypass);
+            return Ok(response);
+        }
+
+        let request =
+            fingerprint::normalized_request(messages, model_id, tools.as_deref(), Some(options));
+        let key = fingerprint::hash_value(&request);
+
+        if let Some(mut response) = self.load(&key) {
+            response.cache = Some(CacheStatus::Hit);
+            // No tokens were spent on this call
+            response.usage = None;
+            response.response_time = std::time::Duration::ZERO;
+            return Ok(response);
+        }
+
+        let mut response = self
+            .inner
+            .chat_with_options(messages, model_id, tools, options)
+            .await?;
+
+        // A failed write only costs a future cache miss
+        if let Err(e) = self.store(&key, request, &response) {
+            eprintln!("Failed to write LLM cache entry {}: {}", key, e);
+        }
+
+        response.cache = Some(CacheStatus::Miss);
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMClient for CachingClient {
+    async fn chat(&self, messages: &[LLMMessage], model_id: &str) -> Result<LLMResponse> {
+        self.fetch(messages, model_id, None, &LLMRequestOptions::default())
+            .await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+    ) -> Result<LLMResponse> {
+        self.fetch(messages, model_id, tools, &LLMRequestOptions::default())
+            .await
+    }
+
+    async fn chat_with_options(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+        options: &LLMRequestOptions,
+    ) -> Result<LLMResponse> {
+        self.fetch(messages, model_id, tools, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{MockLLMClient, ScriptedResponse};
+
+    fn user(content: &str) -> Vec<LLMMessage> {
+        vec![LLMMessage {
+            role: "user".to_string(),
+            content: content.into(),
+            tool_calls: None,
+        }]
+    }
+
+    fn cache_config(dir: &tempfile::TempDir) -> CacheConfig {
+        CacheConfig {
+            directory: dir.path().to_path_buf(),
+            ttl_secs: Some(3600),
+            max_bytes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hit_miss_and_bypass() {
+        let backend = Arc::new(MockLLMClient::new());
+        backend.push(ScriptedResponse::text("fresh"));
+        backend.push(ScriptedResponse::text("bypassed"));
+        let dir = tempfile::tempdir().unwrap();
+        let config = cache_config(&dir);
+        let client = CachingClient::new(backend.clone(), config.clone());
+
+        let miss = client.chat(&user("hi"), "m").await.unwrap();
+        let hit = client.chat(&user("hi"), "m").await.unwrap();
+        let bypass = client
+            .chat_with_options(
+                &user("hi"),
+                "m",
+                None,
+                &LLMRequestOptions {
+                    bypass_cache: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(miss.cache, Some(CacheStatus::Miss));
+        assert_eq!(hit.cache, Some(CacheStatus::Hit));
+        assert_eq!(hit.response, "fresh");
+        assert!(hit.usage.is_none());
+        assert_eq!(bypass.cache, Some(CacheStatus::Bypass));
+        assert_eq!(bypass.response, "bypassed");
+        assert_eq!(backend.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_refetched() {
+        let backend = Arc::new(MockLLMClient::new());
+        backend.push(ScriptedResponse::text("first"));
+        backend.push(ScriptedResponse::text("second"));
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = cache_config(&dir);
+        config.ttl_secs = Some(0);
+        let client = CachingClient::new(backend.clone(), config.clone());
+
+        client.chat(&user("hi"), "m").await.unwrap();
+        // Backdate the entry past the TTL
+        let path = std::fs::read_dir(&config.directory)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let mut entry: CacheEntry =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        entry.created_at -= 10;
+        std::fs::write(&path, serde_json::to_string(&entry).unwrap()).unwrap();
+
+        let again = client.chat(&user("hi"), "m").await.unwrap();
+        assert_eq!(again.cache, Some(CacheStatus::Miss));
+        assert_eq!(again.response, "second");
+    }
+
+    #[tokio::test]
+    async fn test_size_cap_evicts_entries() {
+        let backend = Arc::new(MockLLMClient::new());
+        backend.push(ScriptedResponse::text("a"));
+        backend.push(ScriptedResponse::text("b"));
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = cache_config(&dir);
+        config.max_bytes = Some(1);
+        let client = CachingClient::new(backend, config.clone());
+
+        client.chat(&user("one"), "m").await.unwrap();
+        client.chat(&user("two"), "m").await.unwrap();
+
+        assert!(client.entries().unwrap().is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 //! HTTP-based LLM client implementation for Cloudflare Workers AI
 
-use crate::core::Result;
+use crate::core::{LLMRequestOptions, ResponseFormat, Result, SamplingParams};
+use crate::llm::structured;
+use crate::llm::tool_parsing::{self, ToolCallExtraction};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
@@ -10,6 +12,24 @@ pub struct HttpClient {
     base_url: String,
     client: Client,
     api_token: Option<String>,
+    max_tool_parse_retries: usize,
+    default_sampling: SamplingParams,
+}
+
+/// Default number of corrective round-trips when a textual tool call fails to parse
+const DEFAULT_TOOL_PARSE_RETRIES: usize = 2;
+
+/// Workers AI models that accept image content parts
+const VISION_MODELS: &[&str] = &[
+    "@cf/meta/llama-3.2-11b-vision-instruct",
+    "@cf/meta/llama-4-scout-17b-16e-instruct",
+    "@cf/mistralai/mistral-small-3.1-24b-instruct",
+    "@cf/google/gemma-3-12b-it",
+];
+
+/// Whether `model_id` can take images as input
+pub fn supports_vision(model_id: &str) -> bool {
+    VISION_MODELS.contains(&model_id)
 }
 
 #[derive(Debug, Serialize)]
@@ -17,11 +37,43 @@ struct LLMRequest {
     model: String,
     messages: Vec<crate::llm::LLMMessage>,
     tools: Option<Vec<crate::llm::LLMTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
     stream: Option<bool>,
 }
 
+impl LLMRequest {
+    fn new(
+        model_id: &str,
+        messages: Vec<crate::llm::LLMMessage>,
+        tools: Option<Vec<crate::llm::LLMTool>>,
+        sampling: &SamplingParams,
+    ) -> Self {
+        Self {
+            model: model_id.to_string(),
+            messages,
+            tools,
+            max_tokens: sampling.max_tokens,
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            stop: sampling.stop.clone(),
+            seed: sampling.seed,
+            response_format: None,
+            stream: Some(false),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct LLMResponse {
     response: String,
@@ -47,6 +99,8 @@ impl HttpClient {
             base_url,
             client: Client::new(),
             api_token: std::env::var("CF_API_TOKEN").ok(),
+            max_tool_parse_retries: DEFAULT_TOOL_PARSE_RETRIES,
+            default_sampling: SamplingParams::default(),
         }
     }
 
@@ -56,6 +110,8 @@ impl HttpClient {
             base_url,
             client: Client::new(),
             api_token: Some(api_token),
+            max_tool_parse_retries: DEFAULT_TOOL_PARSE_RETRIES,
+            default_sampling: SamplingParams::default(),
         }
     }
 
@@ -64,6 +120,24 @@ impl HttpClient {
         self.api_token = Some(token);
     }
 
+    /// Set how many corrective messages are sent when a textual tool call can't be parsed
+    pub fn set_max_tool_parse_retries(&mut self, retries: usize) {
+        self.max_tool_parse_retries = retries;
+    }
+
+    /// Set the sampling parameters used when a request doesn't carry its own
+    pub fn set_default_sampling(&mut self, sampling: SamplingParams) {
+        self.default_sampling = sampling;
+    }
+
+    /// Sampling parameters for a request, falling back to the client default
+    fn sampling_for(&self, options: &LLMRequestOptions) -> SamplingParams {
+        options
+            .sampling
+            .clone()
+            .unwrap_or_else(|| self.default_sampling.clone())
+    }
+
     /// Get base URL
     pub fn base_url(&self) -> &str {
         &self.base_url
@@ -137,7 +211,7 @@ impl HttpClient {
             crate::core::AppError::LLM(format!("Failed to read response body: {}", e))
         })?;
 
-        println!("DEBUG: Raw LLM response body: {}", response_text);
+        eprintln!("DEBUG: Raw LLM response body: {}", response_text);
 
         let llm_response: LLMResponse = serde_json::from_str(&response_text)
             .map_err(|e| crate::core::AppError::LLM(format!("Failed to parse response: {}", e)))?;
@@ -146,8 +220,21 @@ impl HttpClient {
     }
 
     /// Convert internal messages to HTTP format
-    fn convert_messages(messages: &[crate::llm::LLMMessage]) -> Vec<crate::llm::LLMMessage> {
-        messages.to_vec()
+    /// Images are replaced with a placeholder for models that can't see them
+    fn convert_messages(
+        messages: &[crate::llm::LLMMessage],
+        model_id: &str,
+    ) -> Vec<crate::llm::LLMMessage> {
+        if supports_vision(model_id) {
+            return messages.to_vec();
+        }
+        messages
+            .iter()
+            .map(|m| crate::llm::LLMMessage {
+                content: m.content.without_images(),
+                ..m.clone()
+            })
+            .collect()
     }
 
     /// Convert internal tools to HTTP format
@@ -163,30 +250,9 @@ impl crate::llm::LLMClient for HttpClient {
         messages: &[crate::llm::LLMMessage],
         model_id: &str,
     ) -> Result<crate::llm::LLMResponse> {
-        let request = LLMRequest {
-            model: model_id.to_string(),
-            messages: Self::convert_messages(messages),
-            tools: None,
-            max_tokens: Some(4096),
-            temperature: Some(0.7),
-            stream: Some(false),
-        };
-
-        let start_time = std::time::Instant::now();
-        let response = self.make_request(request).await?;
-        let response_time = start_time.elapsed();
-
-        Ok(crate::llm::LLMResponse {
-            response: response.response,
-            tool_calls: response.tool_calls,
-            model: response.model,
-            usage: response.usage.map(|u| crate::llm::LLMUsage {
-                input_tokens: u.prompt_tokens,
-                output_tokens: u.completion_tokens,
-                total_tokens: u.total_tokens,
-            }),
-            response_time,
-        })
+        // Same request path as tool calls, so sampling is applied consistently
+        self.chat_with_options(messages, model_id, None, &LLMRequestOptions::default())
+            .await
     }
 
     async fn chat_with_tools(
@@ -195,120 +261,112 @@ impl crate::llm::LLMClient for HttpClient {
         model_id: &str,
         tools: Option<Vec<crate::llm::LLMTool>>,
     ) -> Result<crate::llm::LLMResponse> {
-        let request = LLMRequest {
-            model: model_id.to_string(),
-            messages: Self::convert_messages(messages),
-            tools: tools.map(|t| Self::convert_tools(&t)),
-            max_tokens: Some(4096),
-            temperature: Some(0.7),
-            stream: Some(false),
-        };
-
-        let start_time = std::time::Instant::now();
-        let response = self.make_request(request).await?;
-        let response_time = start_time.elapsed();
-
-        Ok(crate::llm::LLMResponse {
-            response: response.response,
-            tool_calls: response.tool_calls,
-            model: response.model,
-            usage: response.usage.map(|u| crate::llm::LLMUsage {
-                input_tokens: u.prompt_tokens,
-                output_tokens: u.completion_tokens,
-                total_tokens: u.total_tokens,
-            }),
-            response_time,
-        })
-    }
-}
-
-/// Mock LLM client for testing
-pub struct MockLLMClient {
-    responses: std::collections::VecDeque<String>,
-    tool_calls: std::collections::VecDeque<Option<Vec<crate::llm::LLMToolCall>>>,
-}
-
-impl MockLLMClient {
-    pub fn new() -> Self {
-        Self {
-            responses: std::collections::VecDeque::new(),
-            tool_calls: std::collections::VecDeque::new(),
-        }
-    }
-
-    pub fn add_response(&mut self, response: String) {
-        self.responses.push_back(response);
-        self.tool_calls.push_back(None);
-    }
-
-    pub fn add_tool_response(
-        &mut self,
-        response: String,
-        tool_calls: Vec<crate::llm::LLMToolCall>,
-    ) {
-        self.responses.push_back(response);
-        self.tool_calls.push_back(Some(tool_calls));
+        self.chat_with_options(messages, model_id, tools, &LLMRequestOptions::default())
+            .await
     }
-}
 
-#[async_trait]
-impl crate::llm::LLMClient for MockLLMClient {
-    async fn chat(
+    async fn chat_with_options(
         &self,
-        _messages: &[crate::llm::LLMMessage],
-        _model_id: &str,
+        messages: &[crate::llm::LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<crate::llm::LLMTool>>,
+        options: &LLMRequestOptions,
     ) -> Result<crate::llm::LLMResponse> {
-        let response = self
-            .responses
-            .front()
-            .cloned()
-            .unwrap_or_else(|| "Mock response".to_string());
-
-        Ok(crate::llm::LLMResponse {
-            response,
-            tool_calls: None,
-            model: "mock-model".to_string(),
-            usage: Some(crate::llm::LLMUsage {
-                input_tokens: 10,
-                output_tokens: 20,
-                total_tokens: 30,
-            }),
-            response_time: std::time::Duration::from_millis(100),
-        })
-    }
+        let sampling = self.sampling_for(options);
+        sampling.validate()?;
 
-    async fn chat_with_tools(
-        &self,
-        _messages: &[crate::llm::LLMMessage],
-        _model_id: &str,
-        _tools: Option<Vec<crate::llm::LLMTool>>,
-    ) -> Result<crate::llm::LLMResponse> {
-        let response = self
-            .responses
-            .front()
-            .cloned()
-            .unwrap_or_else(|| "Mock tool response".to_string());
-
-        let tool_calls = self.tool_calls.front().cloned().flatten();
-
-        Ok(crate::llm::LLMResponse {
-            response,
-            tool_calls,
-            model: "mock-model".to_string(),
-            usage: Some(crate::llm::LLMUsage {
-                input_tokens: 15,
-                output_tokens: 25,
-                total_tokens: 40,
-            }),
-            response_time: std::time::Duration::from_millis(150),
-        })
+        let offered_tools = tools.clone().unwrap_or_default();
+        let mut messages = Self::convert_messages(messages, model_id);
+        let start_time = std::time::Instant::now();
+        let mut attempt = 0;
+        // Usage of earlier attempts that were answered with a corrective message
+        let mut retry_usage: Option<crate::llm::LLMUsage> = None;
+
+        loop {
+            let mut request = LLMRequest::new(
+                model_id,
+                messages.clone(),
+                tools.as_ref().map(|t| Self::convert_tools(t)),
+                &sampling,
+            );
+            // Other models get the schema through the prompt only
+            if structured::supports_json_mode(model_id) {
+                request.response_format = options.response_format.clone();
+            }
+
+            let response = self.make_request(request).await?;
+            let mut result = crate::llm::LLMResponse {
+                response: response.response,
+                tool_calls: response.tool_calls,
+                model: response.model,
+                usage: response.usage.map(|u| crate::llm::LLMUsage {
+                    input_tokens: u.prompt_tokens,
+                    output_tokens: u.completion_tokens,
+                    total_tokens: u.total_tokens,
+                }),
+                response_time: start_time.elapsed(),
+                cache: None,
+            };
+
+            if let Some(previous) = &retry_usage {
+                let usage = result.usage.get_or_insert_with(Default::default);
+                usage.input_tokens += previous.input_tokens;
+                usage.output_tokens += previous.output_tokens;
+                usage.total_tokens += previous.total_tokens;
+            }
+
+            // Native function calling worked, or there was nothing to call
+            let has_native_calls = result
+                .tool_calls
+                .as_ref()
+                .map(|calls| !calls.is_empty())
+                .unwrap_or(false);
+            if has_native_calls || offered_tools.is_empty() {
+                return Ok(result);
+            }
+
+            // Fall back to tool calls written into the response text
+            match tool_parsing::extract_tool_calls(&result.response, &offered_tools) {
+                ToolCallExtraction::None => return Ok(result),
+                ToolCallExtraction::Calls {
+                    calls,
+                    remaining_text,
+                } => {
+                    result.response = remaining_text;
+                    result.tool_calls = Some(calls);
+                    return Ok(result);
+                }
+                ToolCallExtraction::Invalid { errors } => {
+                    if attempt >= self.max_tool_parse_retries {
+                        eprintln!(
+                            "Giving up on malformed tool call after {} correction(s): {}",
+                            attempt,
+                            errors.join("; ")
+                        );
+                        return Ok(result);
+                    }
+
+                    attempt += 1;
+                    retry_usage = result.usage.clone();
+                    messages.push(crate::llm::LLMMessage {
+                        role: "assistant".to_string(),
+                        content: result.response.into(),
+                        tool_calls: None,
+                    });
+                    messages.push(crate::llm::LLMMessage {
+                        role: "user".to_string(),
+                        content: tool_parsing::corrective_message(&errors, &offered_tools).into(),
+                        tool_calls: None,
+                    });
+                }
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::LLMClient;
 
     #[tokio::test]
     async fn test_http_client_creation() {
@@ -316,58 +374,70 @@ mod tests {
         assert_eq!(client.base_url(), "http://localhost:8787");
     }
 
-    #[tokio::test]
-    async fn test_mock_client() {
-        let mut mock = MockLLMClient::new();
-        mock.add_response("Hello from mock!".to_string());
-
+    #[test]
+    fn test_images_flattened_for_text_models() {
         let messages = vec![crate::llm::LLMMessage {
             role: "user".to_string(),
-            content: "Hello".to_string(),
+            content: vec![
+                crate::core::ContentPart::text("Look"),
+                crate::core::ContentPart::image_base64("image/png", "iVBORw0KGgo="),
+            ]
+            .into(),
             tool_calls: None,
         }];
 
-        let response = mock.chat(&messages, "test-model").await.unwrap();
-        assert_eq!(response.response, "Hello from mock!");
-        assert_eq!(response.model, "mock-model");
-        assert!(response.usage.is_some());
+        let vision =
+            HttpClient::convert_messages(&messages, "@cf/meta/llama-3.2-11b-vision-instruct");
+        assert_eq!(vision[0].content.image_count(), 1);
+
+        let text = HttpClient::convert_messages(&messages, "@cf/meta/llama-3.1-8b-instruct");
+        assert_eq!(text[0].content.as_text(), "Look\n[image omitted]");
     }
 
-    #[tokio::test]
-    async fn test_mock_client_with_tools() {
-        let mut mock = MockLLMClient::new();
-        mock.add_tool_response(
-            "I'll use the tool".to_string(),
-            vec![crate::llm::LLMToolCall {
-                name: "test_tool".to_string(),
-                arguments: serde_json::json!({"param": "value"}),
-                id: Some("call_1".to_string()),
-            }],
-        );
+    #[test]
+    fn test_request_carries_sampling_params() {
+        let sampling = SamplingParams {
+            temperature: Some(0.0),
+            top_p: Some(0.9),
+            max_tokens: Some(256),
+            stop: vec!["</answer>".to_string()],
+            seed: Some(42),
+        };
+        let request = LLMRequest::new("m", Vec::new(), None, &sampling);
+        let body = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(body["temperature"], 0.0);
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["stop"][0], "</answer>");
+        assert_eq!(body["seed"], 42);
+
+        let defaults = serde_json::to_value(LLMRequest::new(
+            "m",
+            Vec::new(),
+            None,
+            &SamplingParams::default(),
+        ))
+        .unwrap();
+        assert!(defaults.get("seed").is_none());
+        assert!(defaults.get("stop").is_none());
+    }
 
-        let messages = vec![crate::llm::LLMMessage {
-            role: "user".to_string(),
-            content: "Use a tool".to_string(),
-            tool_calls: None,
-        }];
+    #[test]
+    fn test_client_default_sampling_applies_without_options() {
+        let mut client = HttpClient::new("http://localhost:8787".to_string());
+        client.set_default_sampling(SamplingParams::deterministic());
 
-        let tools = vec![crate::llm::LLMTool {
-            name: "test_tool".to_string(),
-            description: "A test tool".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "param": {"type": "string"}
-                }
-            }),
-        }];
+        let sampling = client.sampling_for(&LLMRequestOptions::default());
+        assert_eq!(sampling, SamplingParams::deterministic());
 
-        let response = mock
-            .chat_with_tools(&messages, "test-model", Some(tools))
-            .await
-            .unwrap();
-        assert_eq!(response.response, "I'll use the tool");
-        assert!(response.tool_calls.is_some());
-        assert_eq!(response.tool_calls.as_ref().unwrap().len(), 1);
+        let explicit = SamplingParams {
+            temperature: Some(0.7),
+            ..SamplingParams::default()
+        };
+        let options = LLMRequestOptions {
+            sampling: Some(explicit.clone()),
+            ..LLMRequestOptions::default()
+        };
+        assert_eq!(client.sampling_for(&options), explicit);
     }
 }
@@ -0,0 +1,567 @@
+//! Context-window management for long agent runs
+//!
+//! Estimates the size of the transcript sent to the model and, when it would
+//! overflow the model's context window, applies the configured strategies in
+//! order until it fits: dropping raw HTML from observations, truncating old
+//! observations and summarizing older turns with the LLM.
+
+use crate::core::{LLMClient, LLMMessage, LLMUsage, MessageContent, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<(script|style)[^>]*>.*?</(script|style)>|<[^>]+>").expect("valid regex")
+});
+static WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").expect("valid regex"));
+
+/// Known context window sizes (in tokens) for Workers AI models
+const MODEL_CONTEXT_LIMITS: &[(&str, usize)] = &[
+    ("@cf/meta/llama-3.1-8b-instruct", 7_968),
+    ("@cf/meta/llama-3.1-8b-instruct-fast", 128_000),
+    ("@cf/meta/llama-3.1-70b-instruct", 24_000),
+    ("@cf/meta/llama-3.3-70b-instruct-fp8-fast", 24_000),
+    ("@cf/meta/llama-4-scout-17b-16e-instruct", 131_000),
+    ("@cf/mistral/mistral-7b-instruct-v0.2", 32_768),
+    ("@hf/nousresearch/hermes-2-pro-mistral-7b", 24_000),
+    ("@cf/qwen/qwen2.5-coder-32b-instruct", 32_768),
+];
+
+/// Context limit used for models missing from the table
+pub const DEFAULT_CONTEXT_LIMIT: usize = 8_000;
+
+/// Fixed per-message overhead for role markers and separators
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Rough token cost of one attached image
+const IMAGE_TOKENS: usize = 1000;
+
+/// Look up the context window size for a model
+pub fn context_limit_for_model(model_id: &str) -> usize {
+    MODEL_CONTEXT_LIMITS
+        .iter()
+        .find(|(id, _)| *id == model_id)
+        .map(|(_, limit)| *limit)
+        .unwrap_or(DEFAULT_CONTEXT_LIMIT)
+}
+
+/// Approximate token count for a piece of text.
+///
+/// Uses the common ~4 characters per token rule, bumped up for text with many
+/// short words or symbols where that rule underestimates.
+pub fn estimate_tokens(text: &str) -> usize {
+    let chars = text.chars().count();
+    let words = text.split_whitespace().count();
+    let by_chars = chars.div_ceil(4);
+    let by_words = (words * 4).div_ceil(3);
+    by_chars.max(by_words)
+}
+
+/// Approximate token count for a transcript
+pub fn estimate_message_tokens(messages: &[LLMMessage]) -> usize {
+    messages
+        .iter()
+        .map(|m| {
+            let tool_call_tokens = m
+                .tool_calls
+                .as_ref()
+                .map(|calls| estimate_tokens(&serde_json::to_string(calls).unwrap_or_default()))
+                .unwrap_or(0);
+            estimate_tokens(&m.content.as_text())
+                + m.content.image_count() * IMAGE_TOKENS
+                + tool_call_tokens
+                + MESSAGE_OVERHEAD_TOKENS
+        })
+        .sum()
+}
+
+/// Strategy for shrinking a transcript that no longer fits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Remove raw HTML from tool observations
+    DropRawHtml,
+    /// Truncate tool observations outside the most recent turns
+    TruncateObservations,
+    /// Replace older turns with an LLM-written summary
+    SummarizeOlderTurns,
+}
+
+impl std::fmt::Display for ContextStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ContextStrategy::DropRawHtml => "drop_raw_html",
+            ContextStrategy::TruncateObservations => "truncate_observations",
+            ContextStrategy::SummarizeOlderTurns => "summarize_older_turns",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Context management settings for an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Override for the model's context window (tokens)
+    pub context_limit: Option<usize>,
+    /// Tokens kept free for the model's answer
+    pub reserve_output_tokens: usize,
+    /// Strategies to apply, in order, until the transcript fits
+    pub strategies: Vec<ContextStrategy>,
+    /// Number of most recent messages that are never truncated or summarized
+    pub keep_recent_messages: usize,
+    /// Maximum characters kept when truncating an observation
+    pub max_observation_chars: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            context_limit: None,
+            reserve_output_tokens: 1024,
+            strategies: vec![
+                ContextStrategy::DropRawHtml,
+                ContextStrategy::TruncateObservations,
+                ContextStrategy::SummarizeOlderTurns,
+            ],
+            keep_recent_messages: 4,
+            max_observation_chars: 2000,
+        }
+    }
+}
+
+/// What the context manager did to a transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextReport {
+    /// Strategies that changed the transcript
+    pub strategies_applied: Vec<ContextStrategy>,
+    /// Estimated tokens before management
+    pub tokens_before: usize,
+    /// Estimated tokens after management
+    pub tokens_after: usize,
+    /// Token budget the transcript had to fit into
+    pub budget: usize,
+    /// Usage of any LLM calls made while managing the context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_usage: Option<LLMUsage>,
+}
+
+impl ContextReport {
+    /// Human-readable summary for execution steps
+    pub fn describe(&self) -> String {
+        let applied: Vec<String> = self
+            .strategies_applied
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        format!(
+            "Context management: applied {} (~{} -> ~{} tokens, budget {})",
+            applied.join(", "),
+            self.tokens_before,
+            self.tokens_after,
+            self.budget
+        )
+    }
+}
+
+/// Applies context strategies to transcripts before LLM calls
+#[derive(Debug, Clone, Default)]
+pub struct ContextManager {
+    config: ContextConfig,
+}
+
+impl ContextManager {
+    pub fn new(config: ContextConfig) -> Self {
+        Self { config }
+    }
+
+    /// Token budget available for the prompt when calling `model_id`
+    pub fn budget_for(&self, model_id: &str) -> usize {
+        let limit = self
+            .config
+            .context_limit
+            .unwrap_or_else(|| context_limit_for_model(model_id));
+        limit.saturating_sub(self.config.reserve_output_tokens)
+    }
+
+    /// Shrink `messages` in place so they fit the model's context window.
+    ///
+    /// Returns a report when any strategy changed the transcript.
+    pub async fn prepare(
+        &self,
+        messages: &mut Vec<LLMMessage>,
+        model_id: &str,
+        llm: &dyn LLMClient,
+    ) -> Result<Option<ContextReport>> {
+        let budget = self.budget_for(model_id);
+        let tokens_before = estimate_message_tokens(messages);
+        if tokens_before <= budget {
+            return Ok(None);
+        }
+
+        let mut applied = Vec::new();
+        let mut llm_usage = None;
+        for strategy in &self.config.strategies {
+            let changed = match strategy {
+                ContextStrategy::DropRawHtml => self.drop_raw_html(messages),
+                ContextStrategy::TruncateObservations => self.truncate_observations(messages),
+                ContextStrategy::SummarizeOlderTurns => {
+                    match self.summarize_older_turns(messages, model_id, llm).await? {
+                        Some(usage) => {
+                            llm_usage = usage;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+            };
+            if changed {
+                applied.push(*strategy);
+            }
+            if estimate_message_tokens(messages) <= budget {
+                break;
+            }
+        }
+
+        if applied.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(ContextReport {
+            strategies_applied: applied,
+            tokens_before,
+            tokens_after: estimate_message_tokens(messages),
+            budget,
+            llm_usage,
+        }))
+    }
+
+    /// Index of the first message protected from truncation and summarization
+    ///
+    /// Tool results stay with the assistant message that requested them, since
+    /// providers reject results whose call is no longer in the transcript.
+    fn recent_start(&self, messages: &[LLMMessage]) -> usize {
+        let mut start = messages
+            .len()
+            .saturating_sub(self.config.keep_recent_messages);
+        while start > 0 && start < messages.len() && messages[start].role == "tool" {
+            start -= 1;
+        }
+        start
+    }
+
+    fn drop_raw_html(&self, messages: &mut [LLMMessage]) -> bool {
+        let mut changed = false;
+        for message in messages.iter_mut().filter(|m| m.role == "tool") {
+            if let MessageContent::Text(text) = &mut message.content {
+                if let Some(stripped) = strip_html(text) {
+                    *text = stripped;
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    fn truncate_observations(&self, messages: &mut [LLMMessage]) -> bool {
+        let recent_start = self.recent_start(messages);
+        let max_chars = self.config.max_observation_chars;
+        let mut changed = false;
+
+        for message in messages[..recent_start]
+            .iter_mut()
+            .filter(|m| m.role == "tool")
+        {
+            let MessageContent::Text(text) = &mut message.content else {
+                continue;
+            };
+            if let Some(truncated) = truncate_observation(text, max_chars) {
+                *text = truncated;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Returns `None` when there was nothing to summarize, otherwise the
+    /// usage reported for the summarization call.
+    async fn summarize_older_turns(
+        &self,
+        messages: &mut Vec<LLMMessage>,
+        model_id: &str,
+        llm: &dyn LLMClient,
+    ) -> Result<Option<Option<LLMUsage>>> {
+        // Keep the leading system prompt and the most recent turns verbatim
+        let first = usize::from(
+            messages
+                .first()
+                .map(|m| m.role == "system")
+                .unwrap_or(false),
+        );
+        let recent_start = self.recent_start(messages).max(first);
+        if recent_start.saturating_sub(first) < 2 {
+            return Ok(None);
+        }
+
+        let transcript: String = messages[first..recent_start]
+            .iter()
+            .map(|m| format!("{}: {}", m.role, m.content))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let request = vec![
+            LLMMessage {
+                role: "system".to_string(),
+                content: "Summarize the following agent transcript in a few sentences. \
+                          Keep facts, tool results and decisions that later steps may need."
+                    .into(),
+                tool_calls: None,
+            },
+            LLMMessage {
+                role: "user".to_string(),
+                content: transcript.into(),
+                tool_calls: None,
+            },
+        ];
+        let summary = llm.chat(&request, model_id).await?;
+
+        messages.drain(first..recent_start);
+        messages.insert(
+            first,
+            LLMMessage {
+                role: "user".to_string(),
+                content: format!("Summary of earlier conversation: {}", summary.response).into(),
+                tool_calls: None,
+            },
+        );
+        Ok(Some(summary.usage))
+    }
+}
+
+/// Remove raw HTML from an observation, returning `None` if there was none
+fn strip_html(content: &str) -> Option<String> {
+    // Structured observations carry HTML in dedicated fields
+    if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(content) {
+        return if remove_html_fields(&mut value) > 0 {
+            serde_json::to_string(&value).ok()
+        } else {
+            None
+        };
+    }
+
+    if !HTML_TAG.is_match(content) {
+        return None;
+    }
+    let text = HTML_TAG.replace_all(content, " ");
+    Some(WHITESPACE.replace_all(&text, " ").trim().to_string())
+}
+
+/// Shorten an observation to about `max_chars`, keeping JSON observations valid JSON
+fn truncate_observation(text: &str, max_chars: usize) -> Option<String> {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return None;
+    }
+
+    let Ok(value) = serde_json::from_str::<Value>(text) else {
+        let kept: String = text.chars().take(max_chars).collect();
+        return Some(format!(
+            "{}\n[truncated {} characters to save context]",
+            kept,
+            total - max_chars
+        ));
+    };
+
+    // Cut long strings and arrays inside the value rather than the serialized text
+    let shrunk = shrink_json(&value, max_chars).to_string();
+    (shrunk != text).then_some(shrunk)
+}
+
+/// Copy of `value` with strings cut to `limit` characters and arrays to a few items
+fn shrink_json(value: &Value, limit: usize) -> Value {
+    match value {
+        Value::String(text) => {
+            let total = text.chars().count();
+            if total <= limit {
+                return value.clone();
+            }
+            let kept: String = text.chars().take(limit).collect();
+            Value::String(format!("{}[truncated {} characters]", kept, total - limit))
+        }
+        Value::Array(items) => {
+            let keep = (limit / 100).max(1);
+            let mut shrunk: Vec<Value> = items
+                .iter()
+                .take(keep)
+                .map(|item| shrink_json(item, limit))
+                .collect();
+            if items.len() > keep {
+                shrunk.push(Value::String(format!(
+                    "[truncated {} more items]",
+                    items.len() - keep
+                )));
+            }
+            Value::Array(shrunk)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, child)| (key.clone(), shrink_json(child, limit)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Recursively remove `html`/`raw_html` fields, returning how many were removed
+fn remove_html_fields(value: &mut serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut removed = usize::from(map.remove("html").is_some())
+                + usize::from(map.remove("raw_html").is_some());
+            for child in map.values_mut() {
+                removed += remove_html_fields(child);
+            }
+            removed
+        }
+        serde_json::Value::Array(items) => items.iter_mut().map(remove_html_fields).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLLMClient;
+
+    fn message(role: &str, content: &str) -> LLMMessage {
+        LLMMessage {
+            role: role.to_string(),
+            content: content.into(),
+            tool_calls: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 2);
+        assert!(estimate_tokens(&"word ".repeat(100)) >= 125);
+    }
+
+    #[test]
+    fn test_context_limit_lookup() {
+        assert_eq!(
+            context_limit_for_model("@cf/meta/llama-3.1-8b-instruct"),
+            7_968
+        );
+        assert_eq!(context_limit_for_model("unknown"), DEFAULT_CONTEXT_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_small_transcript_is_untouched() {
+        let manager = ContextManager::default();
+        let llm = MockLLMClient::new();
+        let mut messages = vec![message("system", "You help."), message("user", "Hi")];
+
+        let report = manager
+            .prepare(&mut messages, "@cf/meta/llama-3.1-8b-instruct", &llm)
+            .await
+            .unwrap();
+        assert!(report.is_none());
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_drops_html_then_truncates_old_observations() {
+        let manager = ContextManager::new(ContextConfig {
+            context_limit: Some(500),
+            reserve_output_tokens: 0,
+            strategies: vec![
+                ContextStrategy::DropRawHtml,
+                ContextStrategy::TruncateObservations,
+            ],
+            keep_recent_messages: 1,
+            max_observation_chars: 100,
+        });
+        let llm = MockLLMClient::new();
+        let observation = serde_json::json!({
+            "success": true,
+            "data": {
+                "content": "x".repeat(4_000),
+                "html": format!("<html>{}</html>", "y".repeat(4_000)),
+            },
+        })
+        .to_string();
+        let mut messages = vec![
+            message("system", "You research."),
+            message("tool", &observation),
+            message("user", "Continue"),
+        ];
+
+        let report = manager
+            .prepare(&mut messages, "any-model", &llm)
+            .await
+            .unwrap()
+            .expect("transcript should have been managed");
+
+        assert_eq!(
+            report.strategies_applied,
+            vec![
+                ContextStrategy::DropRawHtml,
+                ContextStrategy::TruncateObservations
+            ]
+        );
+        assert!(!messages[1].content.as_text().contains("<html>"));
+        assert!(messages[1].content.as_text().contains("[truncated"));
+        let truncated: serde_json::Value =
+            serde_json::from_str(&messages[1].content.as_text()).expect("still valid JSON");
+        assert_eq!(truncated["success"], true);
+        assert!(report.tokens_after <= report.budget);
+        assert!(report.describe().contains("drop_raw_html"));
+    }
+
+    #[tokio::test]
+    async fn test_summary_keeps_tool_results_with_their_call() {
+        let manager = ContextManager::new(ContextConfig {
+            context_limit: Some(200),
+            reserve_output_tokens: 0,
+            strategies: vec![ContextStrategy::SummarizeOlderTurns],
+            keep_recent_messages: 2,
+            max_observation_chars: 2000,
+        });
+        let mut llm = MockLLMClient::new();
+        llm.add_response("Searched twice.".to_string());
+
+        let call = |id: &str| LLMMessage {
+            role: "assistant".to_string(),
+            content: "".into(),
+            tool_calls: Some(vec![crate::core::LLMToolCall {
+                name: "web_search".to_string(),
+                arguments: serde_json::json!({"query": id}),
+                id: Some(id.to_string()),
+            }]),
+        };
+        let mut messages = vec![
+            message("system", "You research."),
+            message("user", &"Find things. ".repeat(50)),
+            call("first"),
+            message("tool", &"result one ".repeat(50)),
+            call("second"),
+            message("tool", &"result two ".repeat(20)),
+            message("tool", &"result three ".repeat(20)),
+        ];
+
+        manager
+            .prepare(&mut messages, "any-model", &llm)
+            .await
+            .unwrap()
+            .expect("transcript should have been summarized");
+
+        // The cut moved back to the assistant message owning both recent results
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user", "assistant", "tool", "tool"]);
+        assert!(messages[1].content.as_text().contains("Searched twice."));
+        assert!(messages[2].tool_calls.is_some());
+    }
+}
@@ -0,0 +1,178 @@
+//! Stable fingerprints of LLM requests
+//!
+//! Requests are normalized (whitespace trimmed, tool call IDs dropped, tools
+//! sorted by name, sampling and response format included when set) and hashed
+//! with FNV-1a so keys stay identical across runs, platforms and compiler
+//! versions.
+
+use crate::core::{ContentPart, LLMMessage, LLMRequestOptions, LLMTool, MessageContent};
+use serde_json::{json, Value};
+
+/// Normalized JSON form of a request, used for hashing and for fixtures
+pub fn normalized_request(
+    messages: &[LLMMessage],
+    model_id: &str,
+    tools: Option<&[LLMTool]>,
+    options: Option<&LLMRequestOptions>,
+) -> Value {
+    let messages: Vec<Value> = messages
+        .iter()
+        .map(|m| {
+            let tool_calls: Vec<Value> = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|c| json!({"name": c.name, "arguments": c.arguments}))
+                .collect();
+            json!({
+                "role": m.role,
+                "content": normalized_content(&m.content),
+                "tool_calls": tool_calls,
+            })
+        })
+        .collect();
+
+    let tools = tools.map(|tools| {
+        let mut tools: Vec<Value> = tools
+            .iter()
+            .map(|t| json!({"name": t.name, "description": t.description, "parameters": t.parameters}))
+            .collect();
+        tools.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        tools
+    });
+
+    let mut request = json!({
+        "model": model_id,
+        "messages": messages,
+        "tools": tools,
+    });
+    // Only added when set so keys of plain requests stay unchanged. The cache
+    // bypass flag doesn't affect the response, so it's never part of the key.
+    if let Some(options) = options {
+        if let Some(sampling) = &options.sampling {
+            request["sampling"] = serde_json::to_value(sampling).unwrap_or(Value::Null);
+        }
+        if let Some(format) = &options.response_format {
+            request["response_format"] = serde_json::to_value(format).unwrap_or(Value::Null);
+        }
+    }
+    request
+}
+
+fn normalized_content(content: &MessageContent) -> Value {
+    match content {
+        MessageContent::Text(text) => json!(text.trim()),
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({"type": "text", "text": text.trim()}),
+                image => serde_json::to_value(image).unwrap_or(Value::Null),
+            })
+            .collect(),
+    }
+}
+
+/// Hex-encoded FNV-1a hash of a JSON value's canonical serialization
+pub fn hash_value(value: &Value) -> String {
+    // serde_json maps are sorted by key, so serialization is canonical
+    let canonical = serde_json::to_string(value).unwrap_or_default();
+    format!("{:016x}", fnv1a(canonical.as_bytes()))
+}
+
+/// Fingerprint of a request
+pub fn request_key(
+    messages: &[LLMMessage],
+    model_id: &str,
+    tools: Option<&[LLMTool]>,
+    options: Option<&LLMRequestOptions>,
+) -> String {
+    hash_value(&normalized_request(messages, model_id, tools, options))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::LLMToolCall;
+
+    fn message(content: &str, call_id: Option<&str>) -> LLMMessage {
+        LLMMessage {
+            role: "assistant".to_string(),
+            content: content.into(),
+            tool_calls: Some(vec![LLMToolCall {
+                name: "web_search".to_string(),
+                arguments: json!({"query": "rust"}),
+                id: call_id.map(str::to_string),
+            }]),
+        }
+    }
+
+    fn tool(name: &str) -> LLMTool {
+        LLMTool {
+            name: name.to_string(),
+            description: String::new(),
+            parameters: json!({}),
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_whitespace_call_ids_and_tool_order() {
+        let a = request_key(
+            &[message("Searching ", Some("call_1"))],
+            "m",
+            Some(&[tool("a"), tool("b")]),
+            None,
+        );
+        let b = request_key(
+            &[message("Searching", Some("call_2"))],
+            "m",
+            Some(&[tool("b"), tool("a")]),
+            None,
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 16);
+    }
+
+    #[test]
+    fn test_key_depends_on_model_and_content() {
+        let base = request_key(&[message("x", None)], "m1", None, None);
+        assert_ne!(base, request_key(&[message("x", None)], "m2", None, None));
+        assert_ne!(base, request_key(&[message("y", None)], "m1", None, None));
+        assert_eq!(
+            base,
+            request_key(
+                &[message("x", None)],
+                "m1",
+                None,
+                Some(&LLMRequestOptions {
+                    bypass_cache: true,
+                    ..Default::default()
+                })
+            )
+        );
+        assert_ne!(
+            base,
+            request_key(&[message("x", None)], "m1", Some(&[]), None)
+        );
+        assert_ne!(
+            base,
+            request_key(
+                &[message("x", None)],
+                "m1",
+                None,
+                Some(&LLMRequestOptions {
+                    sampling: Some(crate::core::SamplingParams::deterministic()),
+                    ..Default::default()
+                })
+            )
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Scriptable mock LLM client for deterministic tests
+//!
+//! Responses are consumed in order, so a test can script a multi-turn run such
+//! as "call tool A, then call tool B, then answer". Matchers can pick a
+//! response based on the last message instead, and every request is recorded
+//! so tests can assert on what the agent actually sent.
+
+use crate::core::{
+    AppError, LLMClient, LLMMessage, LLMResponse, LLMTool, LLMToolCall, LLMUsage, Result,
+};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// A response the mock will return
+#[derive(Debug, Clone)]
+pub struct ScriptedResponse {
+    pub response: String,
+    pub tool_calls: Option<Vec<LLMToolCall>>,
+    /// Usage to report; a fixed default is reported when `None`
+    pub usage: Option<LLMUsage>,
+}
+
+impl ScriptedResponse {
+    /// A plain text answer
+    pub fn text(response: impl Into<String>) -> Self {
+        Self {
+            response: response.into(),
+            tool_calls: None,
+            usage: None,
+        }
+    }
+
+    /// A response that calls tools
+    pub fn tool_calls(response: impl Into<String>, tool_calls: Vec<LLMToolCall>) -> Self {
+        Self {
+            response: response.into(),
+            tool_calls: Some(tool_calls),
+            usage: None,
+        }
+    }
+
+    /// Report specific usage for this response
+    pub fn with_usage(mut self, usage: LLMUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+}
+
+/// A request received by the mock
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub messages: Vec<LLMMessage>,
+    pub model: String,
+    /// Tools offered with the request (`None` for plain `chat` calls)
+    pub tools: Option<Vec<LLMTool>>,
+}
+
+impl RecordedRequest {
+    /// The last message of the request, if any
+    pub fn last_message(&self) -> Option<&LLMMessage> {
+        self.messages.last()
+    }
+}
+
+type MessagePredicate = Box<dyn Fn(&LLMMessage) -> bool + Send + Sync>;
+
+struct Matcher {
+    predicate: MessagePredicate,
+    response: ScriptedResponse,
+}
+
+/// Mock LLM client for testing
+pub struct MockLLMClient {
+    script: Mutex<VecDeque<ScriptedResponse>>,
+    matchers: Vec<Matcher>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockLLMClient {
+    pub fn new() -> Self {
+        Self {
+            script: Mutex::new(VecDeque::new()),
+            matchers: Vec::new(),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Append a plain text answer to the script
+    pub fn add_response(&mut self, response: String) {
+        self.push(ScriptedResponse::text(response));
+    }
+
+    /// Append a tool-calling response to the script
+    pub fn add_tool_response(&mut self, response: String, tool_calls: Vec<LLMToolCall>) {
+        self.push(ScriptedResponse::tool_calls(response, tool_calls));
+    }
+
+    /// Append a response to the script (usable while the mock is shared)
+    pub fn push(&self, response: ScriptedResponse) {
+        self.script
+            .lock()
+            .expect("mock script lock poisoned")
+            .push_back(response);
+    }
+
+    /// Answer with `response` whenever the last message satisfies `predicate`.
+    ///
+    /// Matchers are checked before the script and are not consumed.
+    pub fn add_matcher<F>(&mut self, predicate: F, response: ScriptedResponse)
+    where
+        F: Fn(&LLMMessage) -> bool + Send + Sync + 'static,
+    {
+        self.matchers.push(Matcher {
+            predicate: Box::new(predicate),
+            response,
+        });
+    }
+
+    /// Answer with `response` whenever the last message contains `text`
+    pub fn when_last_message_contains(&mut self, text: &str, response: ScriptedResponse) {
+        let text = text.to_string();
+        self.add_matcher(
+            move |message| message.content.as_text().contains(text.as_str()),
+            response,
+        );
+    }
+
+    /// All requests received so far, in order
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .expect("mock request lock poisoned")
+            .clone()
+    }
+
+    /// Number of requests received so far
+    pub fn request_count(&self) -> usize {
+        self.requests
+            .lock()
+            .expect("mock request lock poisoned")
+            .len()
+    }
+
+    /// Number of scripted responses not yet consumed
+    pub fn remaining(&self) -> usize {
+        self.script.lock().expect("mock script lock poisoned").len()
+    }
+
+    fn next_response(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+    ) -> Result<ScriptedResponse> {
+        let request_number = {
+            let mut requests = self.requests.lock().expect("mock request lock poisoned");
+            requests.push(RecordedRequest {
+                messages: messages.to_vec(),
+                model: model_id.to_string(),
+                tools,
+            });
+            requests.len()
+        };
+
+        if let Some(last) = messages.last() {
+            if let Some(matcher) = self.matchers.iter().find(|m| (m.predicate)(last)) {
+                return Ok(matcher.response.clone());
+            }
+        }
+
+        self.script
+            .lock()
+            .expect("mock script lock poisoned")
+            .pop_front()
+            .ok_or_else(|| {
+                AppError::LLM(format!(
+                    "MockLLMClient script exhausted: request #{} has no scripted response (last message: {:?})",
+                    request_number,
+                    messages
+                        .last()
+                        .map(|m| m.content.to_string())
+                        .unwrap_or_default()
+                ))
+            })
+    }
+}
+
+impl Default for MockLLMClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LLMClient for MockLLMClient {
+    async fn chat(&self, messages: &[LLMMessage], model_id: &str) -> Result<LLMResponse> {
+        let scripted = self.next_response(messages, model_id, None)?;
+
+        Ok(LLMResponse {
+            response: scripted.response,
+            tool_calls: None,
+            model: "mock-model".to_string(),
+            usage: Some(scripted.usage.unwrap_or(LLMUsage {
+                input_tokens: 10,
+                output_tokens: 20,
+                total_tokens: 30,
+            })),
+            response_time: std::time::Duration::from_millis(100),
+            cache: None,
+        })
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+    ) -> Result<LLMResponse> {
+        let scripted = self.next_response(messages, model_id, tools)?;
+
+        Ok(LLMResponse {
+            response: scripted.response,
+            tool_calls: scripted.tool_calls,
+            model: "mock-model".to_string(),
+            usage: Some(scripted.usage.unwrap_or(LLMUsage {
+                input_tokens: 15,
+                output_tokens: 25,
+                total_tokens: 40,
+            })),
+            response_time: std::time::Duration::from_millis(150),
+            cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(content: &str) -> Vec<LLMMessage> {
+        vec![LLMMessage {
+            role: "user".to_string(),
+            content: content.into(),
+            tool_calls: None,
+        }]
+    }
+
+    fn call(name: &str) -> LLMToolCall {
+        LLMToolCall {
+            name: name.to_string(),
+            arguments: serde_json::json!({}),
+            id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_client() {
+        let mut mock = MockLLMClient::new();
+        mock.add_response("Hello from mock!".to_string());
+
+        let response = mock.chat(&user("Hello"), "test-model").await.unwrap();
+        assert_eq!(response.response, "Hello from mock!");
+        assert_eq!(response.model, "mock-model");
+        assert!(response.usage.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_with_tools() {
+        let mut mock = MockLLMClient::new();
+        mock.add_tool_response(
+            "I'll use the tool".to_string(),
+            vec![LLMToolCall {
+                name: "test_tool".to_string(),
+                arguments: serde_json::json!({"param": "value"}),
+                id: Some("call_1".to_string()),
+            }],
+        );
+
+        let tools = vec![LLMTool {
+            name: "test_tool".to_string(),
+            description: "A test tool".to_string(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "param": {"type": "string"}
+                }
+            }),
+        }];
+
+        let response = mock
+            .chat_with_tools(&user("Use a tool"), "test-model", Some(tools))
+            .await
+            .unwrap();
+        assert_eq!(response.response, "I'll use the tool");
+        assert!(response.tool_calls.is_some());
+        assert_eq!(response.tool_calls.as_ref().unwrap().len(), 1);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].model, "test-model");
+        assert_eq!(requests[0].tools.as_ref().unwrap()[0].name, "test_tool");
+    }
+
+    #[tokio::test]
+    async fn test_script_is_consumed_in_order() {
+        let mock = MockLLMClient::new();
+        mock.push(ScriptedResponse::tool_calls("a", vec![call("tool_a")]));
+        mock.push(ScriptedResponse::tool_calls("b", vec![call("tool_b")]));
+        mock.push(ScriptedResponse::text("done"));
+
+        let first = mock.chat_with_tools(&user("go"), "m", None).await.unwrap();
+        let second = mock.chat_with_tools(&user("go"), "m", None).await.unwrap();
+        let third = mock.chat_with_tools(&user("go"), "m", None).await.unwrap();
+
+        assert_eq!(first.tool_calls.unwrap()[0].name, "tool_a");
+        assert_eq!(second.tool_calls.unwrap()[0].name, "tool_b");
+        assert_eq!(third.response, "done");
+        assert_eq!(mock.remaining(), 0);
+        assert_eq!(mock.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_script_errors() {
+        let mut mock = MockLLMClient::new();
+        mock.add_response("only one".to_string());
+
+        assert!(mock.chat(&user("first"), "m").await.is_ok());
+        let err = mock.chat(&user("second"), "m").await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("script exhausted"));
+        assert!(message.contains("request #2"));
+        assert!(message.contains("second"));
+    }
+
+    #[tokio::test]
+    async fn test_matchers_take_priority_and_persist() {
+        let mut mock = MockLLMClient::new();
+        mock.when_last_message_contains("weather", ScriptedResponse::text("sunny"));
+        mock.add_response("scripted".to_string());
+
+        let matched = mock.chat(&user("what's the weather?"), "m").await.unwrap();
+        let matched_again = mock.chat(&user("weather again"), "m").await.unwrap();
+        let scripted = mock.chat(&user("hello"), "m").await.unwrap();
+
+        assert_eq!(matched.response, "sunny");
+        assert_eq!(matched_again.response, "sunny");
+        assert_eq!(scripted.response, "scripted");
+    }
+}
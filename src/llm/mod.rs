@@ -1,9 +1,27 @@
 //! Enhanced LLM client for the local Rust app
 
+pub mod cache;
 pub mod client;
+pub mod context;
+pub mod fingerprint;
+pub mod mock;
+pub mod replay;
+pub mod structured;
+pub mod tool_parsing;
+pub mod usage;
 
 /// Re-export client types
-pub use client::{HttpClient, MockLLMClient};
+pub use cache::{CacheConfig, CachingClient};
+pub use client::HttpClient;
+pub use context::{ContextConfig, ContextManager, ContextReport, ContextStrategy};
+pub use mock::{MockLLMClient, RecordedRequest, ScriptedResponse};
+pub use replay::{RecordReplayClient, ReplayMode};
+pub use structured::StructuredOutput;
+pub use tool_parsing::{extract_tool_calls, ToolCallExtraction};
+pub use usage::{ExecutionUsage, PriceTable, UsageBudget, UsageSummary};
 
 // Re-export from core module for convenience
-pub use crate::core::{LLMClient, LLMMessage, LLMResponse, LLMTool, LLMToolCall, LLMUsage};
+pub use crate::core::{
+    CacheStatus, ContentPart, LLMClient, LLMMessage, LLMRequestOptions, LLMResponse, LLMTool,
+    LLMToolCall, LLMUsage, MessageContent, ResponseFormat, SamplingParams,
+};
@@ -0,0 +1,324 @@
+//! Record-and-replay LLM client for regression testing
+//!
+//! In record mode every request/response pair from a real backend is appended
+//! to a JSON fixture file. In replay mode responses are served from that file,
+//! keyed by a normalized request hash, so a captured agent session can be
+//! re-run offline in `cargo test`.
+
+use crate::core::{
+    AppError, LLMClient, LLMMessage, LLMRequestOptions, LLMResponse, LLMTool, Result,
+};
+use crate::llm::fingerprint;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Current fixture file format version
+const FIXTURE_VERSION: u32 = 1;
+
+/// `record:<path>` or `replay:<path>` to record LLM calls to, or serve them from, a fixture
+pub const REPLAY_ENV: &str = "CF_AI_LLM_REPLAY";
+
+/// Whether to record a session or replay one, and the fixture file to use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl ReplayMode {
+    /// Parse `record:<path>` or `replay:<path>`
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().split_once(':') {
+            Some(("record", path)) if !path.is_empty() => Ok(Self::Record(path.into())),
+            Some(("replay", path)) if !path.is_empty() => Ok(Self::Replay(path.into())),
+            _ => Err(AppError::Configuration(format!(
+                "{} must be record:<path> or replay:<path>, got '{}'",
+                REPLAY_ENV, value
+            ))),
+        }
+    }
+
+    /// Mode from `CF_AI_LLM_REPLAY`, or `None` when it isn't set
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var(REPLAY_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(&value).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A recorded request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Normalized request hash
+    pub key: String,
+    /// Normalized request, kept for readable diffs of fixtures
+    pub request: serde_json::Value,
+    pub response: LLMResponse,
+}
+
+/// Contents of a fixture file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    pub version: u32,
+    pub interactions: Vec<Interaction>,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            version: FIXTURE_VERSION,
+            interactions: Vec::new(),
+        }
+    }
+}
+
+impl Fixture {
+    /// Load a fixture from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            AppError::Configuration(format!("Cannot read fixture {}: {}", path.display(), e))
+        })?;
+        let fixture: Fixture = serde_json::from_str(&text)?;
+        if fixture.version != FIXTURE_VERSION {
+            return Err(AppError::Configuration(format!(
+                "Fixture {} has version {}, expected {}",
+                path.display(),
+                fixture.version,
+                FIXTURE_VERSION
+            )));
+        }
+        Ok(fixture)
+    }
+
+    /// Write the fixture to disk, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+enum Mode {
+    Record {
+        inner: Arc<dyn LLMClient>,
+        fixture: Mutex<Fixture>,
+    },
+    Replay {
+        /// Remaining responses per request key, served in recorded order
+        responses: Mutex<HashMap<String, VecDeque<LLMResponse>>>,
+    },
+}
+
+/// LLM client that records to or replays from a fixture file
+pub struct RecordReplayClient {
+    path: PathBuf,
+    mode: Mode,
+}
+
+impl RecordReplayClient {
+    /// Record every call made through `inner` into the fixture at `path`.
+    ///
+    /// Any existing fixture at `path` is replaced.
+    pub fn record(inner: Arc<dyn LLMClient>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: Mode::Record {
+                inner,
+                fixture: Mutex::new(Fixture::default()),
+            },
+        }
+    }
+
+    /// Serve responses from the fixture at `path` without any backend
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let fixture = Fixture::load(&path)?;
+
+        let mut responses: HashMap<String, VecDeque<LLMResponse>> = HashMap::new();
+        for interaction in fixture.interactions {
+            responses
+                .entry(interaction.key)
+                .or_default()
+                .push_back(interaction.response);
+        }
+
+        Ok(Self {
+            path,
+            mode: Mode::Replay {
+                responses: Mutex::new(responses),
+            },
+        })
+    }
+
+    /// Record through `inner` or replay without it, as `mode` says
+    pub fn from_mode(mode: ReplayMode, inner: Arc<dyn LLMClient>) -> Result<Self> {
+        match mode {
+            ReplayMode::Record(path) => Ok(Self::record(inner, path)),
+            ReplayMode::Replay(path) => Self::replay(path),
+        }
+    }
+
+    /// Fixture file backing this client
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn handle(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+        options: Option<&LLMRequestOptions>,
+    ) -> Result<LLMResponse> {
+        let request =
+            fingerprint::normalized_request(messages, model_id, tools.as_deref(), options);
+        let key = fingerprint::hash_value(&request);
+
+        match &self.mode {
+            Mode::Record { inner, fixture } => {
+                let response = match (tools, options) {
+                    (tools, Some(options)) => {
+                        inner
+                            .chat_with_options(messages, model_id, tools, options)
+                            .await?
+                    }
+                    (Some(tools), None) => {
+                        inner
+                            .chat_with_tools(messages, model_id, Some(tools))
+                            .await?
+                    }
+                    (None, None) => inner.chat(messages, model_id).await?,
+                };
+
+                // Save after every call so a crashed session still leaves a usable fixture
+                let mut fixture = fixture.lock().await;
+                fixture.interactions.push(Interaction {
+                    key,
+                    request,
+                    response: response.clone(),
+                });
+                fixture.save(&self.path)?;
+
+                Ok(response)
+            }
+            Mode::Replay { responses } => responses
+                .lock()
+                .await
+                .get_mut(&key)
+                .and_then(|queue| queue.pop_front())
+                .ok_or_else(|| {
+                    AppError::LLM(format!(
+                        "No recorded response for request {} in fixture {} (last message: {:?})",
+                        key,
+                        self.path.display(),
+                        messages
+                            .last()
+                            .map(|m| m.content.to_string())
+                            .unwrap_or_default()
+                    ))
+                }),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMClient for RecordReplayClient {
+    async fn chat(&self, messages: &[LLMMessage], model_id: &str) -> Result<LLMResponse> {
+        self.handle(messages, model_id, None, None).await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+    ) -> Result<LLMResponse> {
+        self.handle(messages, model_id, tools, None).await
+    }
+
+    async fn chat_with_options(
+        &self,
+        messages: &[LLMMessage],
+        model_id: &str,
+        tools: Option<Vec<LLMTool>>,
+        options: &LLMRequestOptions,
+    ) -> Result<LLMResponse> {
+        self.handle(messages, model_id, tools, Some(options)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{MockLLMClient, ScriptedResponse};
+
+    fn user(content: &str) -> Vec<LLMMessage> {
+        vec![LLMMessage {
+            role: "user".to_string(),
+            content: content.into(),
+            tool_calls: None,
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roundtrip.json");
+
+        let backend = MockLLMClient::new();
+        backend.push(ScriptedResponse::text("first answer"));
+        backend.push(ScriptedResponse::text("second answer"));
+        backend.push(ScriptedResponse::text("other answer"));
+        let recorder = RecordReplayClient::record(Arc::new(backend), &path);
+
+        recorder.chat(&user("hello"), "m").await.unwrap();
+        recorder.chat(&user("hello"), "m").await.unwrap();
+        recorder
+            .chat_with_tools(&user("other"), "m", Some(vec![]))
+            .await
+            .unwrap();
+
+        let replayer = RecordReplayClient::replay(&path).unwrap();
+        let first = replayer.chat(&user("hello"), "m").await.unwrap();
+        let second = replayer.chat(&user("hello "), "m").await.unwrap();
+        let other = replayer
+            .chat_with_tools(&user("other"), "m", Some(vec![]))
+            .await
+            .unwrap();
+
+        assert_eq!(first.response, "first answer");
+        assert_eq!(second.response, "second answer");
+        assert_eq!(other.response, "other answer");
+
+        // Unrecorded or exhausted requests fail instead of reaching a backend
+        assert!(replayer.chat(&user("hello"), "m").await.is_err());
+        assert!(replayer.chat(&user("new"), "m").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(
+            ReplayMode::parse("record:tests/fixtures/run.json").unwrap(),
+            ReplayMode::Record("tests/fixtures/run.json".into())
+        );
+        assert_eq!(
+            ReplayMode::parse("replay:/tmp/run.json").unwrap(),
+            ReplayMode::Replay("/tmp/run.json".into())
+        );
+        assert!(ReplayMode::parse("replay:").is_err());
+        assert!(ReplayMode::parse("rewind:run.json").is_err());
+    }
+
+    #[test]
+    fn test_replay_missing_fixture_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(RecordReplayClient::replay(dir.path().join("missing.json")).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+//! Structured (JSON) output from LLM calls
+//!
+//! The schema is described to the model in the system prompt and, for models
+//! that support it, also sent as a JSON-mode response format. Answers are
+//! extracted, checked with a small JSON Schema validator and retried with the
+//! validation errors until they match or the retry budget runs out.
+//!
+//! The validator covers the keywords agents actually use: `type`, `enum`,
+//! `const`, `properties`, `required`, `additionalProperties`, `items`,
+//! `minItems`/`maxItems`, `minLength`/`maxLength`, `minimum`/`maximum` and
+//! `anyOf`/`oneOf` (both treated as "at least one matches").
+
+use crate::core::{
+    AppError, LLMClient, LLMMessage, LLMRequestOptions, LLMUsage, ResponseFormat, Result,
+};
+use crate::llm::tool_parsing;
+use serde_json::Value;
+
+/// Corrective round-trips before structured generation gives up
+pub const DEFAULT_STRUCTURED_RETRIES: usize = 2;
+
+/// Workers AI models that accept a JSON-mode `response_format`
+const JSON_MODE_MODELS: &[&str] = &[
+    "@cf/meta/llama-3.1-8b-instruct",
+    "@cf/meta/llama-3.1-8b-instruct-fast",
+    "@cf/meta/llama-3.1-8b-instruct-awq",
+    "@cf/meta/llama-3.1-8b-instruct-fp8",
+    "@cf/meta/llama-3.1-70b-instruct",
+    "@cf/meta/llama-3.3-70b-instruct-fp8-fast",
+    "@cf/meta/llama-3-8b-instruct",
+    "@cf/meta/llama-3-8b-instruct-awq",
+    "@cf/meta/llama-3.2-3b-instruct",
+    "@cf/meta/llama-3.2-11b-vision-instruct",
+    "@hf/nousresearch/hermes-2-pro-mistral-7b",
+    "@cf/deepseek-ai/deepseek-r1-distill-qwen-32b",
+];
+
+/// Whether the backend honours a JSON-mode response format for `model_id`
+pub fn supports_json_mode(model_id: &str) -> bool {
+    JSON_MODE_MODELS.contains(&model_id)
+}
+
+/// Result of a structured generation
+#[derive(Debug, Clone)]
+pub struct StructuredOutput {
+    /// Parsed answer, valid against the schema
+    pub value: Value,
+    /// Model that produced the answer
+    pub model: String,
+    /// Usage reported for each attempt, in order
+    pub usage: Vec<Option<LLMUsage>>,
+}
+
+/// Ask `client` for JSON matching `schema`, retrying up to `max_retries` times
+pub async fn generate<C: LLMClient + ?Sized>(
+    client: &C,
+    messages: &[LLMMessage],
+    model_id: &str,
+    schema: &Value,
+    options: &LLMRequestOptions,
+    max_retries: usize,
+) -> Result<StructuredOutput> {
+    if !schema.is_object() {
+        return Err(AppError::Configuration(
+            "Output schema must be a JSON object".to_string(),
+        ));
+    }
+
+    let mut options = options.clone();
+    options.response_format = Some(ResponseFormat::JsonSchema {
+        json_schema: schema.clone(),
+    });
+
+    let mut messages = with_schema_instructions(messages, schema);
+    let mut usage = Vec::new();
+    let mut errors = Vec::new();
+
+    for _ in 0..=max_retries {
+        let response = client
+            .chat_with_options(&messages, model_id, None, &options)
+            .await?;
+        usage.push(response.usage.clone());
+
+        errors = match extract_json(&response.response) {
+            Some(value) => {
+                let errors = validate(&value, schema);
+                if errors.is_empty() {
+                    return Ok(StructuredOutput {
+                        value,
+                        model: response.model,
+                        usage,
+                    });
+                }
+                errors
+            }
+            None => vec!["Response is not valid JSON".to_string()],
+        };
+
+        messages.push(LLMMessage {
+            role: "assistant".to_string(),
+            content: response.response.into(),
+            tool_calls: None,
+        });
+        messages.push(LLMMessage {
+            role: "user".to_string(),
+            content: corrective_message(&errors).into(),
+            tool_calls: None,
+        });
+    }
+
+    Err(AppError::LLM(format!(
+        "Structured output did not match the schema after {} attempt(s): {}",
+        usage.len(),
+        errors.join("; ")
+    )))
+}
+
+/// Extract a JSON value from a model answer (bare, fenced or surrounded by prose)
+pub fn extract_json(text: &str) -> Option<Value> {
+    let trimmed = text.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    if let Some(fenced) = fenced_block(trimmed) {
+        if let Some(value) = tool_parsing::repair_json(fenced) {
+            return Some(value);
+        }
+    }
+
+    let start = trimmed.find(&['{', '['][..])?;
+    let end = tool_parsing::matching_close(trimmed, start).unwrap_or(trimmed.len());
+    tool_parsing::repair_json(&trimmed[start..end])
+}
+
+/// Validate `value` against `schema`, returning one message per violation
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(value, schema, "$", &mut errors);
+    errors
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(keyword).and_then(|o| o.as_array()) {
+            if !options
+                .iter()
+                .any(|option| validate(value, option).is_empty())
+            {
+                errors.push(format!(
+                    "{}: does not match any of the allowed schemas",
+                    path
+                ));
+            }
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                path,
+                Value::Array(options.clone())
+            ));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{}: must equal {}", path, expected));
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !object.contains_key(key) {
+                        errors.push(format!("{}: missing required property '{}'", path, key));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (key, child) in object {
+                let child_path = format!("{}.{}", path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(child_schema) => validate_at(child, child_schema, &child_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: unexpected property '{}'", path, key))
+                        }
+                        Some(extra) if extra.is_object() => {
+                            validate_at(child, extra, &child_path, errors)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+                if (items.len() as u64) < min {
+                    errors.push(format!("{}: expected at least {} item(s)", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+                if items.len() as u64 > max {
+                    errors.push(format!("{}: expected at most {} item(s)", path, max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item, item_schema, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+                if length < min {
+                    errors.push(format!("{}: expected at least {} character(s)", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+                if length > max {
+                    errors.push(format!("{}: expected at most {} character(s)", path, max));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(|m| m.as_f64()) {
+                if number < min {
+                    errors.push(format!("{}: must be at least {}", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|m| m.as_f64()) {
+                if number > max {
+                    errors.push(format!("{}: must be at most {}", path, max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+/// Append the schema to the system prompt, or add a system prompt holding it
+fn with_schema_instructions(messages: &[LLMMessage], schema: &Value) -> Vec<LLMMessage> {
+    let instructions = format!(
+        "Respond with a single JSON value that matches this JSON Schema, and nothing else:\n{}",
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    );
+
+    let mut messages = messages.to_vec();
+    match messages.first_mut() {
+        Some(first) if first.role == "system" => {
+            first.content.push_text(&instructions);
+        }
+        _ => messages.insert(
+            0,
+            LLMMessage {
+                role: "system".to_string(),
+                content: instructions.into(),
+                tool_calls: None,
+            },
+        ),
+    }
+    messages
+}
+
+fn corrective_message(errors: &[String]) -> String {
+    format!(
+        "Your previous answer did not match the required JSON Schema:\n- {}\nReply again with only the corrected JSON.",
+        errors.join("\n- ")
+    )
+}
+
+fn fenced_block(text: &str) -> Option<&str> {
+    let start = text.find("```")?;
+    let after_fence = start + 3;
+    let body_start = after_fence + text[after_fence..].find('\n')? + 1;
+    let body_end = body_start + text[body_start..].find("```")?;
+    Some(&text[body_start..body_end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{MockLLMClient, ScriptedResponse};
+    use serde_json::json;
+
+    fn report_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "summary": {"type": "string", "minLength": 1},
+                "sources": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {"url": {"type": "string"}},
+                        "required": ["url"]
+                    }
+                },
+                "confidence": {"type": "string", "enum": ["low", "medium", "high"]}
+            },
+            "required": ["summary", "sources"],
+            "additionalProperties": false
+        })
+    }
+
+    fn user(content: &str) -> Vec<LLMMessage> {
+        vec![LLMMessage {
+            role: "user".to_string(),
+            content: content.into(),
+            tool_calls: None,
+        }]
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let value = json!({
+            "summary": "",
+            "sources": [{"title": "no url"}],
+            "confidence": "certain",
+            "extra": 1
+        });
+        let errors = validate(&value, &report_schema());
+
+        assert!(errors.iter().any(|e| e.starts_with("$.summary")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("$.sources[0]") && e.contains("'url'")));
+        assert!(errors.iter().any(|e| e.starts_with("$.confidence")));
+        assert!(errors.iter().any(|e| e.contains("'extra'")));
+
+        let valid = json!({"summary": "ok", "sources": [{"url": "https://example.com"}]});
+        assert!(validate(&valid, &report_schema()).is_empty());
+    }
+
+    #[test]
+    fn test_extract_json_from_prose_and_fences() {
+        assert_eq!(extract_json(r#"{"a": 1}"#), Some(json!({"a": 1})));
+        assert_eq!(
+            extract_json("Here you go:\n```json\n{\"a\": 1,}\n```"),
+            Some(json!({"a": 1}))
+        );
+        assert_eq!(
+            extract_json(r#"The answer is {"a": [1, 2]} as requested."#),
+            Some(json!({"a": [1, 2]}))
+        );
+        assert_eq!(extract_json("no json here"), None);
+    }
+
+    #[tokio::test]
+    async fn test_retries_with_validation_errors() {
+        let mock = MockLLMClient::new();
+        mock.push(ScriptedResponse::text(r#"{"summary": "Rust"}"#));
+        mock.push(ScriptedResponse::text(
+            r#"{"summary": "Rust", "sources": [{"url": "https://rust-lang.org"}]}"#,
+        ));
+
+        let output = generate(
+            &mock,
+            &user("Research Rust"),
+            "m",
+            &report_schema(),
+            &LLMRequestOptions::default(),
+            DEFAULT_STRUCTURED_RETRIES,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output.value["sources"][0]["url"], "https://rust-lang.org");
+        assert_eq!(output.usage.len(), 2);
+
+        let requests = mock.requests();
+        assert!(requests[0].messages[0]
+            .content
+            .as_text()
+            .contains("JSON Schema"));
+        let correction = requests[1].last_message().unwrap();
+        assert!(correction
+            .content
+            .as_text()
+            .contains("missing required property 'sources'"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_retries() {
+        let mock = MockLLMClient::new();
+        for _ in 0..=DEFAULT_STRUCTURED_RETRIES {
+            mock.push(ScriptedResponse::text("not json"));
+        }
+
+        let err = mock
+            .chat_structured(
+                &user("Research Rust"),
+                "m",
+                &report_schema(),
+                &LLMRequestOptions::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("did not match the schema after 3"));
+        assert_eq!(mock.remaining(), 0);
+
+        let result = generate(
+            &MockLLMClient::new(),
+            &user("x"),
+            "m",
+            &json!(true),
+            &LLMRequestOptions::default(),
+            0,
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::Configuration(_))));
+    }
+}
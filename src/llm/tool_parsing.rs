@@ -0,0 +1,690 @@
+//! Tolerant extraction of tool calls embedded in LLM response text
+//!
+//! Many Workers AI models have no native function calling and instead write the
+//! call into the `response` text, either as bare JSON, inside a fenced code
+//! block, or wrapped in `<tool_call>` / `<function=...>` tags. This module
+//! recognises those formats, repairs slightly malformed JSON arguments and
+//! validates the result against the tools that were offered to the model.
+//!
+//! Only explicit markup (`<tool_call>`, `<function=...>` or a ```` ```tool_call ````
+//! fence) can make a response invalid. Unmarked JSON is a call only when it names
+//! an offered tool and carries `arguments`; anything else is ordinary answer text.
+
+use crate::core::{LLMTool, LLMToolCall};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+static TRAILING_COMMA: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r",\s*([}\]])").expect("valid regex"));
+static BARE_KEY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([{,]\s*)([A-Za-z_][A-Za-z0-9_]*)\s*:"#).expect("valid regex"));
+static PYTHON_LITERAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r":\s*(True|False|None)\b").expect("valid regex"));
+
+/// Outcome of scanning a response text for tool calls
+#[derive(Debug, Clone)]
+pub enum ToolCallExtraction {
+    /// The text contains no tool call attempt; treat it as a final answer
+    None,
+    /// One or more valid tool calls were recognised
+    Calls {
+        calls: Vec<LLMToolCall>,
+        /// Response text with the tool call markup removed
+        remaining_text: String,
+    },
+    /// The text attempted a tool call that could not be parsed or validated
+    Invalid { errors: Vec<String> },
+}
+
+/// A tool call candidate found in the text, before validation
+struct Candidate {
+    /// Byte range of the candidate in the original text
+    span: (usize, usize),
+    /// Raw text of the candidate
+    raw: String,
+    /// Tool name if it was given outside the JSON (e.g. `<function=name>`)
+    name_hint: Option<String>,
+    /// Whether markup makes this unambiguously a tool call; only these can be invalid
+    explicit: bool,
+}
+
+/// Extract tool calls from free-form response text.
+pub fn extract_tool_calls(text: &str, tools: &[LLMTool]) -> ToolCallExtraction {
+    let candidates = find_candidates(text);
+    if candidates.is_empty() {
+        return ToolCallExtraction::None;
+    }
+
+    let mut calls = Vec::new();
+    let mut errors = Vec::new();
+    let mut consumed = Vec::new();
+
+    for candidate in candidates {
+        let value = match repair_json(&candidate.raw) {
+            Some(value) => value,
+            None => {
+                if candidate.explicit {
+                    errors.push(format!(
+                        "Could not parse tool call JSON: {}",
+                        truncate(&candidate.raw, 200)
+                    ));
+                }
+                continue;
+            }
+        };
+
+        // Unmarked JSON is answer content unless it clearly calls an offered tool
+        if !candidate.explicit && !is_unmarked_call(&value, tools) {
+            continue;
+        }
+
+        let parsed = match candidate.name_hint {
+            Some(name) => vec![LLMToolCall {
+                name,
+                arguments: normalize_arguments(value),
+                id: None,
+            }],
+            None => parse_call_value(value),
+        };
+
+        if parsed.is_empty() {
+            if candidate.explicit {
+                errors.push(format!(
+                    "Tool call must be an object with \"name\" and \"arguments\" fields: {}",
+                    truncate(&candidate.raw, 200)
+                ));
+            }
+            continue;
+        }
+
+        consumed.push(candidate.span);
+        for call in parsed {
+            match validate_tool_call(call, tools) {
+                Ok(call) => calls.push(call),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return ToolCallExtraction::Invalid { errors };
+    }
+
+    if calls.is_empty() {
+        return ToolCallExtraction::None;
+    }
+
+    ToolCallExtraction::Calls {
+        calls,
+        remaining_text: remove_spans(text, &consumed),
+    }
+}
+
+/// Build the corrective message sent back to the model after a failed parse.
+pub fn corrective_message(errors: &[String], tools: &[LLMTool]) -> String {
+    let available: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    format!(
+        "Your previous tool call could not be processed:\n- {}\n\n\
+         To call a tool, reply with a single JSON object of the form \
+         {{\"name\": \"<tool name>\", \"arguments\": {{...}}}}. \
+         Available tools: {}. If no tool is needed, answer in plain text.",
+        errors.join("\n- "),
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.join(", ")
+        }
+    )
+}
+
+/// Check a parsed call against the offered tools, canonicalising its name.
+pub fn validate_tool_call(
+    mut call: LLMToolCall,
+    tools: &[LLMTool],
+) -> std::result::Result<LLMToolCall, String> {
+    let tool = find_tool(&call.name, tools).ok_or_else(|| {
+        format!(
+            "Unknown tool '{}'. Available tools: {}",
+            call.name,
+            tools
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })?;
+
+    call.name = tool.name.clone();
+
+    if !call.arguments.is_object() {
+        return Err(format!(
+            "Arguments for tool '{}' must be a JSON object",
+            tool.name
+        ));
+    }
+
+    if let Some(required) = tool.parameters.get("required").and_then(|r| r.as_array()) {
+        let missing: Vec<&str> = required
+            .iter()
+            .filter_map(|r| r.as_str())
+            .filter(|r| call.arguments.get(*r).is_none())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Tool '{}' is missing required argument(s): {}",
+                tool.name,
+                missing.join(", ")
+            ));
+        }
+    }
+
+    Ok(call)
+}
+
+/// The offered tool called `name`, tolerating case, spaces and dashes
+fn find_tool<'a>(name: &str, tools: &'a [LLMTool]) -> Option<&'a LLMTool> {
+    tools.iter().find(|t| t.name == name).or_else(|| {
+        let wanted = normalize_name(name);
+        tools.iter().find(|t| normalize_name(&t.name) == wanted)
+    })
+}
+
+/// Whether unmarked JSON is unmistakably a call: an offered tool's `name` plus `arguments`
+fn is_unmarked_call(value: &Value, tools: &[LLMTool]) -> bool {
+    match value {
+        Value::Array(items) => {
+            !items.is_empty() && items.iter().all(|item| is_unmarked_call(item, tools))
+        }
+        Value::Object(map) => {
+            if let Some(calls) = map.get("tool_calls") {
+                return is_unmarked_call(calls, tools);
+            }
+            if let Some(function) = map.get("function") {
+                return is_unmarked_call(function, tools);
+            }
+            map.contains_key("arguments")
+                && map
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| find_tool(name, tools).is_some())
+        }
+        _ => false,
+    }
+}
+
+/// Parse JSON, applying progressively more aggressive repairs on failure.
+pub fn repair_json(input: &str) -> Option<Value> {
+    let trimmed = input.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Some(value);
+    }
+
+    let mut repaired = strip_trailing_commas(trimmed);
+    if let Ok(value) = serde_json::from_str(&repaired) {
+        return Some(value);
+    }
+
+    if !repaired.contains('"') {
+        repaired = repaired.replace('\'', "\"");
+    }
+    repaired = quote_bare_keys(&repaired);
+    repaired = replace_python_literals(&repaired);
+    repaired = strip_trailing_commas(&repaired);
+    if let Ok(value) = serde_json::from_str(&repaired) {
+        return Some(value);
+    }
+
+    repaired = close_unbalanced(&repaired);
+    serde_json::from_str(&repaired).ok()
+}
+
+/// Turn a parsed JSON value into tool calls, if it has a tool call shape.
+fn parse_call_value(value: Value) -> Vec<LLMToolCall> {
+    match value {
+        Value::Array(items) => items.into_iter().flat_map(parse_call_value).collect(),
+        Value::Object(mut map) => {
+            if let Some(calls) = map.remove("tool_calls") {
+                return parse_call_value(calls);
+            }
+
+            // OpenAI style: {"type": "function", "function": {"name", "arguments"}}
+            if let Some(Value::Object(function)) = map.remove("function") {
+                return parse_call_value(Value::Object(function));
+            }
+
+            let name = ["name", "tool", "tool_name", "function_name"]
+                .iter()
+                .find_map(|key| map.get(*key).and_then(|v| v.as_str()).map(str::to_string));
+
+            let name = match name {
+                Some(name) => name,
+                None => return Vec::new(),
+            };
+
+            let arguments = ["arguments", "parameters", "args", "input"]
+                .iter()
+                .find_map(|key| map.get(*key).cloned())
+                .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+
+            let id = map.get("id").and_then(|v| v.as_str()).map(str::to_string);
+
+            vec![LLMToolCall {
+                name,
+                arguments: normalize_arguments(arguments),
+                id,
+            }]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Arguments are sometimes sent as a JSON-encoded string
+fn normalize_arguments(arguments: Value) -> Value {
+    match arguments {
+        Value::String(s) => repair_json(&s).unwrap_or(Value::String(s)),
+        Value::Null => Value::Object(serde_json::Map::new()),
+        other => other,
+    }
+}
+
+/// Locate tool call candidates in the order they appear in the text
+fn find_candidates(text: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    // <tool_call>...</tool_call>
+    collect_tagged(text, "<tool_call>", "</tool_call>", &mut candidates);
+
+    // <function=name>{...}</function>
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find("<function=") {
+        let start = search_from + rel;
+        let name_start = start + "<function=".len();
+        let Some(name_len) = text[name_start..].find('>') else {
+            break;
+        };
+        let name = text[name_start..name_start + name_len].trim().to_string();
+        let body_start = name_start + name_len + 1;
+        let (body_end, end) = match text[body_start..].find("</function>") {
+            Some(rel_end) => (
+                body_start + rel_end,
+                body_start + rel_end + "</function>".len(),
+            ),
+            None => (text.len(), text.len()),
+        };
+        candidates.push(Candidate {
+            span: (start, end),
+            raw: text[body_start..body_end].to_string(),
+            name_hint: Some(name),
+            explicit: true,
+        });
+        search_from = end;
+    }
+
+    if !candidates.is_empty() {
+        candidates.sort_by_key(|c| c.span.0);
+        return candidates;
+    }
+
+    // ```json ... ``` fenced blocks; a ```tool_call fence is explicit
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find("```") {
+        let fence_start = search_from + rel;
+        let after_fence = fence_start + 3;
+        let Some(line_end) = text[after_fence..].find('\n') else {
+            break;
+        };
+        let language = text[after_fence..after_fence + line_end].trim();
+        let body_start = after_fence + line_end + 1;
+        let Some(rel_close) = text[body_start..].find("```") else {
+            break;
+        };
+        let body_end = body_start + rel_close;
+        let body = text[body_start..body_end].trim();
+        let explicit = language == "tool_call";
+        if explicit || body.starts_with('{') || body.starts_with('[') {
+            candidates.push(Candidate {
+                span: (fence_start, body_end + 3),
+                raw: body.to_string(),
+                name_hint: None,
+                explicit,
+            });
+        }
+        search_from = body_end + 3;
+    }
+
+    if !candidates.is_empty() {
+        return candidates;
+    }
+
+    // Bare JSON objects anywhere in the text
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find(&['{', '['][..]) {
+        let start = search_from + rel;
+        let end = matching_close(text, start).unwrap_or(text.len());
+        let raw = &text[start..end];
+        if looks_like_call(raw) {
+            candidates.push(Candidate {
+                span: (start, end),
+                raw: raw.to_string(),
+                name_hint: None,
+                explicit: false,
+            });
+            search_from = end;
+        } else {
+            search_from = start + 1;
+        }
+    }
+
+    candidates
+}
+
+fn collect_tagged(text: &str, open: &str, close: &str, candidates: &mut Vec<Candidate>) {
+    let mut search_from = 0;
+    while let Some(rel) = text[search_from..].find(open) {
+        let start = search_from + rel;
+        let body_start = start + open.len();
+        let (body_end, end) = match text[body_start..].find(close) {
+            Some(rel_end) => (body_start + rel_end, body_start + rel_end + close.len()),
+            None => (text.len(), text.len()),
+        };
+        candidates.push(Candidate {
+            span: (start, end),
+            raw: text[body_start..body_end].to_string(),
+            name_hint: None,
+            explicit: true,
+        });
+        search_from = end;
+    }
+}
+
+/// Cheap pre-filter before parsing: only blobs mentioning a call key can be calls
+fn looks_like_call(raw: &str) -> bool {
+    ["\"name\"", "'name'", "\"function\"", "\"tool_calls\""]
+        .iter()
+        .any(|key| raw.contains(key))
+}
+
+/// Find the byte index just past the bracket matching the one at `start`
+pub(crate) fn matching_close(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in text[start..].char_indices() {
+        if in_string {
+            match ch {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => in_string = false,
+                _ => escaped = false,
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(start + offset + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn strip_trailing_commas(input: &str) -> String {
+    TRAILING_COMMA.replace_all(input, "$1").into_owned()
+}
+
+fn quote_bare_keys(input: &str) -> String {
+    BARE_KEY.replace_all(input, "$1\"$2\":").into_owned()
+}
+
+fn replace_python_literals(input: &str) -> String {
+    PYTHON_LITERAL
+        .replace_all(input, |caps: &regex::Captures| match &caps[1] {
+            "True" => ": true",
+            "False" => ": false",
+            _ => ": null",
+        })
+        .into_owned()
+}
+
+/// Append closing brackets (and a closing quote) for truncated JSON
+fn close_unbalanced(input: &str) -> String {
+    let mut stack = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for ch in input.chars() {
+        if in_string {
+            match ch {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => in_string = false,
+                _ => escaped = false,
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let mut output = input.to_string();
+    if in_string {
+        output.push('"');
+    }
+    while let Some(close) = stack.pop() {
+        output.push(close);
+    }
+    output
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c == ' ' || c == '-' { '_' } else { c })
+        .collect()
+}
+
+fn remove_spans(text: &str, spans: &[(usize, usize)]) -> String {
+    let mut spans = spans.to_vec();
+    spans.sort_by_key(|s| s.0);
+
+    let mut output = String::new();
+    let mut cursor = 0;
+    for (start, end) in spans {
+        if start >= cursor {
+            output.push_str(&text[cursor..start]);
+            cursor = end;
+        }
+    }
+    output.push_str(&text[cursor..]);
+    output.trim().to_string()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        text.chars().take(max_chars).collect::<String>() + "..."
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tools() -> Vec<LLMTool> {
+        vec![
+            LLMTool {
+                name: "web_search".to_string(),
+                description: "Search the web".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {"query": {"type": "string"}},
+                    "required": ["query"]
+                }),
+            },
+            LLMTool {
+                name: "mouse_click".to_string(),
+                description: "Click the mouse".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {"button": {"type": "string"}},
+                    "required": []
+                }),
+            },
+        ]
+    }
+
+    fn expect_calls(extraction: ToolCallExtraction) -> Vec<LLMToolCall> {
+        match extraction {
+            ToolCallExtraction::Calls { calls, .. } => calls,
+            other => panic!("expected calls, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_text_is_not_a_call() {
+        let result = extract_tool_calls("The capital of France is Paris.", &tools());
+        assert!(matches!(result, ToolCallExtraction::None));
+    }
+
+    #[test]
+    fn test_bare_json_call() {
+        let text = r#"{"name": "web_search", "arguments": {"query": "rust async"}}"#;
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "web_search");
+        assert_eq!(calls[0].arguments["query"], "rust async");
+    }
+
+    #[test]
+    fn test_tool_call_tags_and_remaining_text() {
+        let text = "Let me search.\n<tool_call>{\"name\": \"web_search\", \"parameters\": {\"query\": \"tokio\"}}</tool_call>";
+        match extract_tool_calls(text, &tools()) {
+            ToolCallExtraction::Calls {
+                calls,
+                remaining_text,
+            } => {
+                assert_eq!(calls[0].arguments["query"], "tokio");
+                assert_eq!(remaining_text, "Let me search.");
+            }
+            other => panic!("expected calls, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_function_tag_format() {
+        let text = r#"<function=mouse_click>{"button": "left"}</function>"#;
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls[0].name, "mouse_click");
+        assert_eq!(calls[0].arguments["button"], "left");
+    }
+
+    #[test]
+    fn test_fenced_block_with_string_arguments() {
+        let text = "```json\n{\"function\": {\"name\": \"web_search\", \"arguments\": \"{\\\"query\\\": \\\"x\\\"}\"}}\n```";
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls[0].arguments["query"], "x");
+    }
+
+    #[test]
+    fn test_repairs_malformed_json() {
+        let text =
+            "<tool_call>{name: 'web_search', arguments: {query: 'rust', safe: True,}</tool_call>";
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls[0].arguments["query"], "rust");
+        assert_eq!(calls[0].arguments["safe"], true);
+    }
+
+    #[test]
+    fn test_name_is_canonicalised() {
+        let text = r#"{"name": "Web-Search", "arguments": {"query": "q"}}"#;
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls[0].name, "web_search");
+    }
+
+    #[test]
+    fn test_json_answers_are_not_calls() {
+        for text in [
+            r#"{"name": "Alice"}"#,
+            r#"{"name": "Alice", "age": 30}"#,
+            "```json\n{\"name\": \"open_browser\", \"arguments\": {}}\n```",
+            r#"Here is the record: {"name": "Bob", "arguments": ["a", "b"]}"#,
+            r#"[{"name": "x"}, {"name": "y"}]"#,
+        ] {
+            assert!(
+                matches!(extract_tool_calls(text, &tools()), ToolCallExtraction::None),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_unmarked_call_to_offered_tool_is_validated() {
+        let text = r#"{"name": "web_search", "arguments": {}}"#;
+        assert!(matches!(
+            extract_tool_calls(text, &tools()),
+            ToolCallExtraction::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_tool_call_fence_is_explicit() {
+        let text =
+            "```tool_call\n{\"name\": \"mouse_click\", \"arguments\": {\"button\": \"left\"}}\n```";
+        let calls = expect_calls(extract_tool_calls(text, &tools()));
+        assert_eq!(calls[0].name, "mouse_click");
+
+        let text = "```tool_call\nmouse_click(left)\n```";
+        assert!(matches!(
+            extract_tool_calls(text, &tools()),
+            ToolCallExtraction::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_unknown_tool_is_invalid() {
+        let text = r#"<tool_call>{"name": "open_browser", "arguments": {}}</tool_call>"#;
+        match extract_tool_calls(text, &tools()) {
+            ToolCallExtraction::Invalid { errors } => {
+                assert!(errors[0].contains("Unknown tool 'open_browser'"));
+                let message = corrective_message(&errors, &tools());
+                assert!(message.contains("web_search, mouse_click"));
+            }
+            other => panic!("expected invalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_required_argument_is_invalid() {
+        let text = r#"<tool_call>{"name": "web_search", "arguments": {}}</tool_call>"#;
+        assert!(matches!(
+            extract_tool_calls(text, &tools()),
+            ToolCallExtraction::Invalid { .. }
+        ));
+    }
+
+    #[test]
+    fn test_unparseable_tagged_call_is_invalid() {
+        let text = "<tool_call>not json at all</tool_call>";
+        assert!(matches!(
+            extract_tool_calls(text, &tools()),
+            ToolCallExtraction::Invalid { .. }
+        ));
+    }
+}
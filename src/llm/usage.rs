@@ -0,0 +1,310 @@
+//! Token usage and cost accounting
+//!
+//! Usage reported by the LLM backend is aggregated per call, per execution and
+//! per session. Costs are estimated from a per-model price table that can be
+//! overridden with a TOML or JSON file named by `CF_AI_PRICE_TABLE`.
+
+use crate::core::{AppError, LLMUsage, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Environment variable naming a custom price table file
+pub const PRICE_TABLE_ENV: &str = "CF_AI_PRICE_TABLE";
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Per-model price table used to estimate cost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTable {
+    /// Prices keyed by model ID
+    #[serde(default)]
+    pub models: HashMap<String, ModelPrice>,
+    /// Price used for models missing from the table
+    #[serde(default)]
+    pub fallback: Option<ModelPrice>,
+}
+
+impl Default for PriceTable {
+    /// Approximate Workers AI list prices
+    fn default() -> Self {
+        let models = [
+            ("@cf/meta/llama-3.1-8b-instruct", 0.282, 0.827),
+            ("@cf/meta/llama-3.1-8b-instruct-fast", 0.045, 0.384),
+            ("@cf/meta/llama-3.1-70b-instruct", 0.293, 2.253),
+            ("@cf/meta/llama-3.3-70b-instruct-fp8-fast", 0.293, 2.253),
+            ("@cf/meta/llama-4-scout-17b-16e-instruct", 0.270, 0.850),
+            ("@cf/mistral/mistral-7b-instruct-v0.2", 0.110, 0.190),
+            ("@cf/qwen/qwen2.5-coder-32b-instruct", 0.660, 1.000),
+        ]
+        .into_iter()
+        .map(|(id, input, output)| {
+            (
+                id.to_string(),
+                ModelPrice {
+                    input_per_million: input,
+                    output_per_million: output,
+                },
+            )
+        })
+        .collect();
+
+        Self {
+            models,
+            fallback: None,
+        }
+    }
+}
+
+impl PriceTable {
+    /// Load a price table from a `.toml` or `.json` file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&text).map_err(|e| {
+                AppError::Configuration(format!("Invalid price table {}: {}", path.display(), e))
+            }),
+            _ => Ok(serde_json::from_str(&text)?),
+        }
+    }
+
+    /// Built-in prices overridden by the file named in `CF_AI_PRICE_TABLE`, if any
+    pub fn from_env() -> Self {
+        let mut table = Self::default();
+        if let Ok(path) = std::env::var(PRICE_TABLE_ENV) {
+            match Self::from_file(Path::new(&path)) {
+                Ok(custom) => table.merge(custom),
+                Err(e) => eprintln!("Ignoring price table {}: {}", path, e),
+            }
+        }
+        table
+    }
+
+    /// Process-wide price table, loaded on first use
+    pub fn global() -> &'static PriceTable {
+        static TABLE: std::sync::OnceLock<PriceTable> = std::sync::OnceLock::new();
+        TABLE.get_or_init(Self::from_env)
+    }
+
+    /// Override entries with those from `other`
+    pub fn merge(&mut self, other: PriceTable) {
+        self.models.extend(other.models);
+        if other.fallback.is_some() {
+            self.fallback = other.fallback;
+        }
+    }
+
+    /// Price for a model, if known
+    pub fn price_for(&self, model_id: &str) -> Option<ModelPrice> {
+        self.models.get(model_id).copied().or(self.fallback)
+    }
+
+    /// Estimated cost in USD of `usage` on `model_id` (zero for unknown models)
+    pub fn estimate_cost(&self, model_id: &str, usage: &LLMUsage) -> f64 {
+        self.price_for(model_id)
+            .map(|price| {
+                (usage.input_tokens as f64 * price.input_per_million
+                    + usage.output_tokens as f64 * price.output_per_million)
+                    / 1_000_000.0
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+/// Usage of a single LLM call
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LLMCallUsage {
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+    pub cost_usd: f64,
+}
+
+/// Aggregated usage over any number of LLM calls
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UsageSummary {
+    pub llm_calls: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageSummary {
+    fn add_call(&mut self, call: &LLMCallUsage) {
+        self.llm_calls += 1;
+        self.input_tokens += call.input_tokens as u64;
+        self.output_tokens += call.output_tokens as u64;
+        self.total_tokens += call.total_tokens as u64;
+        self.cost_usd += call.cost_usd;
+    }
+
+    /// Add another summary into this one
+    pub fn merge(&mut self, other: &UsageSummary) {
+        self.llm_calls += other.llm_calls;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+
+    /// One-line human-readable summary
+    pub fn describe(&self) -> String {
+        format!(
+            "{} LLM call(s), {} tokens ({} in / {} out), ~${:.4}",
+            self.llm_calls, self.total_tokens, self.input_tokens, self.output_tokens, self.cost_usd
+        )
+    }
+}
+
+/// Usage of a single agent execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ExecutionUsage {
+    /// Every LLM call made during the execution, in order
+    pub calls: Vec<LLMCallUsage>,
+    /// Totals over all calls
+    pub total: UsageSummary,
+}
+
+impl ExecutionUsage {
+    /// Record an LLM call, pricing it with the global price table
+    pub fn record(&mut self, model: &str, usage: Option<&LLMUsage>) {
+        self.record_with_prices(model, usage, PriceTable::global());
+    }
+
+    /// Record an LLM call, pricing it with `prices`
+    pub fn record_with_prices(
+        &mut self,
+        model: &str,
+        usage: Option<&LLMUsage>,
+        prices: &PriceTable,
+    ) {
+        let usage = usage.cloned().unwrap_or_default();
+        let call = LLMCallUsage {
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            total_tokens: usage.total_tokens,
+            cost_usd: prices.estimate_cost(model, &usage),
+        };
+        self.total.add_call(&call);
+        self.calls.push(call);
+    }
+}
+
+/// Hard limits on an agent's usage for a single execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageBudget {
+    /// Maximum total tokens across all LLM calls
+    pub max_total_tokens: Option<u64>,
+    /// Maximum estimated cost in USD
+    pub max_cost_usd: Option<f64>,
+}
+
+impl UsageBudget {
+    /// Return an error describing the first exceeded limit, if any
+    pub fn check(&self, usage: &UsageSummary) -> Result<()> {
+        if let Some(max_tokens) = self.max_total_tokens {
+            if usage.total_tokens > max_tokens {
+                return Err(AppError::Agent(format!(
+                    "Token budget exceeded: used {} of {} tokens",
+                    usage.total_tokens, max_tokens
+                )));
+            }
+        }
+
+        if let Some(max_cost) = self.max_cost_usd {
+            if usage.cost_usd > max_cost {
+                return Err(AppError::Agent(format!(
+                    "Cost budget exceeded: spent ~${:.4} of ${:.4}",
+                    usage.cost_usd, max_cost
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u32, output: u32) -> LLMUsage {
+        LLMUsage {
+            input_tokens: input,
+            output_tokens: output,
+            total_tokens: input + output,
+        }
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let table = PriceTable::default();
+        let cost = table.estimate_cost(
+            "@cf/meta/llama-3.3-70b-instruct-fp8-fast",
+            &usage(1_000_000, 0),
+        );
+        assert!((cost - 0.293).abs() < 1e-9);
+        assert_eq!(table.estimate_cost("unknown", &usage(1000, 1000)), 0.0);
+    }
+
+    #[test]
+    fn test_custom_table_overrides_defaults() {
+        let mut table = PriceTable::default();
+        let custom: PriceTable = toml::from_str(
+            r#"
+            [fallback]
+            input_per_million = 1.0
+            output_per_million = 2.0
+            "#,
+        )
+        .unwrap();
+        table.merge(custom);
+
+        let cost = table.estimate_cost("unknown", &usage(500_000, 500_000));
+        assert!((cost - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_execution_usage_aggregates_calls() {
+        let table = PriceTable::default();
+        let mut execution = ExecutionUsage::default();
+        execution.record_with_prices("m", Some(&usage(10, 20)), &table);
+        execution.record_with_prices("m", Some(&usage(5, 5)), &table);
+        execution.record_with_prices("m", None, &table);
+
+        assert_eq!(execution.calls.len(), 3);
+        assert_eq!(execution.total.llm_calls, 3);
+        assert_eq!(execution.total.total_tokens, 40);
+
+        let mut session = UsageSummary::default();
+        session.merge(&execution.total);
+        session.merge(&execution.total);
+        assert_eq!(session.total_tokens, 80);
+    }
+
+    #[test]
+    fn test_budget_check() {
+        let budget = UsageBudget {
+            max_total_tokens: Some(100),
+            max_cost_usd: None,
+        };
+        let mut summary = UsageSummary {
+            total_tokens: 100,
+            ..Default::default()
+        };
+        assert!(budget.check(&summary).is_ok());
+
+        summary.total_tokens = 101;
+        assert!(budget.check(&summary).is_err());
+    }
+}
@@ -1,45 +1,169 @@
 //! Enhanced Local Rust App - Main Application
 //!
 //! A modular, dynamic agent system with plug-and-play tools and thinking capabilities.
-//! This application runs as a backend client, connecting to a Cloudflare Worker relay
-//! to receive instructions from the frontend and execute agents locally.
+//! By default this application runs as a backend client, connecting to a Cloudflare
+//! Worker relay to receive instructions from the frontend and execute agents locally;
+//! see `--help` for the headless subcommands.
 
+use cf_ai_local_tools::cli::{Cli, Command};
 use cf_ai_local_tools::llm::client::HttpClient;
+use cf_ai_local_tools::llm::{
+    CacheConfig, CachingClient, LLMClient, RecordReplayClient, ReplayMode,
+};
+use cf_ai_local_tools::mcp::{self, McpConfig};
 use cf_ai_local_tools::registry::CentralRegistry;
-use cf_ai_local_tools::websocket::WebSocketRelayClient;
+use cf_ai_local_tools::tools::openapi::{self, OpenApiConfig};
+use cf_ai_local_tools::tools::ToolSettings;
+use clap::Parser;
 use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    // Only the long-running server narrates its startup; other commands keep stdout clean
+    let serving = matches!(cli.command, None | Some(Command::Serve(_)));
+
     // Initialize logging
     env_logger::init();
 
-    println!("🚀 Starting Enhanced Local Rust App...");
-
-    // Initialize components
-    println!("📦 Initializing components...");
+    if serving {
+        println!("🚀 Starting Enhanced Local Rust App...");
+        println!("📦 Initializing components...");
+    }
 
     // Create central registry
-    let mut registry = CentralRegistry::new();
+    let registry = CentralRegistry::new();
     registry.initialize().await?;
+
+    // Add tool plugins found on disk; the registry keeps the loader for rescans
+    let scan = registry.rescan_plugins().await?;
+    for (path, error) in &scan.errors {
+        eprintln!("⚠️  Skipping plugin {}: {}", path.display(), error);
+    }
+    if serving && !scan.loaded.is_empty() {
+        println!(
+            "🔌 Loaded {} plugin tool(s) from {}",
+            scan.loaded.len(),
+            registry.plugins_path().await.display()
+        );
+    }
+
+    // Add agents defined in files
+    let scan = registry.rescan_agents().await?;
+    for (path, error) in &scan.errors {
+        eprintln!(
+            "⚠️  Skipping agent definition {}: {}",
+            path.display(),
+            error
+        );
+    }
+    if let Some(dir) = registry
+        .agents_path()
+        .await
+        .filter(|_| serving && !scan.loaded.is_empty())
+    {
+        println!(
+            "🧩 Loaded {} agent definition(s) from {}",
+            scan.loaded.len(),
+            dir.display()
+        );
+    }
+    // Import tools from the configured MCP servers
+    match McpConfig::from_env() {
+        Ok(config) => {
+            let import = mcp::import_tools(&config, registry.tools.as_ref()).await?;
+            for (server, error) in &import.errors {
+                eprintln!("⚠️  Skipping MCP server {}: {}", server, error);
+            }
+            if serving && !import.loaded.is_empty() {
+                println!(
+                    "🔗 Imported {} tool(s) from {} MCP server(s)",
+                    import.loaded.len(),
+                    import.clients.len()
+                );
+            }
+        }
+        Err(e) => eprintln!("⚠️  Ignoring MCP config: {}", e),
+    }
+    // Generate HTTP tools from the configured OpenAPI documents
+    match OpenApiConfig::from_env() {
+        Ok(config) => {
+            let import = openapi::import_tools(&config, registry.tools.as_ref()).await?;
+            for (api, error) in &import.errors {
+                eprintln!("⚠️  Skipping OpenAPI import for {}: {}", api, error);
+            }
+            if serving && !import.loaded.is_empty() {
+                println!(
+                    "🌐 Imported {} tool(s) from {} OpenAPI document(s)",
+                    import.loaded.len(),
+                    config.apis.len()
+                );
+            }
+        }
+        Err(e) => eprintln!("⚠️  Ignoring OpenAPI config: {}", e),
+    }
+    // Apply per-tool enabled flags and configuration blocks
+    match ToolSettings::from_env() {
+        Ok(settings) => {
+            for (tool, error) in settings.apply(registry.tools.as_ref()).await? {
+                eprintln!("⚠️  Ignoring settings for tool {}: {}", tool, error);
+            }
+        }
+        Err(e) => eprintln!("⚠️  Ignoring tool settings: {}", e),
+    }
+    // Disable agents whose tools didn't make it into the registry
+    let graph = registry.refresh_availability().await?;
+    for dependency in graph.missing() {
+        eprintln!(
+            "⚠️  {} '{}' requires tool '{}', which is not available",
+            dependency.kind, dependency.id, dependency.tool
+        );
+    }
     let registry = Arc::new(registry);
 
     // Create LLM client
     // We configure it to point to the Worker's LLM proxy endpoint
-    let llm = Arc::new(HttpClient::new("http://localhost:8787".to_string()));
+    let http = Arc::new(HttpClient::new("http://localhost:8787".to_string()));
 
-    // Create WebSocket Client
-    // Connects to the Worker relay as the 'desktop' device
-    let ws_url = "ws://localhost:8787/connect?device=desktop";
-    let client = WebSocketRelayClient::new(ws_url.to_string(), registry.clone(), llm.clone());
+    // Optionally record this session's LLM calls to a fixture, or replay them from one
+    let backend: Arc<dyn LLMClient> = match ReplayMode::from_env()? {
+        Some(mode) => {
+            if serving {
+                match &mode {
+                    ReplayMode::Record(path) => {
+                        println!("📼 Recording LLM calls to {}", path.display())
+                    }
+                    ReplayMode::Replay(path) => {
+                        println!("📼 Replaying LLM calls from {}", path.display())
+                    }
+                }
+            }
+            Arc::new(RecordReplayClient::from_mode(mode, http)?)
+        }
+        None => http,
+    };
 
-    println!("🌐 Connecting to relay at {}...", ws_url);
+    // Optionally cache responses on disk while iterating on prompts
+    let llm: Arc<dyn LLMClient> = match CacheConfig::from_env() {
+        Some(config) => {
+            if serving {
+                println!(
+                    "🗄️  Caching LLM responses in {}",
+                    config.directory.display()
+                );
+            }
+            Arc::new(CachingClient::new(backend, config))
+        }
+        None => backend,
+    };
 
-    // Run the client loop
-    if let Err(e) = client.run().await {
-        eprintln!("❌ Application error: {}", e);
+    if let Err(e) = cli.execute(registry, llm).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
     }
 
-    println!("👋 Enhanced Local Rust App shutting down...");
+    if serving {
+        println!("👋 Enhanced Local Rust App shutting down...");
+    }
     Ok(())
 }
@@ -0,0 +1,480 @@
+//! Client side of MCP: talks to one server over stdio or streamable HTTP
+//!
+//! The connection is opened on first use and reopened, with a fresh
+//! `initialize` handshake, when the server process has exited or its HTTP
+//! session has expired.
+
+use crate::core::{AppError, Result};
+use crate::mcp::config::McpServerConfig;
+use crate::mcp::protocol::{
+    CallToolResult, ListToolsResult, Message, ToolInfo, METHOD_NOT_FOUND, PROTOCOL_VERSION,
+    SUPPORTED_VERSIONS,
+};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+
+/// Header carrying the session assigned by a streamable HTTP server
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// A failed exchange; `retry` is set when the server never saw the request
+struct TransportError {
+    error: AppError,
+    retry: bool,
+}
+
+impl TransportError {
+    fn fatal(error: impl Into<AppError>) -> Self {
+        Self {
+            error: error.into(),
+            retry: false,
+        }
+    }
+
+    fn retryable(error: impl Into<AppError>) -> Self {
+        Self {
+            error: error.into(),
+            retry: true,
+        }
+    }
+}
+
+type TransportResult<T> = std::result::Result<T, TransportError>;
+
+/// A server running as a child process, one JSON message per line
+struct StdioConnection {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl StdioConnection {
+    fn spawn(config: &McpServerConfig, command: &str) -> Result<Self> {
+        let mut child = tokio::process::Command::new(command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                AppError::Tool(format!("Failed to start MCP server {}: {}", config.name, e))
+            })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+        })
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    async fn send(&mut self, message: &Message) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await
+    }
+
+    async fn request(&mut self, request: &Message) -> TransportResult<Message> {
+        self.send(request)
+            .await
+            .map_err(TransportError::retryable)?;
+
+        loop {
+            let line = self
+                .stdout
+                .next_line()
+                .await
+                .map_err(TransportError::fatal)?
+                .ok_or_else(|| {
+                    TransportError::fatal(AppError::Tool(
+                        "MCP server closed its output".to_string(),
+                    ))
+                })?;
+            let Ok(message) = serde_json::from_str::<Message>(&line) else {
+                continue;
+            };
+            if message.is_response() && message.id == request.id {
+                return Ok(message);
+            }
+            // Requests from the server: answer pings, decline the rest
+            if let (Some(id), Some(method)) = (message.id, message.method.as_deref()) {
+                let reply = if method == "ping" {
+                    Message::response(id, json!({}))
+                } else {
+                    Message::error_response(id, METHOD_NOT_FOUND, "Not supported by this client")
+                };
+                self.send(&reply).await.map_err(TransportError::fatal)?;
+            }
+        }
+    }
+
+    async fn notify(&mut self, notification: &Message) -> TransportResult<()> {
+        self.send(notification)
+            .await
+            .map_err(TransportError::retryable)
+    }
+}
+
+/// A server reached by POSTing each message to one endpoint
+struct HttpConnection {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+}
+
+impl HttpConnection {
+    fn new(config: &McpServerConfig, url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            headers: config
+                .headers
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            session_id: None,
+            protocol_version: None,
+        }
+    }
+
+    async fn post(&mut self, message: &Message) -> TransportResult<reqwest::Response> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("accept", "application/json, text/event-stream")
+            .json(message);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(session_id) = &self.session_id {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        if let Some(version) = &self.protocol_version {
+            request = request.header("mcp-protocol-version", version);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            TransportError::retryable(AppError::Network(format!("MCP request failed: {}", e)))
+        })?;
+        let status = response.status();
+        // The server forgot our session, so it has not handled this request
+        if status == reqwest::StatusCode::NOT_FOUND && self.session_id.is_some() {
+            return Err(TransportError::retryable(AppError::Network(
+                "MCP session expired".to_string(),
+            )));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(TransportError::fatal(AppError::Network(format!(
+                "MCP server answered {}: {}",
+                status,
+                body.trim()
+            ))));
+        }
+        if let Some(session_id) = response.headers().get(SESSION_HEADER) {
+            self.session_id = session_id.to_str().ok().map(str::to_string);
+        }
+        Ok(response)
+    }
+
+    async fn request(&mut self, request: &Message) -> TransportResult<Message> {
+        let response = self.post(request).await?;
+        let is_stream = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await.map_err(|e| {
+            TransportError::fatal(AppError::Network(format!("MCP response failed: {}", e)))
+        })?;
+
+        if !is_stream {
+            return serde_json::from_str(&body).map_err(TransportError::fatal);
+        }
+        sse_messages(&body)
+            .into_iter()
+            .find(|message| message.is_response() && message.id == request.id)
+            .ok_or_else(|| {
+                TransportError::fatal(AppError::Network(
+                    "MCP event stream ended without a response".to_string(),
+                ))
+            })
+    }
+
+    async fn notify(&mut self, notification: &Message) -> TransportResult<()> {
+        self.post(notification).await.map(|_| ())
+    }
+}
+
+/// JSON-RPC messages carried by the `data:` lines of a server-sent event stream
+fn sse_messages(body: &str) -> Vec<Message> {
+    body.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            serde_json::from_str(&data.join("\n")).ok()
+        })
+        .collect()
+}
+
+enum Connection {
+    Stdio(Box<StdioConnection>),
+    Http(HttpConnection),
+}
+
+impl Connection {
+    fn is_alive(&mut self) -> bool {
+        match self {
+            Self::Stdio(connection) => connection.is_alive(),
+            Self::Http(_) => true,
+        }
+    }
+
+    async fn request(&mut self, request: &Message) -> TransportResult<Message> {
+        match self {
+            Self::Stdio(connection) => connection.request(request).await,
+            Self::Http(connection) => connection.request(request).await,
+        }
+    }
+
+    async fn notify(&mut self, notification: &Message) -> TransportResult<()> {
+        match self {
+            Self::Stdio(connection) => connection.notify(notification).await,
+            Self::Http(connection) => connection.notify(notification).await,
+        }
+    }
+}
+
+/// Connection to one MCP server
+pub struct McpClient {
+    config: McpServerConfig,
+    connection: Mutex<Option<Connection>>,
+    next_id: AtomicU64,
+    connects: AtomicUsize,
+}
+
+impl McpClient {
+    /// Client for `config`; nothing is started until the first request
+    pub fn new(config: McpServerConfig) -> Self {
+        Self {
+            config,
+            connection: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+            connects: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &McpServerConfig {
+        &self.config
+    }
+
+    /// How many times the server had to be reconnected after the first start
+    pub fn restarts(&self) -> usize {
+        self.connects.load(Ordering::SeqCst).saturating_sub(1)
+    }
+
+    /// Every tool the server offers, following pagination
+    pub async fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = cursor.as_ref().map(|cursor| json!({"cursor": cursor}));
+            let page: ListToolsResult =
+                serde_json::from_value(self.request("tools/list", params).await?)?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) if Some(&next) != cursor.as_ref() => cursor = Some(next),
+                _ => return Ok(tools),
+            }
+        }
+    }
+
+    /// Run one of the server's tools
+    pub async fn call_tool(&self, name: &str, arguments: &Value) -> Result<CallToolResult> {
+        let params = json!({"name": name, "arguments": arguments});
+        Ok(serde_json::from_value(
+            self.request("tools/call", Some(params)).await?,
+        )?)
+    }
+
+    /// Send a request, (re)starting the server first if it isn't running
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value> {
+        let mut slot = self.connection.lock().await;
+        let mut retried = false;
+        loop {
+            if !slot.as_mut().is_some_and(Connection::is_alive) {
+                if slot.take().is_some() {
+                    eprintln!("⚠️  MCP server {} stopped; restarting", self.config.name);
+                }
+                *slot = Some(self.connect().await?);
+            }
+            let connection = slot.as_mut().expect("connected above");
+
+            let request = Message::request(self.next_id(), method, params.clone());
+            match tokio::time::timeout(self.config.timeout(), connection.request(&request)).await {
+                Ok(Ok(response)) => return response.into_result(),
+                Ok(Err(failure)) => {
+                    *slot = None;
+                    if failure.retry && !retried {
+                        retried = true;
+                        continue;
+                    }
+                    return Err(failure.error);
+                }
+                Err(_) => {
+                    // Dropping the connection kills a hung server
+                    *slot = None;
+                    return Err(AppError::Tool(format!(
+                        "MCP server {} did not answer {} within {:?}",
+                        self.config.name,
+                        method,
+                        self.config.timeout()
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Stop the server; the next request starts it again
+    pub async fn disconnect(&self) {
+        self.connection.lock().await.take();
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    async fn connect(&self) -> Result<Connection> {
+        self.config.validate()?;
+        let mut connection = match (&self.config.command, &self.config.url) {
+            (Some(command), _) => {
+                Connection::Stdio(Box::new(StdioConnection::spawn(&self.config, command)?))
+            }
+            (None, Some(url)) => Connection::Http(HttpConnection::new(&self.config, url)),
+            (None, None) => unreachable!("validated above"),
+        };
+
+        let initialize = Message::request(
+            self.next_id(),
+            "initialize",
+            Some(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+        );
+        let handshake = async {
+            let result = connection
+                .request(&initialize)
+                .await
+                .map_err(|f| f.error)?
+                .into_result()?;
+            let version = result["protocolVersion"].as_str().unwrap_or_default();
+            if !SUPPORTED_VERSIONS.contains(&version) {
+                return Err(AppError::Tool(format!(
+                    "MCP server {} speaks unsupported protocol version '{}'",
+                    self.config.name, version
+                )));
+            }
+            if let Connection::Http(http) = &mut connection {
+                http.protocol_version = Some(version.to_string());
+            }
+            connection
+                .notify(&Message::notification("notifications/initialized", None))
+                .await
+                .map_err(|f| f.error)
+        };
+        tokio::time::timeout(self.config.timeout(), handshake)
+            .await
+            .map_err(|_| {
+                AppError::Tool(format!(
+                    "MCP server {} did not finish the handshake within {:?}",
+                    self.config.name,
+                    self.config.timeout()
+                ))
+            })??;
+
+        self.connects.fetch_add(1, Ordering::SeqCst);
+        Ok(connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_messages() {
+        let body = "event: message\r\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\r\n\r\n\
+                    id: 2\ndata: {\"jsonrpc\":\"2.0\",\ndata: \"id\":7,\"result\":{}}\n\n";
+        let messages = sse_messages(body);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[1].is_response());
+        assert_eq!(messages[1].id, Some(Value::from(7)));
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http() {
+        use axum::http::{HeaderMap, StatusCode};
+        use axum::response::IntoResponse;
+
+        async fn endpoint(headers: HeaderMap, body: String) -> axum::response::Response {
+            let message: Message = serde_json::from_str(&body).unwrap();
+            let session = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok());
+            match (message.method.as_deref(), message.id) {
+                (Some("initialize"), Some(id)) => {
+                    let result = json!({"protocolVersion": PROTOCOL_VERSION, "capabilities": {}});
+                    let mut response = axum::Json(Message::response(id, result)).into_response();
+                    response
+                        .headers_mut()
+                        .insert(SESSION_HEADER, "session-1".parse().unwrap());
+                    response
+                }
+                _ if session != Some("session-1") => StatusCode::NOT_FOUND.into_response(),
+                (_, None) => StatusCode::ACCEPTED.into_response(),
+                (Some("tools/list"), Some(id)) => {
+                    let result = json!({"tools": [{"name": "remote_echo", "inputSchema": {"type": "object"}}]});
+                    let event = format!(
+                        "event: message\ndata: {}\n\n",
+                        serde_json::to_string(&Message::response(id, result)).unwrap()
+                    );
+                    ([("content-type", "text/event-stream")], event).into_response()
+                }
+                (_, Some(id)) => {
+                    let result = json!({"content": [{"type": "text", "text": "remote"}]});
+                    axum::Json(Message::response(id, result)).into_response()
+                }
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let app = axum::Router::new().route("/mcp", axum::routing::post(endpoint));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = McpClient::new(McpServerConfig::http("remote", &url));
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "remote_echo");
+        let result = client.call_tool("remote_echo", &json!({})).await.unwrap();
+        assert_eq!(result.text(), "remote");
+    }
+}
@@ -0,0 +1,181 @@
+//! MCP servers the app connects to, read from `mcp.toml`
+
+use crate::core::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// File listing the MCP servers to import tools from
+pub const MCP_CONFIG_ENV: &str = "CF_AI_MCP_CONFIG";
+
+/// Seconds a server may take to answer one request when its entry doesn't say
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// How to reach one MCP server: a `command` for stdio or a `url` for streamable HTTP
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Short name, used to prefix the IDs of the server's tools
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Registry category for the server's tools; defaults to `mcp:<name>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl McpServerConfig {
+    /// Server launched as a child process speaking MCP over stdio
+    pub fn stdio(name: &str, command: &str, args: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            command: Some(command.to_string()),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Server reached over streamable HTTP
+    pub fn http(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn category(&self) -> String {
+        self.category
+            .clone()
+            .unwrap_or_else(|| format!("mcp:{}", self.name))
+    }
+
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err(AppError::Configuration(format!(
+                "Invalid MCP server name '{}': use letters, digits, '_' and '-'",
+                self.name
+            )));
+        }
+        match (&self.command, &self.url) {
+            (Some(command), None) if !command.trim().is_empty() => Ok(()),
+            (None, Some(url)) if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(())
+            }
+            _ => Err(AppError::Configuration(format!(
+                "MCP server '{}' needs either a command or an http(s) url",
+                self.name
+            ))),
+        }
+    }
+}
+
+/// Contents of `mcp.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpConfig {
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+}
+
+impl McpConfig {
+    /// Path from `CF_AI_MCP_CONFIG`, or `mcp.toml` in the user's config directory
+    pub fn default_path() -> PathBuf {
+        match std::env::var(MCP_CONFIG_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => dirs::config_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cf_ai_local_tools")
+                .join("mcp.toml"),
+        }
+    }
+
+    /// Read the default config; a missing file means no servers
+    pub fn from_env() -> Result<Self> {
+        Self::load(&Self::default_path())
+    }
+
+    /// Read a config file; a missing file means no servers
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let config: Self = toml::from_str(&text).map_err(|e| {
+            AppError::Configuration(format!("Invalid MCP config {}: {}", path.display(), e))
+        })?;
+        for (i, server) in config.servers.iter().enumerate() {
+            server.validate()?;
+            if config.servers[..i].iter().any(|s| s.name == server.name) {
+                return Err(AppError::Configuration(format!(
+                    "MCP server '{}' is configured twice",
+                    server.name
+                )));
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let config: McpConfig = toml::from_str(
+            r#"
+[[servers]]
+name = "files"
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]
+
+[[servers]]
+name = "remote"
+url = "https://example.com/mcp"
+headers = { Authorization = "Bearer token" }
+category = "remote"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.servers.len(), 2);
+        assert!(config.servers.iter().all(|s| s.validate().is_ok()));
+        assert_eq!(config.servers[0].category(), "mcp:files");
+        assert_eq!(config.servers[1].category(), "remote");
+
+        let both = McpServerConfig {
+            url: Some("http://localhost/mcp".to_string()),
+            ..config.servers[0].clone()
+        };
+        assert!(both.validate().is_err());
+        assert!(McpServerConfig::http("bad name", "http://x")
+            .validate()
+            .is_err());
+        assert!(McpServerConfig::http("ftp", "ftp://x").validate().is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mcp.toml");
+        assert!(McpConfig::load(&path).unwrap().servers.is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+//! Model Context Protocol support
+//!
+//! Tools offered by the MCP servers listed in `mcp.toml` are imported into the
+//! tool registry and called through `McpClient`; `McpServer` offers the
+//! registry's own tools to other MCP clients.
+
+pub mod client;
+pub mod config;
+pub mod protocol;
+pub mod server;
+pub mod tool;
+
+pub use client::McpClient;
+pub use config::{McpConfig, McpServerConfig, MCP_CONFIG_ENV};
+pub use server::McpServer;
+pub use tool::McpTool;
+
+use crate::core::Tool;
+use crate::tools::registry::ToolRegistry;
+use std::sync::Arc;
+
+/// Outcome of importing tools from MCP servers
+#[derive(Default)]
+pub struct McpImport {
+    /// Connected servers, kept so their tools can reach them
+    pub clients: Vec<Arc<McpClient>>,
+    /// Tool IDs registered
+    pub loaded: Vec<String>,
+    /// Servers or tools that could not be imported, with the reason
+    pub errors: Vec<(String, String)>,
+}
+
+/// Connect to every configured server and register its tools
+pub async fn import_tools(
+    config: &McpConfig,
+    tools: &dyn ToolRegistry,
+) -> crate::core::Result<McpImport> {
+    let mut import = McpImport::default();
+    for server in &config.servers {
+        let client = Arc::new(McpClient::new(server.clone()));
+        let infos = match client.list_tools().await {
+            Ok(infos) => infos,
+            Err(e) => {
+                import.errors.push((server.name.clone(), e.to_string()));
+                continue;
+            }
+        };
+
+        for info in infos {
+            let tool = McpTool::new(client.clone(), info);
+            let id = tool.id().to_string();
+            if tools.get(&id).await?.is_some() {
+                import.errors.push((
+                    server.name.clone(),
+                    format!("tool '{}' is already registered", id),
+                ));
+                continue;
+            }
+            tools.register(Box::new(tool)).await?;
+            import.loaded.push(id);
+        }
+        import.clients.push(client);
+    }
+    Ok(import)
+}
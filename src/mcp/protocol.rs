@@ -0,0 +1,193 @@
+//! JSON-RPC messages and MCP payloads shared by the client and server
+
+use crate::core::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// MCP revision this crate speaks
+pub const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Revisions accepted from the other side
+pub const SUPPORTED_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// JSON-RPC error codes used by MCP
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Any JSON-RPC message: a request, a notification (no `id`) or a response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// The `error` member of a failed response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Message {
+    pub fn request(id: u64, method: &str, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(Value::from(id)),
+            method: Some(method.to_string()),
+            params,
+            ..Default::default()
+        }
+    }
+
+    pub fn notification(method: &str, params: Option<Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: Some(method.to_string()),
+            params,
+            ..Default::default()
+        }
+    }
+
+    pub fn response(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            result: Some(result),
+            ..Default::default()
+        }
+    }
+
+    pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Whether this is a response (rather than a request or notification)
+    pub fn is_response(&self) -> bool {
+        self.method.is_none() && (self.result.is_some() || self.error.is_some())
+    }
+
+    /// The result of a response, or its error as an `AppError`
+    pub fn into_result(self) -> Result<Value> {
+        match self.error {
+            Some(error) => Err(AppError::Tool(format!(
+                "MCP error {}: {}",
+                error.code, error.message
+            ))),
+            None => Ok(self.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// A tool as described by `tools/list`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "empty_schema")]
+    pub input_schema: Value,
+}
+
+fn empty_schema() -> Value {
+    serde_json::json!({"type": "object", "properties": {}})
+}
+
+/// Result of `tools/list`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListToolsResult {
+    #[serde(default)]
+    pub tools: Vec<ToolInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Result of `tools/call`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    #[serde(default)]
+    pub content: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// The text blocks of the result joined by newlines
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_kinds() {
+        let request = Message::request(1, "tools/list", None);
+        let text = serde_json::to_string(&request).unwrap();
+        assert_eq!(text, r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#);
+        assert!(!request.is_response());
+
+        let response: Message =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","id":1,"result":{"tools":[]}}"#).unwrap();
+        assert!(response.is_response());
+        assert_eq!(
+            response.into_result().unwrap()["tools"],
+            serde_json::json!([])
+        );
+
+        let failed = Message::error_response(Value::from(2), METHOD_NOT_FOUND, "nope");
+        assert!(failed.is_response());
+        assert!(failed.into_result().is_err());
+    }
+
+    #[test]
+    fn test_call_result_text() {
+        let result: CallToolResult = serde_json::from_value(serde_json::json!({
+            "content": [
+                {"type": "text", "text": "first"},
+                {"type": "image", "data": "AAAA", "mimeType": "image/png"},
+                {"type": "text", "text": "second"}
+            ],
+            "isError": true
+        }))
+        .unwrap();
+        assert!(result.is_error);
+        assert_eq!(result.text(), "first\nsecond");
+    }
+}
@@ -0,0 +1,473 @@
+//! Server side of MCP: offers registered tools to other MCP clients over stdio
+//!
+//! Calls run through the same path as agent tool calls, and the progress
+//! updates tools send are forwarded as MCP progress or log notifications.
+
+use crate::agents::conversation::{ConversationManager, ProgressType};
+use crate::core::{parameters_to_json_schema, AppError, Result, Tool, ToolExecutionState};
+use crate::mcp::protocol::{
+    Message, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR, PROTOCOL_VERSION,
+    SUPPORTED_VERSIONS,
+};
+use crate::registry::CentralRegistry;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::AbortHandle;
+
+/// Agent ID reported to tools called by MCP clients
+pub const MCP_AGENT_ID: &str = "mcp-client";
+
+/// Forwards a tool's updates to the client that called it
+#[derive(Debug)]
+struct ProgressNotifier {
+    outgoing: UnboundedSender<Message>,
+    tool_id: String,
+    progress_token: Option<Value>,
+    updates: Mutex<u64>,
+}
+
+impl ProgressNotifier {
+    fn log(&self, level: &str, message: &str) {
+        let _ = self.outgoing.send(Message::notification(
+            "notifications/message",
+            Some(json!({"level": level, "logger": self.tool_id, "data": message})),
+        ));
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationManager for ProgressNotifier {
+    async fn send_thinking_update(
+        &self,
+        _agent_id: &str,
+        _step_number: usize,
+        thought: &str,
+    ) -> Result<()> {
+        self.log("debug", thought);
+        Ok(())
+    }
+
+    async fn send_progress_update(
+        &self,
+        _agent_id: &str,
+        _progress_type: ProgressType,
+        message: &str,
+        percentage: Option<f32>,
+    ) -> Result<()> {
+        let Some(token) = &self.progress_token else {
+            self.log("info", message);
+            return Ok(());
+        };
+        let mut updates = self.updates.lock().unwrap();
+        *updates += 1;
+        let mut params = json!({"progressToken": token, "message": message});
+        match percentage {
+            Some(percentage) => {
+                params["progress"] = json!(percentage);
+                params["total"] = json!(100.0);
+            }
+            // Without a percentage, count the updates so progress still increases
+            None => params["progress"] = json!(*updates),
+        }
+        let _ = self.outgoing.send(Message::notification(
+            "notifications/progress",
+            Some(params),
+        ));
+        Ok(())
+    }
+
+    async fn send_error_update(
+        &self,
+        _agent_id: &str,
+        error: &str,
+        _recovery_suggestions: Vec<String>,
+    ) -> Result<()> {
+        self.log("error", error);
+        Ok(())
+    }
+
+    async fn send_completion_update(
+        &self,
+        _agent_id: &str,
+        final_response: &str,
+        _success: bool,
+    ) -> Result<()> {
+        self.log("info", final_response);
+        Ok(())
+    }
+}
+
+/// Serves a fixed set of tools to one MCP client
+#[derive(Clone)]
+pub struct McpServer {
+    tools: Arc<Vec<Box<dyn Tool>>>,
+    execution_state: Arc<tokio::sync::RwLock<ToolExecutionState>>,
+    running: Arc<Mutex<HashMap<String, AbortHandle>>>,
+}
+
+impl McpServer {
+    pub fn new(tools: Vec<Box<dyn Tool>>) -> Self {
+        Self {
+            tools: Arc::new(tools),
+            execution_state: Default::default(),
+            running: Default::default(),
+        }
+    }
+
+    /// Serve the registry's enabled tools; a non-empty `only` limits them to those IDs
+    pub async fn from_registry(registry: &CentralRegistry, only: &[String]) -> Result<Self> {
+        let tools = registry.tools.list().await?;
+        if let Some(missing) = only
+            .iter()
+            .find(|id| !tools.iter().any(|t| t.id() == id.as_str()))
+        {
+            return Err(AppError::Registry(format!("Tool not found: {}", missing)));
+        }
+        let mut enabled = Vec::new();
+        for tool in tools {
+            if registry.tools.is_enabled(tool.id()).await? == Some(true) {
+                enabled.push(tool);
+            } else if only.iter().any(|id| id == tool.id()) {
+                return Err(AppError::Registry(format!(
+                    "Tool '{}' is disabled",
+                    tool.id()
+                )));
+            }
+        }
+        let mut tools = enabled;
+        if !only.is_empty() {
+            tools.retain(|t| only.iter().any(|id| id == t.id()));
+        }
+        tools.sort_by(|a, b| a.id().cmp(b.id()));
+        Ok(Self::new(tools))
+    }
+
+    /// Answer on stdout the requests read from stdin until stdin closes
+    pub async fn serve_stdio(self) -> Result<()> {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        self.serve(stdin, tokio::io::stdout()).await
+    }
+
+    /// Answer the newline-delimited JSON-RPC messages read from `reader`
+    pub async fn serve<R, W>(self, reader: R, mut writer: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outgoing, mut queue) = tokio::sync::mpsc::unbounded_channel::<Message>();
+        let write_task = tokio::spawn(async move {
+            while let Some(message) = queue.recv().await {
+                let mut line = serde_json::to_string(&message)?;
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+                writer.flush().await?;
+            }
+            Ok::<_, AppError>(())
+        });
+
+        let mut lines = reader.lines();
+        let mut calls = tokio::task::JoinSet::new();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message: Message = match serde_json::from_str(&line) {
+                Ok(message) => message,
+                Err(e) => {
+                    let _ = outgoing.send(Message::error_response(
+                        Value::Null,
+                        PARSE_ERROR,
+                        e.to_string(),
+                    ));
+                    continue;
+                }
+            };
+
+            // Tool calls may be slow, so they run alongside further requests
+            if message.method.as_deref() == Some("tools/call") && message.id.is_some() {
+                let server = self.clone();
+                let outgoing = outgoing.clone();
+                let key = message
+                    .id
+                    .as_ref()
+                    .map(Value::to_string)
+                    .unwrap_or_default();
+                // Holding the lock keeps the call from finishing before it is recorded
+                let mut running = self.running.lock().unwrap();
+                let task_key = key.clone();
+                let handle = calls.spawn(async move {
+                    let response = server.call_tool(message, &outgoing).await;
+                    server.running.lock().unwrap().remove(&task_key);
+                    let _ = outgoing.send(response);
+                });
+                running.insert(key, handle);
+                continue;
+            }
+            if let Some(response) = self.handle(message) {
+                let _ = outgoing.send(response);
+            }
+        }
+
+        // Let running calls answer before the output closes
+        while calls.join_next().await.is_some() {}
+        drop(outgoing);
+        write_task
+            .await
+            .map_err(|e| AppError::Tool(format!("MCP writer failed: {}", e)))?
+    }
+
+    /// Answer every request except `tools/call`; notifications get no answer
+    fn handle(&self, message: Message) -> Option<Message> {
+        let method = message.method.clone().unwrap_or_default();
+        let Some(id) = message.id else {
+            if method == "notifications/cancelled" {
+                let params = message.params.unwrap_or_default();
+                let key = params["requestId"].to_string();
+                if let Some(handle) = self.running.lock().unwrap().remove(&key) {
+                    handle.abort();
+                }
+            }
+            return None;
+        };
+
+        let result = match method.as_str() {
+            "initialize" => {
+                let requested = message
+                    .params
+                    .as_ref()
+                    .and_then(|p| p["protocolVersion"].as_str())
+                    .unwrap_or_default();
+                let version = if SUPPORTED_VERSIONS.contains(&requested) {
+                    requested
+                } else {
+                    PROTOCOL_VERSION
+                };
+                json!({
+                    "protocolVersion": version,
+                    "capabilities": {"tools": {"listChanged": false}, "logging": {}},
+                    "serverInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                })
+            }
+            "ping" | "logging/setLevel" => json!({}),
+            "tools/list" => json!({ "tools": self.tool_list() }),
+            "" => {
+                return Some(Message::error_response(
+                    id,
+                    INVALID_REQUEST,
+                    "Missing method",
+                ))
+            }
+            _ => {
+                return Some(Message::error_response(
+                    id,
+                    METHOD_NOT_FOUND,
+                    format!("Unknown method: {}", method),
+                ))
+            }
+        };
+        Some(Message::response(id, result))
+    }
+
+    fn tool_list(&self) -> Vec<Value> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.id(),
+                    "title": tool.name(),
+                    "description": tool.description(),
+                    "inputSchema": parameters_to_json_schema(tool.parameters()),
+                })
+            })
+            .collect()
+    }
+
+    async fn call_tool(&self, message: Message, outgoing: &UnboundedSender<Message>) -> Message {
+        let id = message.id.unwrap_or_default();
+        let params = message.params.unwrap_or_default();
+        let Some(name) = params["name"].as_str() else {
+            return Message::error_response(id, INVALID_PARAMS, "Missing tool name");
+        };
+        if !self.tools.iter().any(|t| t.id() == name) {
+            return Message::error_response(id, INVALID_PARAMS, format!("Unknown tool: {}", name));
+        }
+        let arguments = match &params["arguments"] {
+            Value::Null => json!({}),
+            arguments => arguments.clone(),
+        };
+
+        let notifier: Arc<dyn ConversationManager> = Arc::new(ProgressNotifier {
+            outgoing: outgoing.clone(),
+            tool_id: name.to_string(),
+            progress_token: params["_meta"].get("progressToken").cloned(),
+            updates: Mutex::new(0),
+        });
+        let observation = crate::agents::thinking::execute_tool(
+            MCP_AGENT_ID,
+            name,
+            &arguments,
+            &self.tools,
+            &Some(notifier),
+            &self.execution_state,
+        )
+        .await;
+
+        let mut result = json!({
+            "content": [{"type": "text", "text": observation.message}],
+            "isError": !observation.success,
+        });
+        if let Some(data) = observation.data {
+            result["content"]
+                .as_array_mut()
+                .expect("content is an array")
+                .push(json!({"type": "text", "text": data.to_string()}));
+            if data.is_object() {
+                result["structuredContent"] = data;
+            }
+        }
+        Message::response(id, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{ToolContext, ToolParameter, ToolResult};
+
+    #[derive(Clone)]
+    struct Shout;
+
+    #[async_trait::async_trait]
+    impl Tool for Shout {
+        fn id(&self) -> &str {
+            "shout"
+        }
+        fn name(&self) -> &str {
+            "Shout"
+        }
+        fn description(&self) -> &str {
+            "Upper-cases text"
+        }
+        fn category(&self) -> &str {
+            "test"
+        }
+        fn parameters(&self) -> &[ToolParameter] {
+            &[]
+        }
+        async fn execute(&self, args: &Value, context: &ToolContext) -> Result<ToolResult> {
+            if let Some(manager) = &context.conversation_manager {
+                manager
+                    .send_progress_update(
+                        &context.agent_id,
+                        ProgressType::Executing,
+                        "shouting",
+                        Some(50.0),
+                    )
+                    .await?;
+            }
+            let text = args["text"]
+                .as_str()
+                .ok_or_else(|| AppError::Tool("text is required".to_string()))?;
+            Ok(ToolResult {
+                success: true,
+                message: text.to_uppercase(),
+                data: Some(json!({"length": text.len()})),
+                execution_time: std::time::Duration::ZERO,
+            })
+        }
+        fn validate_args(&self, _args: &Value) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn exchange(requests: &[Value]) -> Vec<Message> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let (client, server_end) = tokio::io::duplex(64 * 1024);
+        let server = McpServer::new(vec![Box::new(Shout)]);
+        let reader = tokio::io::BufReader::new(input.as_bytes());
+        server.serve(reader, server_end).await.unwrap();
+
+        let mut output = String::new();
+        let mut client = client;
+        tokio::io::AsyncReadExt::read_to_string(&mut client, &mut output)
+            .await
+            .unwrap();
+        output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_lists_and_calls_tools() {
+        let messages = exchange(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2024-11-05"}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "shout", "arguments": {"text": "hi"}, "_meta": {"progressToken": "p1"}}}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {"name": "nope"}}),
+            json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {"name": "shout"}}),
+            json!({"jsonrpc": "2.0", "id": 6, "method": "resources/list"}),
+        ])
+        .await;
+        let response = |id: u64| {
+            messages
+                .iter()
+                .find(|m| m.is_response() && m.id == Some(Value::from(id)))
+                .cloned()
+                .unwrap()
+        };
+
+        assert_eq!(response(1).result.unwrap()["protocolVersion"], "2024-11-05");
+        let tools = response(2).result.unwrap();
+        assert_eq!(tools["tools"][0]["name"], "shout");
+        assert_eq!(tools["tools"][0]["inputSchema"]["type"], "object");
+
+        let call = response(3).result.unwrap();
+        assert_eq!(call["isError"], false);
+        assert_eq!(call["content"][0]["text"], "HI");
+        assert_eq!(call["structuredContent"]["length"], 2);
+        assert!(messages.iter().any(|m| {
+            m.method.as_deref() == Some("notifications/progress")
+                && m.params.as_ref().unwrap()["progressToken"] == "p1"
+        }));
+
+        assert_eq!(response(4).error.unwrap().code, INVALID_PARAMS);
+        assert_eq!(response(5).result.unwrap()["isError"], true);
+        assert_eq!(response(6).error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_client_round_trip_over_stdio_protocol() {
+        // Our own client can import the tools this server offers
+        let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_end);
+        let server = McpServer::new(vec![Box::new(Shout)]);
+        tokio::spawn(server.serve(tokio::io::BufReader::new(server_read), server_write));
+
+        let (read, mut write) = tokio::io::split(client_end);
+        let mut lines = tokio::io::BufReader::new(read).lines();
+        let request = Message::request(1, "tools/list", None);
+        write
+            .write_all(format!("{}\n", serde_json::to_string(&request).unwrap()).as_bytes())
+            .await
+            .unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        let listed: crate::mcp::protocol::ListToolsResult = serde_json::from_value(
+            serde_json::from_str::<Message>(&line)
+                .unwrap()
+                .into_result()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(listed.tools[0].name, "shout");
+        assert_eq!(listed.tools[0].title.as_deref(), Some("Shout"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Tools imported from an MCP server
+
+use crate::core::{tool_id, AppError, Result, Tool, ToolContext, ToolParameter, ToolResult};
+use crate::mcp::client::McpClient;
+use crate::mcp::protocol::ToolInfo;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Flatten the top level of a JSON Schema object into tool parameters
+pub fn schema_to_parameters(schema: &Value) -> Vec<ToolParameter> {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema["properties"].as_object() else {
+        return Vec::new();
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            // `["number", "null"]` means an optional number
+            let param_type = match &property["type"] {
+                Value::String(t) => t.clone(),
+                Value::Array(types) => types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|t| *t != "null")
+                    .unwrap_or("string")
+                    .to_string(),
+                _ => "string".to_string(),
+            };
+            let enum_values = property["enum"].as_array().and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            });
+            ToolParameter {
+                name: name.clone(),
+                param_type,
+                description: property["description"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                required: required.contains(&name.as_str()),
+                default: property.get("default").cloned(),
+                enum_values,
+            }
+        })
+        .collect()
+}
+
+/// A tool that forwards `execute` to an MCP server
+#[derive(Clone)]
+pub struct McpTool {
+    client: Arc<McpClient>,
+    info: ToolInfo,
+    id: String,
+    category: String,
+    parameters: Vec<ToolParameter>,
+}
+
+impl McpTool {
+    pub fn new(client: Arc<McpClient>, info: ToolInfo) -> Self {
+        let config = client.config();
+        Self {
+            id: tool_id(&config.name, &info.name),
+            category: config.category(),
+            parameters: schema_to_parameters(&info.input_schema),
+            client,
+            info,
+        }
+    }
+
+    /// The tool as the server describes it
+    pub fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    /// Name of the server the tool belongs to
+    pub fn server(&self) -> &str {
+        &self.client.config().name
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for McpTool {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The registry ID, so same-named tools from different servers stay apart
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn description(&self) -> &str {
+        &self.info.description
+    }
+
+    fn category(&self) -> &str {
+        &self.category
+    }
+
+    fn parameters(&self) -> &[ToolParameter] {
+        &self.parameters
+    }
+
+    async fn execute(&self, args: &Value, _context: &ToolContext) -> Result<ToolResult> {
+        let start = Instant::now();
+        let result = self.client.call_tool(&self.info.name, args).await?;
+        let only_text = result.content.iter().all(|block| block["type"] == "text");
+        let data = match result.structured_content.clone() {
+            Some(structured) => Some(structured),
+            None if !only_text => Some(Value::Array(result.content.clone())),
+            None => None,
+        };
+        Ok(ToolResult {
+            success: !result.is_error,
+            message: result.text(),
+            data,
+            execution_time: start.elapsed(),
+        })
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<()> {
+        let fields = args
+            .as_object()
+            .ok_or_else(|| AppError::Tool("Invalid arguments: expected an object".to_string()))?;
+        for param in &self.parameters {
+            if param.required && !fields.contains_key(&param.name) {
+                return Err(AppError::Tool(format!(
+                    "Invalid arguments: missing '{}'",
+                    param.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_to_parameters() {
+        let params = schema_to_parameters(&serde_json::json!({
+            "type": "object",
+            "properties": {
+                "mode": {"type": "string", "enum": ["fast", "slow"], "description": "Speed"},
+                "count": {"type": ["integer", "null"], "default": 3}
+            },
+            "required": ["mode"]
+        }));
+        let mode = params.iter().find(|p| p.name == "mode").unwrap();
+        assert!(mode.required);
+        assert_eq!(mode.description, "Speed");
+        assert_eq!(
+            mode.enum_values.as_deref(),
+            Some(&["fast".to_string(), "slow".to_string()][..])
+        );
+        let count = params.iter().find(|p| p.name == "count").unwrap();
+        assert_eq!(count.param_type, "integer");
+        assert!(!count.required);
+        assert_eq!(count.default, Some(serde_json::json!(3)));
+
+        assert!(schema_to_parameters(&serde_json::json!({"type": "object"})).is_empty());
+        assert_eq!(tool_id("files", "read file"), "files__read_file");
+    }
+}
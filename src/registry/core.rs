@@ -1,8 +1,15 @@
 //! Core registry traits and central registry system
 
-use crate::agents::registry::{AgentRegistry, DefaultAgentRegistry};
+use crate::agents::registry::{AgentRegistry, Availability, DefaultAgentRegistry};
+use crate::core::AppError;
+use crate::registry::dependencies::DependencyGraph;
+use crate::registry::events::{EventSender, RegistryEvent, EVENT_CAPACITY};
+use crate::registry::loader::{ComponentLoader, PluginScan};
+use crate::registry::presets::get_default_presets;
 use crate::tools::registry::{DefaultToolRegistry, ToolRegistry};
 use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::sync::{broadcast, Mutex};
 
 /// Core trait for all component registries
 #[async_trait]
@@ -36,100 +43,168 @@ pub struct ComponentMetadata {
 }
 
 /// Central registry that manages all component registries
+///
+/// Both registries lock internally, so a shared `Arc<CentralRegistry>` can be
+/// changed at runtime; every change is published on one broadcast channel.
+/// Register and unregister through the methods here so agent availability
+/// follows; bulk loads that go to the registries directly should finish with
+/// `refresh_availability`.
 pub struct CentralRegistry {
     pub agents: Box<dyn AgentRegistry>,
     pub tools: Box<dyn ToolRegistry>,
+    events: EventSender,
+    /// Finds plugins and agent definitions on disk, and remembers what it registered
+    loader: Mutex<ComponentLoader>,
 }
 
 impl CentralRegistry {
     pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
         Self {
-            agents: Box::new(DefaultAgentRegistry::new()),
-            tools: Box::new(DefaultToolRegistry::new()),
+            agents: Box::new(DefaultAgentRegistry::new().with_events(events.clone())),
+            tools: Box::new(DefaultToolRegistry::new().with_events(events.clone())),
+            events,
+            loader: Mutex::new(ComponentLoader::new()),
         }
     }
 
+    /// Scan `loader`'s directories instead of `CF_AI_PLUGINS_DIR` and `CF_AI_AGENTS_DIR`
+    pub fn with_loader(mut self, loader: ComponentLoader) -> Self {
+        self.loader = Mutex::new(loader);
+        self
+    }
+
+    /// Receive every registry change made from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
     /// Initialize the central registry with built-in components
-    pub async fn initialize(&mut self) -> crate::core::Result<()> {
+    pub async fn initialize(&self) -> crate::core::Result<()> {
         // Register built-in agents
         self.register_builtin_agents().await?;
 
         // Register built-in tools
         self.register_builtin_tools().await?;
 
+        self.refresh_availability().await?;
         Ok(())
     }
 
-    async fn register_builtin_agents(&mut self) -> crate::core::Result<()> {
-        // Register desktop automation agent
-        let desktop_agent: Box<dyn crate::core::Agent> =
-            Box::new(crate::agents::DesktopAutomationAgent::new());
-        self.agents.register(desktop_agent).await?;
+    /// Dependency graph of the registered agents and tools plus the default presets
+    pub async fn dependency_graph(&self) -> crate::core::Result<DependencyGraph> {
+        DependencyGraph::build(self, &get_default_presets()).await
+    }
 
-        // Register web research agent
-        let web_agent: Box<dyn crate::core::Agent> =
-            Box::new(crate::agents::WebResearchAgent::new());
-        self.agents.register(web_agent).await?;
+    /// Disable agents whose tools are missing and re-enable those whose tools are back
+    pub async fn refresh_availability(&self) -> crate::core::Result<DependencyGraph> {
+        let graph = self.dependency_graph().await?;
+        let usable = graph.usable_tools();
+        for agent in self.agents.list().await? {
+            let availability = match self
+                .agents
+                .validate_dependencies(agent.as_ref(), &usable)
+                .await
+            {
+                Ok(()) => Availability::Available,
+                Err(AppError::Registry(reason)) => Availability::Disabled { reason },
+                Err(e) => return Err(e),
+            };
+            if let Err(e) = self.agents.set_availability(agent.id(), availability).await {
+                // The agent may have been unregistered since it was listed
+                if self.agents.get(agent.id()).await?.is_some() {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(graph)
+    }
 
-        // Register conversational agent
-        let conv_agent: Box<dyn crate::core::Agent> =
-            Box::new(crate::agents::ConversationalAgent::new());
-        self.agents.register(conv_agent).await?;
+    /// Add an agent, disabled right away if its tools are missing
+    pub async fn register_agent(
+        &self,
+        agent: Box<dyn crate::core::Agent>,
+    ) -> crate::core::Result<DependencyGraph> {
+        self.agents.register(agent).await?;
+        self.refresh_availability().await
+    }
 
-        Ok(())
+    /// Remove an agent
+    pub async fn unregister_agent(&self, id: &str) -> crate::core::Result<DependencyGraph> {
+        self.agents.unregister(id).await?;
+        self.refresh_availability().await
     }
 
-    async fn register_builtin_tools(&mut self) -> crate::core::Result<()> {
-        // Register desktop automation tools
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::mouse::MoveCursor::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::mouse::Click::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::mouse::Scroll::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::keyboard::TypeText::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::keyboard::Hotkey::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::screen::Screenshot::new(),
-            ))
-            .await?;
-        self.tools
-            .register(Box::new(
-                crate::tools::desktop_automation::screen::GetPosition::new(),
-            ))
-            .await?;
+    /// Add a tool, then re-enable agents that were waiting for it
+    pub async fn register_tool(
+        &self,
+        tool: Box<dyn crate::core::Tool>,
+    ) -> crate::core::Result<DependencyGraph> {
+        self.tools.register(tool).await?;
+        self.refresh_availability().await
+    }
 
-        // Register web tools
-        self.tools
-            .register(Box::new(crate::tools::web::WebSearch::new()))
-            .await?;
-        self.tools
-            .register(Box::new(crate::tools::web::FetchUrl::new()))
+    /// Remove a tool, then disable agents that can no longer run
+    pub async fn unregister_tool(&self, id: &str) -> crate::core::Result<DependencyGraph> {
+        self.tools.unregister(id).await?;
+        self.refresh_availability().await
+    }
+
+    /// Replace the plugin tools from the last scan with the plugins on disk now
+    pub async fn rescan_plugins(&self) -> crate::core::Result<PluginScan> {
+        let scan = self
+            .loader
+            .lock()
+            .await
+            .rescan_plugins(self.tools.as_ref())
             .await?;
+        self.refresh_availability().await?;
+        Ok(scan)
+    }
 
-        // Register delegation tools
-        self.tools
-            .register(Box::new(crate::tools::delegation::DelegateToAgent::new()))
+    /// Replace the agents from the last scan with the definitions on disk now
+    pub async fn rescan_agents(&self) -> crate::core::Result<PluginScan> {
+        let scan = self
+            .loader
+            .lock()
+            .await
+            .rescan_agents(self.agents.as_ref())
             .await?;
+        self.refresh_availability().await?;
+        Ok(scan)
+    }
+
+    /// Directory scanned for tool plugins
+    pub async fn plugins_path(&self) -> PathBuf {
+        self.loader.lock().await.plugins_path().to_path_buf()
+    }
+
+    /// Directory scanned for agent definitions, if any
+    pub async fn agents_path(&self) -> Option<PathBuf> {
+        self.loader.lock().await.agents_path().map(PathBuf::from)
+    }
+
+    /// Offer a tool to every agent or to none, then update which agents can run
+    pub async fn set_tool_enabled(
+        &self,
+        tool_id: &str,
+        enabled: bool,
+    ) -> crate::core::Result<DependencyGraph> {
+        self.tools.set_enabled(tool_id, enabled).await?;
+        self.refresh_availability().await
+    }
+
+    async fn register_builtin_agents(&self) -> crate::core::Result<()> {
+        for agent in ComponentLoader::new().load_builtin_agents() {
+            self.agents.register(agent).await?;
+        }
+        Ok(())
+    }
 
+    async fn register_builtin_tools(&self) -> crate::core::Result<()> {
+        for tool in ComponentLoader::new().load_builtin_tools() {
+            self.tools.register(tool).await?;
+        }
         Ok(())
     }
 
@@ -139,3 +214,191 @@ impl CentralRegistry {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_shared_registry_publishes_changes() {
+        let registry = Arc::new(CentralRegistry::new());
+        let mut events = registry.subscribe();
+        registry.initialize().await.unwrap();
+
+        let mut seen = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            seen.push(event);
+        }
+        assert!(seen.contains(&RegistryEvent::AgentAdded {
+            agent_id: "web-research-agent".to_string()
+        }));
+        assert!(seen.contains(&RegistryEvent::ToolEnabled {
+            tool_id: "fetch_url".to_string()
+        }));
+
+        // Changes made through a shared handle, e.g. from a spawned task
+        let shared = registry.clone();
+        tokio::spawn(async move { shared.unregister_tool("fetch_url").await.unwrap() })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::ToolRemoved {
+                tool_id: "fetch_url".to_string()
+            }
+        );
+        match events.recv().await.unwrap() {
+            RegistryEvent::AgentUpdated {
+                agent_id,
+                availability: Availability::Disabled { reason },
+            } => {
+                assert_eq!(agent_id, "web-research-agent");
+                assert!(reason.contains("fetch_url"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(events.try_recv().is_err(), "other agents are unchanged");
+    }
+
+    #[tokio::test]
+    async fn test_rescan_registers_plugins_added_after_startup() {
+        let plugins = tempfile::tempdir().unwrap();
+        let registry = Arc::new(
+            CentralRegistry::new().with_loader(ComponentLoader::with_plugins_dir(plugins.path())),
+        );
+        registry.initialize().await.unwrap();
+        assert!(registry.rescan_plugins().await.unwrap().loaded.is_empty());
+        let mut events = registry.subscribe();
+
+        let dir = plugins.path().join("late");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("plugin.toml"),
+            "id = \"late_tool\"\nname = \"Late\"\ndescription = \"Added later\"\ncommand = \"./run.sh\"\n",
+        )
+        .unwrap();
+
+        // As a signal handler or the chat REPL would, through a shared handle
+        let shared = registry.clone();
+        let scan = tokio::spawn(async move { shared.rescan_plugins().await.unwrap() })
+            .await
+            .unwrap();
+        assert_eq!(scan.loaded, vec!["late_tool"]);
+        assert!(registry.tools.get("late_tool").await.unwrap().is_some());
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::ToolEnabled {
+                tool_id: "late_tool".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_tools_disable_their_agents() {
+        let registry = CentralRegistry::new();
+        registry.initialize().await.unwrap();
+        let mut events = registry.subscribe();
+
+        let graph = registry
+            .set_tool_enabled("web_search", false)
+            .await
+            .unwrap();
+        assert!(!graph.usable_tools().contains(&"web_search".to_string()));
+        assert_eq!(
+            registry.tools.is_enabled("web_search").await.unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::ToolDisabled {
+                tool_id: "web_search".to_string()
+            }
+        );
+        assert!(!registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_available());
+
+        // Disabling twice changes nothing
+        registry
+            .set_tool_enabled("web_search", false)
+            .await
+            .unwrap();
+        while let Ok(event) = events.try_recv() {
+            assert!(matches!(event, RegistryEvent::AgentUpdated { .. }));
+        }
+
+        registry.set_tool_enabled("web_search", true).await.unwrap();
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::ToolEnabled {
+                tool_id: "web_search".to_string()
+            }
+        );
+        assert!(registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_available());
+        assert!(registry
+            .set_tool_enabled("no_such_tool", false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_register_and_unregister_keep_availability_current() {
+        let registry = CentralRegistry::new();
+        let available = |id: &'static str| {
+            let registry = &registry;
+            async move {
+                registry
+                    .agents
+                    .availability(id)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .is_available()
+            }
+        };
+
+        // No tools yet, so the agent is disabled as soon as it is registered
+        registry
+            .register_agent(Box::new(crate::agents::ConfigAgent::web_research()))
+            .await
+            .unwrap();
+        assert!(!available("web-research-agent").await);
+
+        registry
+            .register_tool(Box::new(crate::tools::web::WebSearch::new()))
+            .await
+            .unwrap();
+        assert!(!available("web-research-agent").await);
+        registry
+            .register_tool(Box::new(crate::tools::web::FetchUrl::new()))
+            .await
+            .unwrap();
+        assert!(available("web-research-agent").await);
+
+        registry.unregister_tool("web_search").await.unwrap();
+        assert!(!available("web-research-agent").await);
+
+        registry
+            .unregister_agent("web-research-agent")
+            .await
+            .unwrap();
+        assert!(registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
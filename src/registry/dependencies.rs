@@ -0,0 +1,344 @@
+//! Dependency graph across agents, tools and presets
+//!
+//! Every agent, tool and preset that names a tool gets a "requires" edge to it.
+//! A tool is usable when it is registered, enabled and everything it requires is usable;
+//! an edge is satisfied when its tool is usable.
+
+use crate::core::{Agent, Result, Tool};
+use crate::registry::core::CentralRegistry;
+use crate::registry::presets::PresetAgent;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Kind of component in the dependency graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    Agent,
+    Tool,
+    Preset,
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComponentKind::Agent => "agent",
+            ComponentKind::Tool => "tool",
+            ComponentKind::Preset => "preset",
+        })
+    }
+}
+
+/// A component in the graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Component {
+    pub kind: ComponentKind,
+    pub id: String,
+    pub capabilities: Vec<String>,
+    /// Whether every tool it requires is usable
+    pub available: bool,
+}
+
+/// A component requiring a tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub kind: ComponentKind,
+    pub id: String,
+    pub tool: String,
+    /// Whether the tool is registered and usable
+    pub satisfied: bool,
+}
+
+/// Who requires which tools, and who provides which capabilities
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyGraph {
+    pub components: Vec<Component>,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl DependencyGraph {
+    /// Graph of the registered agents and tools plus `presets`
+    pub async fn build(registry: &CentralRegistry, presets: &[PresetAgent]) -> Result<Self> {
+        let agents = registry.agents.list().await?;
+        let tools = registry.tools.list().await?;
+        let mut disabled = Vec::new();
+        for tool in &tools {
+            if registry.tools.is_enabled(tool.id()).await? == Some(false) {
+                disabled.push(tool.id().to_string());
+            }
+        }
+        Ok(Self::from_components(&agents, &tools, &disabled, presets))
+    }
+
+    /// Graph of the given components; `disabled` tools are never usable
+    pub fn from_components(
+        agents: &[Box<dyn Agent>],
+        tools: &[Box<dyn Tool>],
+        disabled: &[String],
+        presets: &[PresetAgent],
+    ) -> Self {
+        let requires: BTreeMap<&str, &[String]> = tools
+            .iter()
+            .filter(|tool| !disabled.iter().any(|id| id == tool.id()))
+            .map(|tool| (tool.id(), tool.tool_dependencies()))
+            .collect();
+        let usable: HashSet<&str> = requires
+            .keys()
+            .copied()
+            .filter(|&id| is_usable(id, &requires, &mut Vec::new()))
+            .collect();
+
+        let mut graph = Self::default();
+        for tool in tools {
+            graph.add(
+                ComponentKind::Tool,
+                tool.id(),
+                tool.capabilities(),
+                tool.tool_dependencies().iter().map(String::as_str),
+                &usable,
+            );
+        }
+        for agent in agents {
+            graph.add(
+                ComponentKind::Agent,
+                agent.id(),
+                agent.capabilities(),
+                agent.tool_dependencies().iter().map(String::as_str),
+                &usable,
+            );
+        }
+        for preset in presets {
+            graph.add(
+                ComponentKind::Preset,
+                &preset.id,
+                &[],
+                preset.tools.iter().map(|t| t.tool_id.as_str()),
+                &usable,
+            );
+        }
+        graph
+            .components
+            .sort_by(|a, b| (a.kind, &a.id).cmp(&(b.kind, &b.id)));
+        graph
+    }
+
+    fn add<'a>(
+        &mut self,
+        kind: ComponentKind,
+        id: &str,
+        capabilities: &[String],
+        tools: impl Iterator<Item = &'a str>,
+        usable: &HashSet<&str>,
+    ) {
+        let mut available = kind != ComponentKind::Tool || usable.contains(id);
+        for tool in tools {
+            let satisfied = usable.contains(tool);
+            available &= satisfied;
+            self.dependencies.push(Dependency {
+                kind,
+                id: id.to_string(),
+                tool: tool.to_string(),
+                satisfied,
+            });
+        }
+        self.components.push(Component {
+            kind,
+            id: id.to_string(),
+            capabilities: capabilities.to_vec(),
+            available,
+        });
+    }
+
+    /// A component, if it is in the graph
+    pub fn component(&self, kind: ComponentKind, id: &str) -> Option<&Component> {
+        self.components
+            .iter()
+            .find(|c| c.kind == kind && c.id == id)
+    }
+
+    /// The tools a component requires
+    pub fn dependencies_of(&self, kind: ComponentKind, id: &str) -> Vec<&Dependency> {
+        self.dependencies
+            .iter()
+            .filter(|d| d.kind == kind && d.id == id)
+            .collect()
+    }
+
+    /// Components that require `tool_id`
+    pub fn dependents_of(&self, tool_id: &str) -> Vec<&Dependency> {
+        self.dependencies
+            .iter()
+            .filter(|d| d.tool == tool_id)
+            .collect()
+    }
+
+    /// Components that provide `capability`
+    pub fn providers_of(&self, capability: &str) -> Vec<&Component> {
+        self.components
+            .iter()
+            .filter(|c| c.capabilities.iter().any(|cap| cap == capability))
+            .collect()
+    }
+
+    /// Requirements whose tool is missing or unusable
+    pub fn missing(&self) -> Vec<&Dependency> {
+        self.dependencies.iter().filter(|d| !d.satisfied).collect()
+    }
+
+    /// IDs of the enabled tools whose own requirements are met
+    pub fn usable_tools(&self) -> Vec<String> {
+        self.components
+            .iter()
+            .filter(|c| c.kind == ComponentKind::Tool && c.available)
+            .map(|c| c.id.clone())
+            .collect()
+    }
+}
+
+/// Whether `id` is registered and all it requires is, following the chain
+fn is_usable<'a>(
+    id: &'a str,
+    requires: &BTreeMap<&str, &'a [String]>,
+    path: &mut Vec<&'a str>,
+) -> bool {
+    let Some(&dependencies) = requires.get(id) else {
+        return false;
+    };
+    // A cycle can never be satisfied
+    if path.contains(&id) {
+        return false;
+    }
+    path.push(id);
+    let usable = dependencies
+        .iter()
+        .all(|dependency| is_usable(dependency, requires, path));
+    path.pop();
+    usable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::presets::get_default_presets;
+    use crate::registry::ComponentLoader;
+    use crate::tools::{PluginManifest, PluginTool};
+
+    fn plugin(id: &str, tools: &[&str]) -> Box<dyn Tool> {
+        let manifest: PluginManifest = toml::from_str(&format!(
+            "id = \"{}\"\nname = \"{}\"\ndescription = \"Test\"\ncommand = \"true\"\ncapabilities = [\"testing\"]",
+            id, id
+        ))
+        .unwrap();
+        Box::new(PluginTool::new(
+            PluginManifest {
+                tools: tools.iter().map(|t| t.to_string()).collect(),
+                ..manifest
+            },
+            ".",
+        ))
+    }
+
+    #[test]
+    fn test_builtin_components_and_presets_resolve() {
+        let loader = ComponentLoader::with_plugins_dir("unused");
+        let graph = DependencyGraph::from_components(
+            &loader.load_builtin_agents(),
+            &loader.load_builtin_tools(),
+            &[],
+            &get_default_presets(),
+        );
+        assert!(graph.missing().is_empty(), "{:?}", graph.missing());
+        assert!(
+            graph
+                .component(ComponentKind::Agent, "desktop-automation-agent")
+                .unwrap()
+                .available
+        );
+
+        let typists = graph.dependents_of("keyboard_type");
+        assert!(typists.iter().any(|d| d.kind == ComponentKind::Agent));
+        assert!(typists.iter().any(|d| d.kind == ComponentKind::Preset));
+        let providers = graph.providers_of("keyboard_control");
+        assert!(providers
+            .iter()
+            .any(|c| c.kind == ComponentKind::Tool && c.id == "keyboard_type"));
+    }
+
+    #[test]
+    fn test_missing_tools_propagate() {
+        let tools = vec![
+            plugin("base", &["gone"]),
+            plugin("derived", &["base"]),
+            plugin("loop_a", &["loop_b"]),
+            plugin("loop_b", &["loop_a"]),
+            plugin("ok", &[]),
+        ];
+        let graph = DependencyGraph::from_components(&[], &tools, &["ok".to_string()], &[]);
+        assert!(
+            graph.usable_tools().is_empty(),
+            "disabled tools are unusable"
+        );
+
+        let graph = DependencyGraph::from_components(&[], &tools, &[], &[]);
+        assert_eq!(graph.usable_tools(), vec!["ok"]);
+        let missing: Vec<_> = graph.missing().iter().map(|d| d.tool.as_str()).collect();
+        assert_eq!(missing, vec!["gone", "base", "loop_b", "loop_a"]);
+        assert_eq!(
+            graph.dependencies_of(ComponentKind::Tool, "derived").len(),
+            1
+        );
+        assert_eq!(graph.providers_of("testing").len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_disables_agents_with_missing_tools() {
+        let registry = CentralRegistry::new();
+        registry.initialize().await.unwrap();
+        let available = registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap();
+        assert_eq!(
+            available,
+            Some(crate::agents::registry::Availability::Available)
+        );
+
+        let graph = registry.unregister_tool("fetch_url").await.unwrap();
+        assert!(graph
+            .dependents_of("fetch_url")
+            .iter()
+            .all(|d| !d.satisfied));
+        match registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+        {
+            Some(crate::agents::registry::Availability::Disabled { reason }) => {
+                assert!(reason.contains("fetch_url"))
+            }
+            other => panic!("expected the agent to be disabled, got {:?}", other),
+        }
+        assert!(registry
+            .agents
+            .availability("conversational-agent")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_available());
+
+        registry
+            .register_tool(Box::new(crate::tools::web::FetchUrl::new()))
+            .await
+            .unwrap();
+        assert!(registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_available());
+    }
+}
@@ -0,0 +1,52 @@
+//! Change notifications published by the registries
+
+use crate::agents::registry::Availability;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events kept for subscribers that fall behind
+pub const EVENT_CAPACITY: usize = 256;
+
+/// Sender shared by the registries of one `CentralRegistry`
+pub type EventSender = broadcast::Sender<RegistryEvent>;
+
+/// Something that changed in a registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RegistryEvent {
+    AgentAdded {
+        agent_id: String,
+    },
+    /// The agent was enabled or disabled
+    AgentUpdated {
+        agent_id: String,
+        availability: Availability,
+    },
+    AgentRemoved {
+        agent_id: String,
+    },
+    /// The tool can be offered to agents
+    ToolEnabled {
+        tool_id: String,
+    },
+    /// The tool can no longer be offered to agents
+    ToolDisabled {
+        tool_id: String,
+    },
+    /// The tool's configuration block changed
+    ToolConfigured {
+        tool_id: String,
+    },
+    ToolRemoved {
+        tool_id: String,
+    },
+}
+
+/// Publish an event if anyone may be listening
+pub(crate) fn emit(events: &Option<EventSender>, event: RegistryEvent) {
+    if let Some(events) = events {
+        // Sending only fails when nobody is subscribed
+        let _ = events.send(event);
+    }
+}
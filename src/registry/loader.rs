@@ -1,188 +1,327 @@
-//! Component loader for dynamic loading of agents and tools
+//! Component loader for built-in components, tool plugins and agent definition files
 
+use crate::agents::declarative::ConfigAgent;
+use crate::agents::registry::AgentRegistry;
 use crate::core::{Agent, Tool};
 use crate::registry::core::CentralRegistry;
-use std::path::PathBuf;
+use crate::tools::plugin::{manifest_path, PluginTool};
+use crate::tools::registry::ToolRegistry;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-/// Component loader for discovering and loading agents and tools
+/// Directory scanned for tool plugins
+pub const PLUGINS_DIR_ENV: &str = "CF_AI_PLUGINS_DIR";
+
+/// Directory scanned for agent definition files
+pub const AGENTS_DIR_ENV: &str = "CF_AI_AGENTS_DIR";
+
+/// Agents loaded from definition files, with the file each came from
+pub type AgentFiles = Vec<(PathBuf, ConfigAgent)>;
+
+/// A plugin directory that failed to load, and why
+pub type PluginError = (PathBuf, String);
+
+/// Component loader for built-in agents and tools plus plugins found on disk
 pub struct ComponentLoader {
-    agents_path: PathBuf,
-    tools_path: PathBuf,
+    plugins_path: PathBuf,
+    /// Directory of agent definitions; `None` loads built-in agents only
+    agents_path: Option<PathBuf>,
+    /// IDs of the plugin tools registered by the last scan
+    loaded_plugins: HashSet<String>,
+    /// IDs of the file-defined agents registered by the last scan
+    loaded_agents: HashSet<String>,
+}
+
+/// Outcome of a plugin or agent definition scan
+#[derive(Debug, Clone, Default)]
+pub struct PluginScan {
+    /// IDs registered by this scan
+    pub loaded: Vec<String>,
+    /// IDs from the previous scan whose file is gone
+    pub removed: Vec<String>,
+    /// Plugins or definitions that could not be loaded, with the reason
+    pub errors: Vec<(PathBuf, String)>,
 }
 
 impl ComponentLoader {
-    /// Create a new component loader
+    /// Loader for `CF_AI_PLUGINS_DIR` and `CF_AI_AGENTS_DIR`, or the user's config directory
     pub fn new() -> Self {
-        let base_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src");
+        Self::with_plugins_dir(dir_from_env(PLUGINS_DIR_ENV, "plugins"))
+            .with_agents_dir(dir_from_env(AGENTS_DIR_ENV, "agents"))
+    }
 
+    pub fn with_plugins_dir(plugins_path: impl Into<PathBuf>) -> Self {
         Self {
-            agents_path: base_path.join("agents"),
-            tools_path: base_path.join("tools"),
+            plugins_path: plugins_path.into(),
+            agents_path: None,
+            loaded_plugins: HashSet::new(),
+            loaded_agents: HashSet::new(),
         }
     }
 
-    /// Load all built-in agents
-    pub async fn load_builtin_agents(&self) -> crate::core::Result<Vec<Box<dyn Agent>>> {
-        let mut agents: Vec<Box<dyn Agent>> = Vec::new();
-
-        // Load desktop automation agent
-        if self.agents_path.join("desktop_automation").exists() {
-            agents.push(Box::new(crate::agents::DesktopAutomationAgent::new()));
-        }
+    /// Also load agent definitions from `agents_path`
+    pub fn with_agents_dir(mut self, agents_path: impl Into<PathBuf>) -> Self {
+        self.agents_path = Some(agents_path.into());
+        self
+    }
 
-        // Load web research agent
-        if self.agents_path.join("web_research").exists() {
-            agents.push(Box::new(crate::agents::WebResearchAgent::new()));
-        }
+    pub fn plugins_path(&self) -> &Path {
+        &self.plugins_path
+    }
 
-        Ok(agents)
+    pub fn agents_path(&self) -> Option<&Path> {
+        self.agents_path.as_deref()
     }
 
-    /// Load all built-in tools
-    pub async fn load_builtin_tools(&self) -> crate::core::Result<Vec<Box<dyn Tool>>> {
-        let mut tools: Vec<Box<dyn Tool>> = Vec::new();
-
-        // Load desktop automation tools
-        if self.tools_path.join("desktop_automation").exists() {
-            // Mouse tools
-            tools.push(Box::new(
-                crate::tools::desktop_automation::mouse::MoveCursor::new(),
-            ));
-            tools.push(Box::new(
-                crate::tools::desktop_automation::mouse::Click::new(),
-            ));
-            tools.push(Box::new(
-                crate::tools::desktop_automation::mouse::Scroll::new(),
-            ));
-
-            // Keyboard tools
-            tools.push(Box::new(
-                crate::tools::desktop_automation::keyboard::TypeText::new(),
-            ));
-            tools.push(Box::new(
-                crate::tools::desktop_automation::keyboard::Hotkey::new(),
-            ));
-
-            // Screen tools
-            tools.push(Box::new(
-                crate::tools::desktop_automation::screen::Screenshot::new(),
-            ));
-            tools.push(Box::new(
-                crate::tools::desktop_automation::screen::GetPosition::new(),
-            ));
-        }
+    /// All agents compiled into the binary
+    pub fn load_builtin_agents(&self) -> Vec<Box<dyn Agent>> {
+        vec![
+            Box::new(ConfigAgent::desktop_automation()),
+            Box::new(ConfigAgent::web_research()),
+            Box::new(ConfigAgent::conversational()),
+        ]
+    }
 
-        Ok(tools)
+    /// All tools compiled into the binary
+    pub fn load_builtin_tools(&self) -> Vec<Box<dyn Tool>> {
+        use crate::tools::desktop_automation::{keyboard, mouse, screen};
+
+        vec![
+            // Desktop automation tools
+            Box::new(mouse::MoveCursor::new()),
+            Box::new(mouse::Click::new()),
+            Box::new(mouse::Scroll::new()),
+            Box::new(keyboard::TypeText::new()),
+            Box::new(keyboard::Hotkey::new()),
+            Box::new(screen::Screenshot::new()),
+            Box::new(screen::GetPosition::new()),
+            // Web tools
+            Box::new(crate::tools::web::WebSearch::new()),
+            Box::new(crate::tools::web::FetchUrl::new()),
+            // Delegation tools
+            Box::new(crate::tools::delegation::DelegateToAgent::new()),
+        ]
     }
 
-    /// Load all built-in components into the registry
+    /// Register the built-in components and every plugin into the registry
     pub async fn load_all_into_registry(
-        &self,
-        registry: &mut CentralRegistry,
-    ) -> crate::core::Result<()> {
-        // Load and register agents
-        let agents = self.load_builtin_agents().await?;
-        for agent in agents {
+        &mut self,
+        registry: &CentralRegistry,
+    ) -> crate::core::Result<PluginScan> {
+        for agent in self.load_builtin_agents() {
             registry.agents.register(agent).await?;
         }
-
-        // Load and register tools
-        let tools = self.load_builtin_tools().await?;
-        for tool in tools {
+        for tool in self.load_builtin_tools() {
             registry.tools.register(tool).await?;
         }
-
-        Ok(())
+        let mut scan = self.rescan_plugins(registry.tools.as_ref()).await?;
+        let agents = self.rescan_agents(registry.agents.as_ref()).await?;
+        scan.loaded.extend(agents.loaded);
+        scan.removed.extend(agents.removed);
+        scan.errors.extend(agents.errors);
+        registry.refresh_availability().await?;
+        Ok(scan)
     }
 
-    /// Discover available agent directories
-    pub fn discover_agent_directories(&self) -> crate::core::Result<Vec<PathBuf>> {
-        self.discover_component_directories(&self.agents_path)
+    /// Plugin directories, i.e. subdirectories holding a manifest
+    pub fn discover_plugin_directories(&self) -> crate::core::Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(&self.plugins_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut directories = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() && manifest_path(&path).is_some() {
+                directories.push(path);
+            }
+        }
+        directories.sort();
+        Ok(directories)
     }
 
-    /// Discover available tool directories
-    pub fn discover_tool_directories(&self) -> crate::core::Result<Vec<PathBuf>> {
-        self.discover_component_directories(&self.tools_path)
+    /// Load every plugin, reporting broken ones instead of failing
+    pub fn load_plugins(&self) -> crate::core::Result<(Vec<PluginTool>, Vec<PluginError>)> {
+        let mut plugins: Vec<PluginTool> = Vec::new();
+        let mut errors = Vec::new();
+        for dir in self.discover_plugin_directories()? {
+            match PluginTool::load(&dir) {
+                Ok(plugin) if plugins.iter().any(|p| p.id() == plugin.id()) => {
+                    errors.push((dir, format!("duplicate plugin ID '{}'", plugin.id())));
+                }
+                Ok(plugin) => plugins.push(plugin),
+                Err(e) => errors.push((dir, e.to_string())),
+            }
+        }
+        Ok((plugins, errors))
     }
 
-    /// Helper method to discover component directories
-    fn discover_component_directories(
-        &self,
-        base_path: &PathBuf,
-    ) -> crate::core::Result<Vec<PathBuf>> {
-        let mut directories = Vec::new();
+    /// Replace the plugin tools registered by the previous scan with what is on disk now
+    pub async fn rescan_plugins(
+        &mut self,
+        tools: &dyn ToolRegistry,
+    ) -> crate::core::Result<PluginScan> {
+        let (plugins, errors) = self.load_plugins()?;
+        let mut scan = PluginScan {
+            errors,
+            ..Default::default()
+        };
 
-        if !base_path.exists() {
-            return Ok(directories);
+        for id in self.loaded_plugins.drain() {
+            tools.unregister(&id).await?;
+            if !plugins.iter().any(|p| p.id() == id) {
+                scan.removed.push(id);
+            }
         }
 
-        for entry in std::fs::read_dir(base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                // Check if it has a mod.rs file (indicating it's a valid component)
-                if path.join("mod.rs").exists() {
-                    directories.push(path);
+        // Plugins may depend on each other, so retry until a pass registers nothing new
+        let mut pending = plugins;
+        loop {
+            let waiting = pending.len();
+            let mut blocked = Vec::new();
+            for plugin in pending {
+                let id = plugin.id().to_string();
+                // Built-in tools win over plugins with the same ID
+                if tools.get(&id).await?.is_some() {
+                    scan.errors.push((
+                        plugin.directory().to_path_buf(),
+                        format!("tool '{}' is already registered", id),
+                    ));
+                    continue;
+                }
+                if tools.validate_dependencies(&plugin).await.is_err() {
+                    blocked.push(plugin);
+                    continue;
                 }
+                tools.register(Box::new(plugin)).await?;
+                self.loaded_plugins.insert(id.clone());
+                scan.loaded.push(id);
+            }
+            pending = blocked;
+            if pending.is_empty() || pending.len() == waiting {
+                break;
+            }
+        }
+        for plugin in pending {
+            if let Err(e) = tools.validate_dependencies(&plugin).await {
+                scan.errors
+                    .push((plugin.directory().to_path_buf(), e.to_string()));
             }
         }
 
-        Ok(directories)
+        scan.removed.sort();
+        Ok(scan)
     }
 
-    /// Validate a component directory structure
-    pub fn validate_component_directory(&self, dir_path: &PathBuf) -> crate::core::Result<()> {
-        // Check for required mod.rs file
-        if !dir_path.join("mod.rs").exists() {
-            return Err(crate::core::AppError::Registry(format!(
-                "Component directory '{}' missing mod.rs file",
-                dir_path.display()
-            )));
-        }
+    /// Agent definition files, i.e. `.toml` and `.json` files in the agents directory
+    pub fn discover_agent_files(&self) -> crate::core::Result<Vec<PathBuf>> {
+        let Some(agents_path) = &self.agents_path else {
+            return Ok(Vec::new());
+        };
+        let entries = match std::fs::read_dir(agents_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
 
-        // Additional validation could be added here
-        // - Check for required functions/structs
-        // - Validate configuration files
-        // - Check dependencies
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|e| e.to_str());
+            if path.is_file() && matches!(extension, Some("toml" | "json")) {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
 
-        Ok(())
+    /// Load every agent definition with its file, reporting broken ones instead of failing
+    pub fn load_agents(&self) -> crate::core::Result<(AgentFiles, Vec<(PathBuf, String)>)> {
+        let mut agents: AgentFiles = Vec::new();
+        let mut errors = Vec::new();
+        for path in self.discover_agent_files()? {
+            match ConfigAgent::load(&path) {
+                Ok(agent) if agents.iter().any(|(_, a)| a.id == agent.id) => {
+                    errors.push((path, format!("duplicate agent ID '{}'", agent.id)));
+                }
+                Ok(agent) => agents.push((path, agent)),
+                Err(e) => errors.push((path, e.to_string())),
+            }
+        }
+        Ok((agents, errors))
     }
 
-    /// Get component information from directory
-    pub fn get_component_info(&self, dir_path: &PathBuf) -> crate::core::Result<ComponentInfo> {
-        let mod_file = dir_path.join("mod.rs");
-
-        // Read mod.rs to extract component information
-        // This is a simplified implementation - in a real system,
-        // you might parse the Rust code or use metadata files
-        let component_name = dir_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .ok_or_else(|| {
-                crate::core::AppError::Registry(format!(
-                    "Invalid component directory name: {}",
-                    dir_path.display()
-                ))
-            })?;
-
-        // Determine component type based on parent directory
-        let component_type = if dir_path.starts_with(&self.agents_path) {
-            ComponentType::Agent
-        } else if dir_path.starts_with(&self.tools_path) {
-            ComponentType::Tool
-        } else {
-            ComponentType::Unknown
+    /// Replace the agents registered by the previous scan with the definitions on disk now
+    pub async fn rescan_agents(
+        &mut self,
+        registry: &dyn AgentRegistry,
+    ) -> crate::core::Result<PluginScan> {
+        let (agents, errors) = self.load_agents()?;
+        let mut scan = PluginScan {
+            errors,
+            ..Default::default()
         };
 
+        for id in self.loaded_agents.drain() {
+            registry.unregister(&id).await?;
+            if !agents.iter().any(|(_, a)| a.id == id) {
+                scan.removed.push(id);
+            }
+        }
+
+        for (path, agent) in agents {
+            let id = agent.id.clone();
+            // Built-in agents win over definitions with the same ID
+            if registry.get(&id).await?.is_some() {
+                scan.errors
+                    .push((path, format!("agent '{}' is already registered", id)));
+                continue;
+            }
+            registry.register(Box::new(agent)).await?;
+            self.loaded_agents.insert(id.clone());
+            scan.loaded.push(id);
+        }
+
+        scan.removed.sort();
+        Ok(scan)
+    }
+
+    /// Check that a plugin directory holds a valid manifest
+    pub fn validate_component_directory(&self, dir_path: &Path) -> crate::core::Result<()> {
+        PluginTool::load(dir_path).map(|_| ())
+    }
+
+    /// Get component information from a plugin directory
+    pub fn get_component_info(&self, dir_path: &Path) -> crate::core::Result<ComponentInfo> {
+        let plugin = PluginTool::load(dir_path)?;
         Ok(ComponentInfo {
-            name: component_name.to_string(),
-            path: dir_path.clone(),
-            component_type,
+            name: plugin.id().to_string(),
+            path: dir_path.to_path_buf(),
+            component_type: ComponentType::Tool,
         })
     }
 }
 
+/// `$env`, or `name` under the user's config directory
+fn dir_from_env(env: &str, name: &str) -> PathBuf {
+    match std::env::var(env) {
+        Ok(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => dirs::config_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("cf_ai_local_tools")
+            .join(name),
+    }
+}
+
+impl Default for ComponentLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Information about a discovered component
 #[derive(Debug, Clone)]
 pub struct ComponentInfo {
@@ -203,82 +342,152 @@ pub enum ComponentType {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_component_loader_creation() {
-        let loader = ComponentLoader::new();
-
-        assert!(loader.agents_path.ends_with("agents/builtin"));
-        assert!(loader.tools_path.ends_with("tools/builtin"));
+    fn write_plugin(root: &Path, dir: &str, id: &str) {
+        let dir = root.join(dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("plugin.toml"),
+            format!(
+                "id = \"{}\"\nname = \"{}\"\ndescription = \"Test plugin\"\ncommand = \"./run.sh\"\n",
+                id, id
+            ),
+        )
+        .unwrap();
     }
 
-    #[tokio::test]
-    async fn test_load_builtin_agents() {
-        let loader = ComponentLoader::new();
-
-        let agents = loader.load_builtin_agents().await.unwrap();
+    #[test]
+    fn test_builtin_components() {
+        let loader = ComponentLoader::with_plugins_dir("unused");
 
-        // Should load at least the desktop automation agent
-        assert!(!agents.is_empty());
+        let agents = loader.load_builtin_agents();
         assert!(agents.iter().any(|a| a.id() == "desktop-automation-agent"));
-    }
-
-    #[tokio::test]
-    async fn test_load_builtin_tools() {
-        let loader = ComponentLoader::new();
-
-        let tools = loader.load_builtin_tools().await.unwrap();
+        assert!(agents.iter().any(|a| a.id() == "conversational-agent"));
 
-        // Should load at least the mouse tools
-        assert!(!tools.is_empty());
+        let tools = loader.load_builtin_tools();
         assert!(tools.iter().any(|t| t.id() == "mouse_move"));
         assert!(tools.iter().any(|t| t.id() == "mouse_click"));
+        assert!(tools.iter().any(|t| t.id() == "web_search"));
     }
 
     #[test]
-    fn test_discover_agent_directories() {
-        let loader = ComponentLoader::new();
-
-        let directories = loader.discover_agent_directories().unwrap();
-
-        // Should find at least the desktop_automation directory
-        assert!(!directories.is_empty());
-        assert!(directories
-            .iter()
-            .any(|d| d.ends_with("desktop_automation")));
+    fn test_discover_and_validate_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write_plugin(root, "alpha", "alpha_tool");
+        std::fs::create_dir_all(root.join("not-a-plugin")).unwrap();
+        std::fs::create_dir_all(root.join("broken")).unwrap();
+        std::fs::write(root.join("broken").join("plugin.toml"), "id = ").unwrap();
+
+        let loader = ComponentLoader::with_plugins_dir(root);
+        let directories = loader.discover_plugin_directories().unwrap();
+        assert_eq!(directories.len(), 2);
+
+        assert!(loader
+            .validate_component_directory(&root.join("alpha"))
+            .is_ok());
+        assert!(loader
+            .validate_component_directory(&root.join("broken"))
+            .is_err());
+
+        let info = loader.get_component_info(&root.join("alpha")).unwrap();
+        assert_eq!(info.name, "alpha_tool");
+        assert_eq!(info.component_type, ComponentType::Tool);
     }
 
     #[test]
-    fn test_discover_tool_directories() {
-        let loader = ComponentLoader::new();
-
-        let directories = loader.discover_tool_directories().unwrap();
-
-        // Should find at least the desktop_automation directory
-        assert!(!directories.is_empty());
-        assert!(directories
-            .iter()
-            .any(|d| d.ends_with("desktop_automation")));
+    fn test_missing_plugins_dir_is_empty() {
+        let temp = tempfile::tempdir().unwrap();
+        let loader = ComponentLoader::with_plugins_dir(temp.path().join("nope"));
+        assert!(loader.discover_plugin_directories().unwrap().is_empty());
     }
 
-    #[test]
-    fn test_validate_component_directory() {
-        let loader = ComponentLoader::new();
-
-        let desktop_dir = loader.agents_path.join("desktop_automation");
-
-        // Should validate successfully
-        assert!(loader.validate_component_directory(&desktop_dir).is_ok());
+    #[tokio::test]
+    async fn test_rescan_registers_and_removes_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write_plugin(root, "alpha", "alpha_tool");
+        write_plugin(root, "beta", "beta_tool");
+        write_plugin(root, "shadow", "web_search");
+
+        let registry = CentralRegistry::new();
+        let mut loader = ComponentLoader::with_plugins_dir(root);
+        let scan = loader.load_all_into_registry(&registry).await.unwrap();
+        assert_eq!(scan.loaded.len(), 2);
+        assert_eq!(
+            scan.errors.len(),
+            1,
+            "built-in web_search must not be shadowed"
+        );
+        assert!(registry.tools.get("alpha_tool").await.unwrap().is_some());
+
+        std::fs::remove_dir_all(root.join("beta")).unwrap();
+        let scan = loader
+            .rescan_plugins(registry.tools.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(scan.loaded, vec!["alpha_tool"]);
+        assert_eq!(scan.removed, vec!["beta_tool"]);
+        assert!(registry.tools.get("beta_tool").await.unwrap().is_none());
+        assert!(registry.tools.get("web_search").await.unwrap().is_some());
     }
 
-    #[test]
-    fn test_get_component_info() {
-        let loader = ComponentLoader::new();
-
-        let desktop_dir = loader.agents_path.join("desktop_automation");
+    #[tokio::test]
+    async fn test_plugins_register_after_their_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write_plugin(root, "zeta", "zeta_tool");
+        for (dir, id, requires) in [
+            ("alpha", "alpha_tool", "zeta_tool"),
+            ("orphan", "orphan_tool", "gone"),
+        ] {
+            let manifest = root.join(dir).join("plugin.toml");
+            write_plugin(root, dir, id);
+            let text = std::fs::read_to_string(&manifest).unwrap();
+            std::fs::write(&manifest, format!("{}tools = [\"{}\"]\n", text, requires)).unwrap();
+        }
 
-        let info = loader.get_component_info(&desktop_dir).unwrap();
+        let registry = CentralRegistry::new();
+        let mut loader = ComponentLoader::with_plugins_dir(root);
+        let mut scan = loader
+            .rescan_plugins(registry.tools.as_ref())
+            .await
+            .unwrap();
+        scan.loaded.sort();
+        assert_eq!(scan.loaded, vec!["alpha_tool", "zeta_tool"]);
+        assert_eq!(scan.errors.len(), 1);
+        assert!(scan.errors[0].1.contains("'gone'"));
+    }
 
-        assert_eq!(info.name, "desktop_automation");
-        assert_eq!(info.component_type, ComponentType::Agent);
+    #[tokio::test]
+    async fn test_rescan_registers_agent_definitions() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let definition = |id: &str| {
+            format!(
+                "id = \"{}\"\nname = \"{}\"\ndescription = \"Test agent\"\nprompt = \"Be brief.\"\n",
+                id, id
+            )
+        };
+        std::fs::write(root.join("triage.toml"), definition("triage-agent")).unwrap();
+        std::fs::write(root.join("shadow.toml"), definition("web-research-agent")).unwrap();
+        std::fs::write(root.join("broken.json"), "{").unwrap();
+        std::fs::write(root.join("notes.txt"), "ignored").unwrap();
+
+        let registry = CentralRegistry::new();
+        let mut loader =
+            ComponentLoader::with_plugins_dir(root.join("plugins")).with_agents_dir(root);
+        let scan = loader.load_all_into_registry(&registry).await.unwrap();
+        assert_eq!(scan.loaded, vec!["triage-agent"]);
+        assert_eq!(scan.errors.len(), 2, "broken file and shadowed built-in");
+        let agent = registry.agents.get("triage-agent").await.unwrap().unwrap();
+        assert_eq!(agent.system_prompt(), "Be brief.");
+
+        std::fs::remove_file(root.join("triage.toml")).unwrap();
+        let scan = loader
+            .rescan_agents(registry.agents.as_ref())
+            .await
+            .unwrap();
+        assert_eq!(scan.removed, vec!["triage-agent"]);
+        assert!(registry.agents.get("triage-agent").await.unwrap().is_none());
     }
 }
@@ -1,9 +1,16 @@
 //! Component registry system for managing agents and tools
 
 pub mod core;
+pub mod dependencies;
+pub mod events;
 pub mod loader;
 pub mod presets;
 
 // Re-export main types
 pub use core::{CentralRegistry, ComponentMetadata, Registry};
-pub use loader::{ComponentInfo, ComponentLoader, ComponentType};
+pub use dependencies::{Component, ComponentKind, Dependency, DependencyGraph};
+pub use events::RegistryEvent;
+pub use loader::{
+    AgentFiles, ComponentInfo, ComponentLoader, ComponentType, PluginScan, AGENTS_DIR_ENV,
+    PLUGINS_DIR_ENV,
+};
@@ -1,3 +1,4 @@
+use crate::core::SamplingParams;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +37,8 @@ pub struct PresetAgent {
     pub separate_reasoning_model: bool,
     #[serde(rename = "reasoningModelId", skip_serializing_if = "Option::is_none")]
     pub reasoning_model_id: Option<String>,
+    #[serde(default)]
+    pub sampling: SamplingParams,
     pub metadata: PresetMetadata,
     #[serde(rename = "isDefault", skip_serializing_if = "Option::is_none")]
     pub is_default: Option<bool>,
@@ -72,6 +75,7 @@ pub fn get_default_presets() -> Vec<PresetAgent> {
             max_iterations: 10,
             separate_reasoning_model: false,
             reasoning_model_id: None,
+            sampling: SamplingParams::default(),
             metadata: metadata.clone(),
             is_default: Some(true),
             is_pinned: None,
@@ -93,11 +97,15 @@ pub fn get_default_presets() -> Vec<PresetAgent> {
                     enabled: true,
                 },
                 ToolReference {
-                    tool_id: "keyboard_input".to_string(),
+                    tool_id: "keyboard_type".to_string(),
                     enabled: true,
                 },
                 ToolReference {
-                    tool_id: "get_mouse_position".to_string(),
+                    tool_id: "screen_get_position".to_string(),
+                    enabled: true,
+                },
+                ToolReference {
+                    tool_id: "screen_screenshot".to_string(),
                     enabled: true,
                 },
             ],
@@ -105,6 +113,7 @@ pub fn get_default_presets() -> Vec<PresetAgent> {
             max_iterations: 3,
             separate_reasoning_model: false,
             reasoning_model_id: None,
+            sampling: SamplingParams::deterministic(),
             metadata: metadata.clone(),
             is_default: Some(true),
             is_pinned: None,
@@ -130,6 +139,7 @@ pub fn get_default_presets() -> Vec<PresetAgent> {
             max_iterations: 8,
             separate_reasoning_model: false,
             reasoning_model_id: None,
+            sampling: SamplingParams::default(),
             metadata,
             is_default: Some(true),
             is_pinned: None,
@@ -10,6 +10,7 @@ pub struct DelegateToAgent {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +30,7 @@ impl DelegateToAgent {
             name: "Delegate to Agent".to_string(),
             description: "Delegate a task to another specialized agent".to_string(),
             category: "delegation".to_string(),
+            capabilities: vec!["delegation".to_string()],
             parameters: vec![
                 ToolParameter {
                     name: "target_agent".to_string(),
@@ -111,6 +113,10 @@ impl Tool for DelegateToAgent {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -8,6 +8,7 @@ pub struct Hotkey {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +24,7 @@ impl Hotkey {
             name: "Hotkey".to_string(),
             description: "Executes keyboard shortcuts and hotkeys".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["keyboard_control".to_string()],
             parameters: vec![
                 ToolParameter {
                     name: "keys".to_string(),
@@ -67,6 +69,10 @@ impl Tool for Hotkey {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -8,6 +8,23 @@ pub struct TypeText {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub config: TypeTextConfig,
+}
+
+/// Typing defaults for calls that don't set them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TypeTextConfig {
+    /// Delay between keystrokes in milliseconds
+    pub delay_ms: u64,
+}
+
+impl Default for TypeTextConfig {
+    fn default() -> Self {
+        Self { delay_ms: 50 }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +41,8 @@ impl TypeText {
             name: "Type Text".to_string(),
             description: "Types text on the keyboard with configurable speed".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["keyboard_control".to_string()],
+            config: TypeTextConfig::default(),
             parameters: vec![
                 ToolParameter {
                     name: "text".to_string(),
@@ -36,7 +55,9 @@ impl TypeText {
                 ToolParameter {
                     name: "delay_ms".to_string(),
                     param_type: "number".to_string(),
-                    description: "Delay between keystrokes in milliseconds".to_string(),
+                    description:
+                        "Delay between keystrokes in milliseconds (default: configured speed)"
+                            .to_string(),
                     required: false,
                     default: Some(serde_json::json!(50)),
                     enum_values: None,
@@ -76,6 +97,35 @@ impl Tool for TypeText {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "delay_ms": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 1000,
+                    "description": "Delay between keystrokes in milliseconds"
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn config(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.config).ok()
+    }
+
+    fn configure(&mut self, config: &serde_json::Value) -> crate::core::Result<()> {
+        self.config = serde_json::from_value(config.clone())
+            .map_err(|e| crate::core::AppError::Tool(format!("Invalid configuration: {}", e)))?;
+        Ok(())
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -90,7 +140,7 @@ impl Tool for TypeText {
             ));
         }
 
-        let delay_ms = args.delay_ms.unwrap_or(50);
+        let delay_ms = args.delay_ms.unwrap_or(self.config.delay_ms);
         let auto_enter = args.auto_enter.unwrap_or(false);
 
         // Send progress update
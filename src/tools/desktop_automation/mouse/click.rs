@@ -8,6 +8,7 @@ pub struct Click {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +25,7 @@ impl Click {
             name: "Mouse Click".to_string(),
             description: "Clicks a mouse button at the current cursor position".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["mouse_control".to_string()],
             parameters: vec![
                 ToolParameter {
                     name: "button".to_string(),
@@ -81,6 +83,10 @@ impl Tool for Click {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -16,6 +16,7 @@ pub struct MoveCursor {
     pub description: String,
     pub category: String,
     pub parameters: Vec<crate::core::ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 impl MoveCursor {
@@ -25,6 +26,7 @@ impl MoveCursor {
             name: "Move Cursor".to_string(),
             description: "Moves the mouse cursor to the specified coordinates".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["mouse_control".to_string()],
             parameters: vec![
                 crate::core::ToolParameter {
                     name: "x".to_string(),
@@ -154,6 +156,10 @@ impl crate::core::Tool for MoveCursor {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -8,6 +8,7 @@ pub struct Scroll {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +25,7 @@ impl Scroll {
             name: "Mouse Scroll".to_string(),
             description: "Scrolls the mouse wheel up or down".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["mouse_control".to_string()],
             parameters: vec![
                 ToolParameter {
                     name: "direction".to_string(),
@@ -76,6 +78,10 @@ impl Tool for Scroll {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
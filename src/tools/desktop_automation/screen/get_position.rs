@@ -8,6 +8,7 @@ pub struct GetPosition {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +21,7 @@ impl GetPosition {
             name: "Get Mouse Position".to_string(),
             description: "Gets the current mouse cursor position on screen".to_string(),
             category: "desktop_automation".to_string(),
+            capabilities: vec!["mouse_control".to_string()],
             parameters: vec![], // No parameters needed
         }
     }
@@ -47,6 +49,10 @@ impl Tool for GetPosition {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -2,10 +2,16 @@
 
 pub mod delegation;
 pub mod desktop_automation;
+pub mod openapi;
+pub mod plugin;
 pub mod registry;
+pub mod settings;
 pub mod web;
 
 // Re-export all built-in tools
 pub use delegation::*;
 pub use desktop_automation::*;
+pub use openapi::{OpenApiConfig, OpenApiTool};
+pub use plugin::{PluginManifest, PluginTool};
+pub use settings::{ToolSetting, ToolSettings};
 pub use web::*;
@@ -0,0 +1,260 @@
+//! HTTP tools generated from OpenAPI 3 documents
+//!
+//! Each API listed in `openapi.toml` contributes one tool per operation, in a
+//! category named after the API. Arguments are sent as path, query, header or
+//! JSON body values as the document describes.
+
+pub mod spec;
+pub mod tool;
+
+pub use spec::{OpenApiSpec, Operation, OperationParameter, ParameterLocation};
+pub use tool::{ApiClient, OpenApiTool};
+
+use crate::core::{AppError, Result, Tool};
+use crate::tools::registry::ToolRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// File listing the OpenAPI documents to import
+pub const OPENAPI_CONFIG_ENV: &str = "CF_AI_OPENAPI_CONFIG";
+
+/// Seconds a request may take when the API entry doesn't say
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// One API to import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenApiSource {
+    /// Short name, used for the tool category and to prefix tool IDs
+    pub name: String,
+    /// OpenAPI document, relative to the config file
+    pub spec: PathBuf,
+    /// Overrides the document's first server URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Sent with every request; `${VAR}` is replaced by the environment variable
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Only import these operation IDs; all of them when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl OpenApiSource {
+    /// Tools for the operations of this API, with `spec` resolved against `base_dir`
+    pub fn load_tools(&self, base_dir: &Path) -> Result<Vec<OpenApiTool>> {
+        let spec = OpenApiSpec::load(&base_dir.join(&self.spec))?;
+        let base_url = self
+            .base_url
+            .clone()
+            .or_else(|| spec.server_url())
+            .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+            .ok_or_else(|| {
+                AppError::Configuration(format!(
+                    "API '{}' needs an absolute base_url; its document has none",
+                    self.name
+                ))
+            })?;
+        let mut headers = Vec::new();
+        for (name, value) in &self.headers {
+            headers.push((name.clone(), expand_env(value)?));
+        }
+        let timeout = Duration::from_secs(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        let api = Arc::new(ApiClient::new(&base_url, headers, timeout));
+
+        let operations = spec.operations()?;
+        if let Some(missing) = self
+            .operations
+            .iter()
+            .find(|id| !operations.iter().any(|o| &o.operation_id == *id))
+        {
+            return Err(AppError::Configuration(format!(
+                "API '{}' has no operation '{}'",
+                self.name, missing
+            )));
+        }
+        Ok(operations
+            .into_iter()
+            .filter(|o| self.operations.is_empty() || self.operations.contains(&o.operation_id))
+            .map(|operation| OpenApiTool::new(&self.name, &self.name, operation, api.clone()))
+            .collect())
+    }
+}
+
+/// Replace `${VAR}` with the value of environment variable `VAR`
+fn expand_env(value: &str) -> Result<String> {
+    let pattern = regex::Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("valid pattern");
+    let mut missing = None;
+    let expanded = pattern.replace_all(value, |captures: &regex::Captures| {
+        std::env::var(&captures[1]).unwrap_or_else(|_| {
+            missing = Some(captures[1].to_string());
+            String::new()
+        })
+    });
+    match missing {
+        Some(name) => Err(AppError::Configuration(format!(
+            "Environment variable {} is not set",
+            name
+        ))),
+        None => Ok(expanded.into_owned()),
+    }
+}
+
+/// Contents of `openapi.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenApiConfig {
+    #[serde(default)]
+    pub apis: Vec<OpenApiSource>,
+    /// Directory that `spec` paths are relative to
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+impl OpenApiConfig {
+    /// Path from `CF_AI_OPENAPI_CONFIG`, or `openapi.toml` in the user's config directory
+    pub fn default_path() -> PathBuf {
+        match std::env::var(OPENAPI_CONFIG_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => dirs::config_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cf_ai_local_tools")
+                .join("openapi.toml"),
+        }
+    }
+
+    /// Read the default config; a missing file means no APIs
+    pub fn from_env() -> Result<Self> {
+        Self::load(&Self::default_path())
+    }
+
+    /// Read a config file; a missing file means no APIs
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut config: Self = toml::from_str(&text).map_err(|e| {
+            AppError::Configuration(format!("Invalid OpenAPI config {}: {}", path.display(), e))
+        })?;
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        for (i, api) in config.apis.iter().enumerate() {
+            let valid_name = !api.name.is_empty()
+                && api
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                return Err(AppError::Configuration(format!(
+                    "Invalid API name '{}': use letters, digits, '_' and '-'",
+                    api.name
+                )));
+            }
+            if config.apis[..i].iter().any(|a| a.name == api.name) {
+                return Err(AppError::Configuration(format!(
+                    "API '{}' is configured twice",
+                    api.name
+                )));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Outcome of importing OpenAPI operations
+#[derive(Debug, Clone, Default)]
+pub struct OpenApiImport {
+    /// Tool IDs registered
+    pub loaded: Vec<String>,
+    /// APIs or operations that could not be imported, with the reason
+    pub errors: Vec<(String, String)>,
+}
+
+/// Register the operations of every configured API as tools
+pub async fn import_tools(
+    config: &OpenApiConfig,
+    tools: &dyn ToolRegistry,
+) -> Result<OpenApiImport> {
+    let mut import = OpenApiImport::default();
+    for api in &config.apis {
+        let api_tools = match api.load_tools(&config.base_dir) {
+            Ok(api_tools) => api_tools,
+            Err(e) => {
+                import.errors.push((api.name.clone(), e.to_string()));
+                continue;
+            }
+        };
+        for tool in api_tools {
+            let id = tool.id().to_string();
+            if tools.get(&id).await?.is_some() {
+                import.errors.push((
+                    api.name.clone(),
+                    format!("tool '{}' is already registered", id),
+                ));
+                continue;
+            }
+            tools.register(Box::new(tool)).await?;
+            import.loaded.push(id);
+        }
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::registry::DefaultToolRegistry;
+
+    #[tokio::test]
+    async fn test_import_from_config_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(
+            dir.join("status.json"),
+            r#"{"openapi": "3.0.0", "info": {"title": "Status", "version": "1"},
+                "servers": [{"url": "https://status.example.com"}],
+                "paths": {"/health": {"get": {"operationId": "health"}},
+                          "/metrics": {"get": {"operationId": "metrics"}}}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("openapi.toml"),
+            r#"
+[[apis]]
+name = "status"
+spec = "status.json"
+operations = ["health"]
+headers = { Authorization = "Bearer ${CF_AI_OPENAPI_TEST_TOKEN}" }
+
+[[apis]]
+name = "missing"
+spec = "nope.yaml"
+"#,
+        )
+        .unwrap();
+        std::env::set_var("CF_AI_OPENAPI_TEST_TOKEN", "t0ken");
+
+        let config = OpenApiConfig::load(&dir.join("openapi.toml")).unwrap();
+        let registry = DefaultToolRegistry::new();
+        let import = import_tools(&config, &registry).await.unwrap();
+        assert_eq!(import.loaded, vec!["status__health"]);
+        assert_eq!(import.errors.len(), 1);
+        assert_eq!(import.errors[0].0, "missing");
+        assert_eq!(registry.find_by_category("status").await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("CF_AI_OPENAPI_EXPAND", "abc");
+        assert_eq!(
+            expand_env("Bearer ${CF_AI_OPENAPI_EXPAND}").unwrap(),
+            "Bearer abc"
+        );
+        assert!(expand_env("${CF_AI_OPENAPI_UNSET_VARIABLE}").is_err());
+        assert_eq!(expand_env("plain").unwrap(), "plain");
+    }
+}
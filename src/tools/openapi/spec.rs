@@ -0,0 +1,451 @@
+//! Reading operations out of an OpenAPI 3 document
+
+use crate::core::{AppError, Result, ToolParameter};
+use serde_json::Value;
+use std::path::Path;
+
+/// HTTP methods an OpenAPI path item may define
+const METHODS: [&str; 7] = ["get", "put", "post", "delete", "patch", "head", "options"];
+
+/// How deep `$ref` chains are followed before giving up
+const MAX_REF_DEPTH: usize = 16;
+
+/// Where an argument goes in the HTTP request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterLocation {
+    Path,
+    Query,
+    Header,
+    /// One property of a JSON object body
+    BodyField,
+    /// The whole request body
+    Body,
+}
+
+/// A tool parameter and where its value is sent
+#[derive(Debug, Clone)]
+pub struct OperationParameter {
+    pub parameter: ToolParameter,
+    pub location: ParameterLocation,
+    /// Name in the request, which differs from the tool parameter when names clash
+    pub wire_name: String,
+}
+
+/// One method on one path
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub operation_id: String,
+    /// Upper-case HTTP method
+    pub method: String,
+    /// Path template such as `/users/{id}`
+    pub path: String,
+    pub description: String,
+    pub parameters: Vec<OperationParameter>,
+    /// Content type of the request body, if the operation takes one
+    pub body_content_type: Option<String>,
+}
+
+/// A parsed OpenAPI document
+#[derive(Debug, Clone)]
+pub struct OpenApiSpec {
+    document: Value,
+}
+
+impl OpenApiSpec {
+    /// Read a `.json`, `.yaml` or `.yml` document
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            AppError::Configuration(format!(
+                "Cannot read OpenAPI spec {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let document: Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&text)?,
+            _ => serde_yaml::from_str(&text).map_err(|e| {
+                AppError::Configuration(format!("Invalid OpenAPI spec {}: {}", path.display(), e))
+            })?,
+        };
+        Self::from_value(document)
+    }
+
+    pub fn from_value(document: Value) -> Result<Self> {
+        let version = document["openapi"].as_str().unwrap_or_default();
+        if !version.starts_with("3.") {
+            return Err(AppError::Configuration(format!(
+                "Only OpenAPI 3 documents are supported, got '{}'",
+                version
+            )));
+        }
+        Ok(Self { document })
+    }
+
+    pub fn title(&self) -> &str {
+        self.document["info"]["title"].as_str().unwrap_or("api")
+    }
+
+    /// URL of the first server, with its variables set to their defaults
+    pub fn server_url(&self) -> Option<String> {
+        let server = &self.document["servers"][0];
+        let mut url = server["url"].as_str()?.to_string();
+        if let Some(variables) = server["variables"].as_object() {
+            for (name, variable) in variables {
+                let default = variable["default"].as_str().unwrap_or_default();
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+        Some(url)
+    }
+
+    /// Every operation in the document, in path order
+    pub fn operations(&self) -> Result<Vec<Operation>> {
+        let Some(paths) = self.document["paths"].as_object() else {
+            return Ok(Vec::new());
+        };
+
+        let mut operations = Vec::new();
+        for (path, item) in paths {
+            let item = self.resolve(item)?;
+            let shared = item["parameters"].as_array().cloned().unwrap_or_default();
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                operations.push(self.operation(path, method, operation, &shared)?);
+            }
+        }
+
+        for (i, operation) in operations.iter().enumerate() {
+            if operations[..i]
+                .iter()
+                .any(|o| o.operation_id == operation.operation_id)
+            {
+                return Err(AppError::Configuration(format!(
+                    "operationId '{}' is used twice",
+                    operation.operation_id
+                )));
+            }
+        }
+        Ok(operations)
+    }
+
+    fn operation(
+        &self,
+        path: &str,
+        method: &str,
+        operation: &Value,
+        shared: &[Value],
+    ) -> Result<Operation> {
+        let operation_id = match operation["operationId"].as_str() {
+            Some(id) => id.to_string(),
+            None => derived_operation_id(method, path),
+        };
+        let description = [&operation["summary"], &operation["description"]]
+            .iter()
+            .filter_map(|v| v.as_str())
+            .find(|s| !s.trim().is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+
+        // Operation-level parameters override path-level ones with the same name and location
+        let mut declared: Vec<Value> = Vec::new();
+        let own = operation["parameters"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for parameter in shared.iter().chain(own.iter()) {
+            let parameter = self.resolve(parameter)?.clone();
+            declared.retain(|p| p["name"] != parameter["name"] || p["in"] != parameter["in"]);
+            declared.push(parameter);
+        }
+
+        let mut parameters = Vec::new();
+        for parameter in declared {
+            let location = match parameter["in"].as_str() {
+                Some("path") => ParameterLocation::Path,
+                Some("query") => ParameterLocation::Query,
+                Some("header") => ParameterLocation::Header,
+                // Cookies are left to the configured headers
+                _ => continue,
+            };
+            let name = parameter["name"].as_str().unwrap_or_default().to_string();
+            let schema = self.resolve(&parameter["schema"])?;
+            parameters.push(OperationParameter {
+                parameter: ToolParameter {
+                    name: name.clone(),
+                    param_type: schema_type(schema),
+                    description: parameter["description"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    required: location == ParameterLocation::Path
+                        || parameter["required"].as_bool().unwrap_or(false),
+                    default: schema.get("default").cloned(),
+                    enum_values: string_enum(schema),
+                },
+                location,
+                wire_name: name,
+            });
+        }
+
+        let body_content_type = self.add_body_parameters(operation, &mut parameters)?;
+        Ok(Operation {
+            operation_id,
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            description,
+            parameters,
+            body_content_type,
+        })
+    }
+
+    /// Add parameters for the request body, preferring a JSON body
+    fn add_body_parameters(
+        &self,
+        operation: &Value,
+        parameters: &mut Vec<OperationParameter>,
+    ) -> Result<Option<String>> {
+        let body = self.resolve(&operation["requestBody"])?;
+        let Some(content) = body["content"].as_object() else {
+            return Ok(None);
+        };
+        let Some((content_type, media)) = content
+            .iter()
+            .find(|(t, _)| t.contains("json"))
+            .or_else(|| content.iter().next())
+        else {
+            return Ok(None);
+        };
+        let body_required = body["required"].as_bool().unwrap_or(false);
+        let schema = self.resolve(&media["schema"])?;
+
+        let properties = schema["properties"].as_object();
+        if let (true, Some(properties)) = (content_type.contains("json"), properties) {
+            let required: Vec<&str> = schema["required"]
+                .as_array()
+                .map(|names| names.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            for (name, property) in properties {
+                let property = self.resolve(property)?;
+                let clashes = parameters.iter().any(|p| &p.parameter.name == name);
+                parameters.push(OperationParameter {
+                    parameter: ToolParameter {
+                        name: if clashes {
+                            format!("body_{}", name)
+                        } else {
+                            name.clone()
+                        },
+                        param_type: schema_type(property),
+                        description: property["description"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        required: body_required && required.contains(&name.as_str()),
+                        default: property.get("default").cloned(),
+                        enum_values: string_enum(property),
+                    },
+                    location: ParameterLocation::BodyField,
+                    wire_name: name.clone(),
+                });
+            }
+        } else {
+            parameters.push(OperationParameter {
+                parameter: ToolParameter {
+                    name: "body".to_string(),
+                    param_type: schema_type(schema),
+                    description: body["description"]
+                        .as_str()
+                        .unwrap_or("Request body")
+                        .to_string(),
+                    required: body_required,
+                    default: None,
+                    enum_values: None,
+                },
+                location: ParameterLocation::Body,
+                wire_name: "body".to_string(),
+            });
+        }
+        Ok(Some(content_type.clone()))
+    }
+
+    /// Follow local `$ref`s such as `#/components/schemas/User`
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> Result<&'a Value> {
+        for _ in 0..MAX_REF_DEPTH {
+            let Some(reference) = value["$ref"].as_str() else {
+                return Ok(value);
+            };
+            let pointer = reference.strip_prefix('#').ok_or_else(|| {
+                AppError::Configuration(format!("Only local $refs are supported: {}", reference))
+            })?;
+            value = self.document.pointer(pointer).ok_or_else(|| {
+                AppError::Configuration(format!("Unresolved $ref: {}", reference))
+            })?;
+        }
+        Err(AppError::Configuration(
+            "$ref chain is too deep or circular".to_string(),
+        ))
+    }
+}
+
+/// `get_users_id` for `GET /users/{id}`
+fn derived_operation_id(method: &str, path: &str) -> String {
+    let mut id = method.to_string();
+    for part in path.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if !part.is_empty() {
+            id.push('_');
+            id.push_str(part);
+        }
+    }
+    id
+}
+
+/// JSON Schema type of `schema`, guessing from its shape when `type` is absent
+fn schema_type(schema: &Value) -> String {
+    match &schema["type"] {
+        Value::String(t) => t.clone(),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("string")
+            .to_string(),
+        _ if schema.get("properties").is_some() => "object".to_string(),
+        _ if schema.get("items").is_some() => "array".to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+fn string_enum(schema: &Value) -> Option<Vec<String>> {
+    schema["enum"].as_array().and_then(|values| {
+        values
+            .iter()
+            .map(|v| v.as_str().map(str::to_string))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn petstore() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Pet Store", "version": "1.0"},
+            "servers": [{"url": "https://{env}.example.com/v1", "variables": {"env": {"default": "api"}}}],
+            "paths": {
+                "/pets/{petId}": {
+                    "parameters": [{"$ref": "#/components/parameters/PetId"}],
+                    "get": {
+                        "operationId": "getPet",
+                        "summary": "Fetch one pet",
+                        "parameters": [
+                            {"name": "fields", "in": "query", "schema": {"type": "array", "items": {"type": "string"}}},
+                            {"name": "X-Trace", "in": "header", "schema": {"type": "string"}}
+                        ]
+                    },
+                    "patch": {
+                        "requestBody": {
+                            "required": true,
+                            "content": {"application/json": {"schema": {"$ref": "#/components/schemas/PetUpdate"}}}
+                        }
+                    }
+                },
+                "/pets": {
+                    "post": {
+                        "operationId": "createPet",
+                        "requestBody": {
+                            "content": {"text/plain": {"schema": {"type": "string"}}}
+                        }
+                    }
+                }
+            },
+            "components": {
+                "parameters": {
+                    "PetId": {"name": "petId", "in": "path", "description": "Pet ID", "schema": {"type": "integer"}}
+                },
+                "schemas": {
+                    "PetUpdate": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": {"type": "string"},
+                            "status": {"type": "string", "enum": ["available", "sold"]},
+                            "petId": {"type": "integer", "description": "New ID"}
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_operations_from_paths_refs_and_bodies() {
+        let spec = OpenApiSpec::from_value(petstore()).unwrap();
+        assert_eq!(spec.title(), "Pet Store");
+        assert_eq!(spec.server_url().unwrap(), "https://api.example.com/v1");
+
+        let operations = spec.operations().unwrap();
+        let mut ids: Vec<_> = operations.iter().map(|o| o.operation_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["createPet", "getPet", "patch_pets_petId"]);
+        let find = |id: &str| operations.iter().find(|o| o.operation_id == id).unwrap();
+
+        let get = find("getPet");
+        assert_eq!(get.method, "GET");
+        assert_eq!(get.description, "Fetch one pet");
+        let pet_id = &get.parameters[0];
+        assert_eq!(pet_id.location, ParameterLocation::Path);
+        assert_eq!(pet_id.parameter.param_type, "integer");
+        assert!(pet_id.parameter.required);
+        assert_eq!(get.parameters[1].parameter.param_type, "array");
+        assert_eq!(get.parameters[2].location, ParameterLocation::Header);
+        assert!(get.body_content_type.is_none());
+
+        let patch = find("patch_pets_petId");
+        assert_eq!(patch.body_content_type.as_deref(), Some("application/json"));
+        let name = patch
+            .parameters
+            .iter()
+            .find(|p| p.parameter.name == "name")
+            .unwrap();
+        assert!(name.parameter.required);
+        assert_eq!(name.location, ParameterLocation::BodyField);
+        // A body field named like the path parameter is renamed for the tool only
+        let renamed = patch
+            .parameters
+            .iter()
+            .find(|p| p.parameter.name == "body_petId")
+            .unwrap();
+        assert_eq!(renamed.wire_name, "petId");
+
+        let create = find("createPet");
+        assert_eq!(create.parameters[0].location, ParameterLocation::Body);
+        assert!(!create.parameters[0].parameter.required);
+    }
+
+    #[test]
+    fn test_rejects_swagger_2_and_bad_refs() {
+        assert!(OpenApiSpec::from_value(json!({"swagger": "2.0"})).is_err());
+
+        let mut document = petstore();
+        document["components"]["parameters"] = json!({});
+        let spec = OpenApiSpec::from_value(document).unwrap();
+        assert!(spec.operations().is_err());
+    }
+
+    #[test]
+    fn test_load_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.yaml");
+        std::fs::write(
+            &path,
+            "openapi: 3.1.0\ninfo:\n  title: Status\n  version: '1'\npaths:\n  /health:\n    get:\n      operationId: health\n",
+        )
+        .unwrap();
+        let spec = OpenApiSpec::load(&path).unwrap();
+        assert_eq!(spec.operations().unwrap()[0].operation_id, "health");
+    }
+}
@@ -0,0 +1,340 @@
+//! Tools that call one OpenAPI operation each
+
+use crate::core::{AppError, Result, Tool, ToolContext, ToolParameter, ToolResult};
+use crate::tools::openapi::spec::{Operation, ParameterLocation};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Longest response body, in characters, handed back to the agent
+const MAX_RESPONSE_CHARS: usize = 20_000;
+
+/// Parse a response body, cutting it to `MAX_RESPONSE_CHARS` first. An
+/// oversized JSON body comes back as truncated text rather than a value.
+fn truncate_body(text: String) -> Value {
+    let length = text.chars().count();
+    if length > MAX_RESPONSE_CHARS {
+        let kept: String = text.chars().take(MAX_RESPONSE_CHARS).collect();
+        return Value::String(format!(
+            "{}... [truncated {} characters]",
+            kept,
+            length - MAX_RESPONSE_CHARS
+        ));
+    }
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+/// Connection settings shared by every operation of one API
+#[derive(Debug)]
+pub struct ApiClient {
+    pub base_url: String,
+    pub headers: Vec<(String, String)>,
+    pub timeout: Duration,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, headers: Vec<(String, String)>, timeout: Duration) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers,
+            timeout,
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+/// A tool that sends one operation's HTTP request
+#[derive(Debug, Clone)]
+pub struct OpenApiTool {
+    id: String,
+    category: String,
+    operation: Operation,
+    parameters: Vec<ToolParameter>,
+    api: Arc<ApiClient>,
+}
+
+impl OpenApiTool {
+    /// Tool `<api>__<operationId>` in category `category`
+    pub fn new(api_name: &str, category: &str, operation: Operation, api: Arc<ApiClient>) -> Self {
+        Self {
+            id: crate::core::tool_id(api_name, &operation.operation_id),
+            category: category.to_string(),
+            parameters: operation
+                .parameters
+                .iter()
+                .map(|p| p.parameter.clone())
+                .collect(),
+            operation,
+            api,
+        }
+    }
+
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
+    /// Build the request for `args` without sending it
+    pub fn build_request(&self, args: &Value) -> Result<reqwest::RequestBuilder> {
+        let mut path = self.operation.path.clone();
+        let mut query: Vec<(String, String)> = Vec::new();
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut fields = Map::new();
+        let mut body: Option<Value> = None;
+
+        for param in &self.operation.parameters {
+            let Some(value) = args.get(&param.parameter.name).filter(|v| !v.is_null()) else {
+                continue;
+            };
+            match param.location {
+                ParameterLocation::Path => {
+                    let placeholder = format!("{{{}}}", param.wire_name);
+                    let encoded = urlencoding::encode(&plain(value)).into_owned();
+                    path = path.replace(&placeholder, &encoded);
+                }
+                ParameterLocation::Query => match value {
+                    Value::Array(items) => query.extend(
+                        items
+                            .iter()
+                            .map(|item| (param.wire_name.clone(), plain(item))),
+                    ),
+                    _ => query.push((param.wire_name.clone(), plain(value))),
+                },
+                ParameterLocation::Header => headers.push((param.wire_name.clone(), plain(value))),
+                ParameterLocation::BodyField => {
+                    fields.insert(param.wire_name.clone(), value.clone());
+                }
+                ParameterLocation::Body => body = Some(value.clone()),
+            }
+        }
+        if path.contains('{') {
+            return Err(AppError::Tool(format!(
+                "Invalid arguments: {} needs every path parameter",
+                self.operation.path
+            )));
+        }
+
+        let method = reqwest::Method::from_bytes(self.operation.method.as_bytes())
+            .map_err(|e| AppError::Tool(format!("Invalid HTTP method: {}", e)))?;
+        let mut request = self
+            .api
+            .http
+            .request(method, format!("{}{}", self.api.base_url, path))
+            .timeout(self.api.timeout);
+        if !query.is_empty() {
+            request = request.query(&query);
+        }
+        for (name, value) in self.api.headers.iter().chain(headers.iter()) {
+            request = request.header(name, value);
+        }
+
+        if !fields.is_empty() && body.is_none() {
+            body = Some(Value::Object(fields));
+        }
+        if let (Some(body), Some(content_type)) = (body, &self.operation.body_content_type) {
+            request = request.header("content-type", content_type);
+            request = if content_type.contains("json") {
+                request.body(body.to_string())
+            } else {
+                request.body(plain(&body))
+            };
+        }
+        Ok(request)
+    }
+}
+
+/// A value as it appears in a URL or header: strings without quotes
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for OpenApiTool {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The registry ID, so operations that share an ID across APIs stay apart
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn description(&self) -> &str {
+        &self.operation.description
+    }
+
+    fn category(&self) -> &str {
+        &self.category
+    }
+
+    fn parameters(&self) -> &[ToolParameter] {
+        &self.parameters
+    }
+
+    async fn execute(&self, args: &Value, _context: &ToolContext) -> Result<ToolResult> {
+        let start = Instant::now();
+        self.validate_args(args)?;
+        let response = self
+            .build_request(args)?
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("{} failed: {}", self.id, e)))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| AppError::Network(format!("{} failed: {}", self.id, e)))?;
+        let body = truncate_body(text);
+
+        Ok(ToolResult {
+            success: status.is_success(),
+            message: format!(
+                "{} {} returned {}",
+                self.operation.method, self.operation.path, status
+            ),
+            data: Some(json!({"status": status.as_u16(), "body": body})),
+            execution_time: start.elapsed(),
+        })
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<()> {
+        let fields = args
+            .as_object()
+            .ok_or_else(|| AppError::Tool("Invalid arguments: expected an object".to_string()))?;
+        for param in &self.parameters {
+            if param.required && !fields.contains_key(&param.name) {
+                return Err(AppError::Tool(format!(
+                    "Invalid arguments: missing '{}'",
+                    param.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::openapi::spec::OpenApiSpec;
+    use axum::extract::{Path, Query};
+    use axum::http::HeaderMap;
+    use std::collections::HashMap;
+
+    fn tools(base_url: &str) -> Vec<OpenApiTool> {
+        let spec = OpenApiSpec::from_value(json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Notes", "version": "1"},
+            "paths": {
+                "/notes/{id}": {
+                    "get": {
+                        "operationId": "getNote",
+                        "parameters": [
+                            {"name": "id", "in": "path", "schema": {"type": "string"}},
+                            {"name": "format", "in": "query", "schema": {"type": "string"}},
+                            {"name": "X-Request-Id", "in": "header", "schema": {"type": "string"}}
+                        ]
+                    },
+                    "put": {
+                        "operationId": "updateNote",
+                        "parameters": [{"name": "id", "in": "path", "schema": {"type": "string"}}],
+                        "requestBody": {"required": true, "content": {"application/json": {"schema": {
+                            "type": "object", "required": ["text"],
+                            "properties": {"text": {"type": "string"}, "pinned": {"type": "boolean"}}
+                        }}}}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let api = Arc::new(ApiClient::new(
+            base_url,
+            vec![("Authorization".to_string(), "Bearer secret".to_string())],
+            Duration::from_secs(5),
+        ));
+        spec.operations()
+            .unwrap()
+            .into_iter()
+            .map(|operation| OpenApiTool::new("notes", "notes", operation, api.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_path_query_header_and_body() {
+        async fn get_note(
+            Path(id): Path<String>,
+            Query(query): Query<HashMap<String, String>>,
+            headers: HeaderMap,
+        ) -> axum::Json<Value> {
+            axum::Json(json!({
+                "id": id,
+                "format": query.get("format"),
+                "auth": headers["authorization"].to_str().unwrap(),
+                "request": headers["x-request-id"].to_str().unwrap(),
+            }))
+        }
+        async fn update_note(Path(id): Path<String>, body: axum::Json<Value>) -> axum::Json<Value> {
+            axum::Json(json!({"id": id, "received": body.0}))
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+        let app =
+            axum::Router::new().route("/notes/:id", axum::routing::get(get_note).put(update_note));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let tools = tools(&base_url);
+        let find = |id: &str| tools.iter().find(|t| t.id() == id).unwrap();
+        let context = ToolContext {
+            agent_id: "test".to_string(),
+            conversation_manager: None,
+            execution_state: Default::default(),
+        };
+
+        let get = find("notes__getNote");
+        assert_eq!(get.name(), "notes__getNote");
+        assert_eq!(get.category(), "notes");
+        let result = get
+            .execute(
+                &json!({"id": "a b", "format": "md", "X-Request-Id": "r1"}),
+                &context,
+            )
+            .await
+            .unwrap();
+        assert!(result.success);
+        let body = &result.data.unwrap()["body"];
+        assert_eq!(body["id"], "a b");
+        assert_eq!(body["format"], "md");
+        assert_eq!(body["auth"], "Bearer secret");
+        assert_eq!(body["request"], "r1");
+
+        let update = find("notes__updateNote");
+        assert!(update.validate_args(&json!({"id": "1"})).is_err());
+        let result = update
+            .execute(&json!({"id": "1", "text": "hi", "pinned": true}), &context)
+            .await
+            .unwrap();
+        let body = &result.data.unwrap()["body"];
+        assert_eq!(body["received"], json!({"text": "hi", "pinned": true}));
+    }
+
+    #[test]
+    fn test_truncate_body_limits_json_too() {
+        assert_eq!(
+            truncate_body(r#"{"ok": true}"#.to_string()),
+            json!({"ok": true})
+        );
+        assert_eq!(truncate_body("plain".to_string()), json!("plain"));
+
+        let items = vec!["x".repeat(100); MAX_RESPONSE_CHARS / 50];
+        let large = serde_json::to_string(&items).unwrap();
+        let body = truncate_body(large);
+        let text = body.as_str().unwrap();
+        assert!(text.chars().count() < MAX_RESPONSE_CHARS + 50);
+        assert!(text.ends_with("characters]"));
+    }
+}
@@ -0,0 +1,385 @@
+//! Tools implemented by external executables
+//!
+//! A plugin is a directory with a `plugin.toml` (or `plugin.json`) manifest. To
+//! run the tool, its command receives `{"tool", "args", "agent_id"}` as JSON on
+//! stdin and answers with `{"success", "message", "data"}` on stdout.
+
+use crate::core::{AppError, Result, Tool, ToolContext, ToolParameter, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+/// Manifest file names, in order of preference
+pub const MANIFEST_FILES: [&str; 2] = ["plugin.toml", "plugin.json"];
+
+/// Seconds a plugin may run when its manifest doesn't say
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Description of a plugin tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    #[serde(default)]
+    pub parameters: Vec<ToolParameter>,
+    /// Capabilities agents can find this tool by
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Other tools that must be registered for this one to work
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Executable to run; paths containing a separator are relative to the plugin directory
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+fn default_category() -> String {
+    "plugin".to_string()
+}
+
+impl PluginManifest {
+    /// Read the manifest of a plugin directory
+    pub fn load(directory: &Path) -> Result<Self> {
+        let path = manifest_path(directory).ok_or_else(|| {
+            AppError::Registry(format!(
+                "Plugin directory '{}' has no plugin.toml or plugin.json",
+                directory.display()
+            ))
+        })?;
+        let text = std::fs::read_to_string(&path)?;
+        let manifest: Self = if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            serde_json::from_str(&text)?
+        } else {
+            toml::from_str(&text).map_err(|e| {
+                AppError::Registry(format!("Invalid manifest {}: {}", path.display(), e))
+            })?
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Check the fields the registry and LLM rely on
+    pub fn validate(&self) -> Result<()> {
+        let valid_id = !self.id.is_empty()
+            && self
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_id {
+            return Err(AppError::Registry(format!(
+                "Invalid plugin ID '{}': use letters, digits, '_' and '-'",
+                self.id
+            )));
+        }
+        if self.command.trim().is_empty() {
+            return Err(AppError::Registry(format!(
+                "Plugin '{}' has no command",
+                self.id
+            )));
+        }
+        if self.tools.contains(&self.id) {
+            return Err(AppError::Registry(format!(
+                "Plugin '{}' depends on itself",
+                self.id
+            )));
+        }
+        for (i, param) in self.parameters.iter().enumerate() {
+            if self.parameters[..i].iter().any(|p| p.name == param.name) {
+                return Err(AppError::Registry(format!(
+                    "Plugin '{}' declares parameter '{}' twice",
+                    self.id, param.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The manifest file of a plugin directory, if it has one
+pub fn manifest_path(directory: &Path) -> Option<PathBuf> {
+    MANIFEST_FILES
+        .iter()
+        .map(|name| directory.join(name))
+        .find(|path| path.is_file())
+}
+
+/// What a plugin prints on stdout
+#[derive(Debug, Deserialize)]
+struct PluginResponse {
+    #[serde(default = "default_success")]
+    success: bool,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// A tool backed by a plugin executable
+#[derive(Debug, Clone)]
+pub struct PluginTool {
+    manifest: PluginManifest,
+    directory: PathBuf,
+}
+
+impl PluginTool {
+    pub fn new(manifest: PluginManifest, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest,
+            directory: directory.into(),
+        }
+    }
+
+    /// Load the plugin in `directory`
+    pub fn load(directory: &Path) -> Result<Self> {
+        Ok(Self::new(PluginManifest::load(directory)?, directory))
+    }
+
+    pub fn manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn program(&self) -> PathBuf {
+        let command = Path::new(&self.manifest.command);
+        if command.components().count() > 1 && command.is_relative() {
+            self.directory.join(command)
+        } else {
+            command.to_path_buf()
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for PluginTool {
+    fn id(&self) -> &str {
+        &self.manifest.id
+    }
+
+    fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    fn description(&self) -> &str {
+        &self.manifest.description
+    }
+
+    fn category(&self) -> &str {
+        &self.manifest.category
+    }
+
+    fn parameters(&self) -> &[ToolParameter] {
+        &self.manifest.parameters
+    }
+
+    fn capabilities(&self) -> &[String] {
+        &self.manifest.capabilities
+    }
+
+    fn tool_dependencies(&self) -> &[String] {
+        &self.manifest.tools
+    }
+
+    async fn execute(&self, args: &serde_json::Value, context: &ToolContext) -> Result<ToolResult> {
+        let start = Instant::now();
+        let id = &self.manifest.id;
+        let mut child = tokio::process::Command::new(self.program())
+            .args(&self.manifest.args)
+            .current_dir(&self.directory)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| AppError::Tool(format!("Failed to start plugin {}: {}", id, e)))?;
+
+        let request = serde_json::json!({
+            "tool": id,
+            "args": args,
+            "agent_id": context.agent_id,
+        });
+        let stdin = child.stdin.take();
+        let write = async move {
+            if let Some(mut stdin) = stdin {
+                // A plugin that ignores its input may exit before reading it
+                match stdin.write_all(request.to_string().as_bytes()).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+                    _ => {}
+                }
+                // Dropping stdin closes it so the plugin sees end of input
+            }
+            Ok(())
+        };
+        // Write while reading, so a plugin that answers before draining its
+        // input can't stall on a full pipe
+        let exchange = async move {
+            let (written, output) = tokio::join!(write, child.wait_with_output());
+            written?;
+            output
+        };
+
+        let timeout =
+            Duration::from_secs(self.manifest.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+        // On timeout the exchange, and with it the child, is dropped, which kills it
+        let output = tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| {
+                AppError::Tool(format!("Plugin {} timed out after {:?}", id, timeout))
+            })??;
+
+        if !output.status.success() {
+            return Err(AppError::Tool(format!(
+                "Plugin {} exited with {}: {}",
+                id,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let response: PluginResponse = serde_json::from_slice(&output.stdout)
+            .map_err(|e| AppError::Tool(format!("Plugin {} returned invalid JSON: {}", id, e)))?;
+        Ok(ToolResult {
+            success: response.success,
+            message: response.message,
+            data: response.data,
+            execution_time: start.elapsed(),
+        })
+    }
+
+    fn validate_args(&self, args: &serde_json::Value) -> Result<()> {
+        let fields = args
+            .as_object()
+            .ok_or_else(|| AppError::Tool("Invalid arguments: expected an object".to_string()))?;
+        for param in &self.manifest.parameters {
+            if param.required && !fields.contains_key(&param.name) {
+                return Err(AppError::Tool(format!(
+                    "Invalid arguments: missing '{}'",
+                    param.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+id = "shout"
+name = "Shout"
+description = "Upper-cases text"
+command = "sh"
+args = ["-c", "read input; echo '{\"message\": \"HELLO\", \"data\": {\"echo\": '\"$input\"'}}'"]
+
+[[parameters]]
+name = "text"
+param_type = "string"
+description = "Text to shout"
+required = true
+"#;
+
+    #[test]
+    fn test_manifest_parsing_and_validation() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::write(dir.join("plugin.toml"), MANIFEST).unwrap();
+
+        let manifest = PluginManifest::load(dir).unwrap();
+        assert_eq!(manifest.id, "shout");
+        assert_eq!(manifest.category, "plugin");
+        assert!(manifest.parameters[0].required);
+
+        let bad = PluginManifest {
+            id: "has space".to_string(),
+            ..manifest
+        };
+        assert!(bad.validate().is_err());
+        assert!(PluginManifest::load(&dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_validate_args_checks_required() {
+        let tool = PluginTool::new(toml::from_str(MANIFEST).unwrap(), ".");
+        assert!(tool
+            .validate_args(&serde_json::json!({"text": "hi"}))
+            .is_ok());
+        assert!(tool.validate_args(&serde_json::json!({})).is_err());
+        assert!(tool.validate_args(&serde_json::json!("hi")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_speaks_json_over_stdio() {
+        let tool = PluginTool::new(toml::from_str(MANIFEST).unwrap(), std::env::temp_dir());
+        let context = ToolContext {
+            agent_id: "test".to_string(),
+            conversation_manager: None,
+            execution_state: Default::default(),
+        };
+
+        let result = tool
+            .execute(&serde_json::json!({"text": "hi"}), &context)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.message, "HELLO");
+        assert_eq!(result.data.unwrap()["echo"]["args"]["text"], "hi");
+
+        let failing = PluginTool::new(
+            PluginManifest {
+                command: "false".to_string(),
+                args: Vec::new(),
+                ..toml::from_str(MANIFEST).unwrap()
+            },
+            std::env::temp_dir(),
+        );
+        assert!(failing
+            .execute(&serde_json::json!({}), &context)
+            .await
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_covers_a_plugin_that_never_reads() {
+        // The request is larger than a pipe buffer, so writing it blocks
+        let tool = PluginTool::new(
+            PluginManifest {
+                command: "sleep".to_string(),
+                args: vec!["30".to_string()],
+                timeout_secs: Some(1),
+                ..toml::from_str(MANIFEST).unwrap()
+            },
+            std::env::temp_dir(),
+        );
+        let context = ToolContext {
+            agent_id: "test".to_string(),
+            conversation_manager: None,
+            execution_state: Default::default(),
+        };
+
+        let start = Instant::now();
+        let error = tool
+            .execute(&serde_json::json!({"text": "x".repeat(1 << 20)}), &context)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
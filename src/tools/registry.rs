@@ -1,16 +1,18 @@
 //! Tool registry for managing tool components
 
 use crate::core::Tool;
+use crate::registry::events::{emit, EventSender, RegistryEvent};
 use async_trait::async_trait;
+use tokio::sync::RwLock;
 
-/// Trait for tool registries
+/// Trait for tool registries; implementations lock internally so they can be shared
 #[async_trait]
 pub trait ToolRegistry: Send + Sync {
     /// Register a tool
-    async fn register(&mut self, tool: Box<dyn Tool>) -> crate::core::Result<()>;
+    async fn register(&self, tool: Box<dyn Tool>) -> crate::core::Result<()>;
 
     /// Unregister a tool by ID
-    async fn unregister(&mut self, id: &str) -> crate::core::Result<()>;
+    async fn unregister(&self, id: &str) -> crate::core::Result<()>;
 
     /// Get a tool by ID
     async fn get(&self, id: &str) -> crate::core::Result<Option<Box<dyn Tool>>>;
@@ -36,24 +38,67 @@ pub trait ToolRegistry: Send + Sync {
         &self,
         tool_id: &str,
     ) -> crate::core::Result<Option<crate::registry::ComponentMetadata>>;
+
+    /// Whether a tool is offered to agents; `None` if it isn't registered
+    async fn is_enabled(&self, id: &str) -> crate::core::Result<Option<bool>>;
+
+    /// Offer a registered tool to every agent, or to none
+    async fn set_enabled(&self, id: &str, enabled: bool) -> crate::core::Result<()>;
+
+    /// Merge `config` into the tool's current configuration, validate the
+    /// result against the tool's schema and apply it
+    async fn configure(&self, id: &str, config: serde_json::Value) -> crate::core::Result<()>;
+}
+
+/// Overlay the keys of `patch` onto `base`, recursing into nested objects
+fn merge(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
 }
 
 /// Default implementation of ToolRegistry
 pub struct DefaultToolRegistry {
+    state: RwLock<ToolState>,
+    events: Option<EventSender>,
+}
+
+/// Registered tools and their indexes, guarded by one lock
+#[derive(Default)]
+struct ToolState {
     tools: std::collections::HashMap<String, Box<dyn Tool>>,
     category_index: std::collections::HashMap<String, Vec<String>>,
     capability_index: std::collections::HashMap<String, Vec<String>>,
+    /// Tools kept from every agent
+    disabled: std::collections::HashSet<String>,
 }
 
 impl DefaultToolRegistry {
     pub fn new() -> Self {
         Self {
-            tools: std::collections::HashMap::new(),
-            category_index: std::collections::HashMap::new(),
-            capability_index: std::collections::HashMap::new(),
+            state: RwLock::new(ToolState::default()),
+            events: None,
         }
     }
 
+    /// Publish tool enabled, disabled and configured events on `events`
+    pub fn with_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+}
+
+impl ToolState {
     /// Rebuild category and capability indexes
     fn rebuild_indexes(&mut self) {
         self.category_index.clear();
@@ -67,53 +112,95 @@ impl DefaultToolRegistry {
                 .or_insert_with(Vec::new)
                 .push(tool_id.clone());
 
-            // Build capability index (tools can provide capabilities)
-            // For now, we'll use the tool ID as a capability
-            let capability = tool.id().to_string();
-            self.capability_index
-                .entry(capability)
-                .or_insert_with(Vec::new)
-                .push(tool_id.clone());
+            // Build capability index
+            for capability in tool.capabilities() {
+                self.capability_index
+                    .entry(capability.clone())
+                    .or_default()
+                    .push(tool_id.clone());
+            }
+        }
+    }
+
+    /// Clones of the tools listed in an index entry
+    fn lookup(&self, tool_ids: Option<&Vec<String>>) -> Vec<Box<dyn Tool>> {
+        tool_ids
+            .into_iter()
+            .flatten()
+            .filter_map(|tool_id| self.tools.get(tool_id))
+            .map(|tool| dyn_clone::clone_box(tool.as_ref()))
+            .collect()
+    }
+
+    fn validate_dependencies(&self, tool: &dyn Tool) -> crate::core::Result<()> {
+        for dependency in tool.tool_dependencies() {
+            if dependency == tool.id() || !self.tools.contains_key(dependency) {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Tool '{}' requires tool '{}' which is not available",
+                    tool.id(),
+                    dependency
+                )));
+            }
         }
+        Ok(())
     }
 }
 
 #[async_trait]
 impl ToolRegistry for DefaultToolRegistry {
-    async fn register(&mut self, tool: Box<dyn Tool>) -> crate::core::Result<()> {
+    async fn register(&self, tool: Box<dyn Tool>) -> crate::core::Result<()> {
         let tool_id = tool.id().to_string();
+        {
+            let mut state = self.state.write().await;
+            if state.tools.contains_key(&tool_id) {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Tool '{}' already registered",
+                    tool_id
+                )));
+            }
+            state.validate_dependencies(tool.as_ref())?;
 
-        if self.tools.contains_key(&tool_id) {
-            return Err(crate::core::AppError::Registry(format!(
-                "Tool '{}' already registered",
-                tool_id
-            )));
+            state.tools.insert(tool_id.clone(), tool);
+            state.rebuild_indexes();
         }
 
-        self.tools.insert(tool_id, tool);
-        self.rebuild_indexes();
-
+        emit(&self.events, RegistryEvent::ToolEnabled { tool_id });
         Ok(())
     }
 
-    async fn unregister(&mut self, id: &str) -> crate::core::Result<()> {
-        if self.tools.remove(id).is_none() {
-            return Err(crate::core::AppError::Registry(format!(
-                "Tool '{}' not found",
-                id
-            )));
+    async fn unregister(&self, id: &str) -> crate::core::Result<()> {
+        {
+            let mut state = self.state.write().await;
+            if state.tools.remove(id).is_none() {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Tool '{}' not found",
+                    id
+                )));
+            }
+            state.disabled.remove(id);
+            state.rebuild_indexes();
         }
 
-        self.rebuild_indexes();
+        emit(
+            &self.events,
+            RegistryEvent::ToolRemoved {
+                tool_id: id.to_string(),
+            },
+        );
         Ok(())
     }
 
     async fn get(&self, id: &str) -> crate::core::Result<Option<Box<dyn Tool>>> {
-        Ok(self.tools.get(id).map(|t| dyn_clone::clone_box(t.as_ref())))
+        let state = self.state.read().await;
+        Ok(state
+            .tools
+            .get(id)
+            .map(|t| dyn_clone::clone_box(t.as_ref())))
     }
 
     async fn list(&self) -> crate::core::Result<Vec<Box<dyn Tool>>> {
-        Ok(self
+        let state = self.state.read().await;
+        Ok(state
             .tools
             .values()
             .map(|t| dyn_clone::clone_box(t.as_ref()))
@@ -121,68 +208,113 @@ impl ToolRegistry for DefaultToolRegistry {
     }
 
     async fn count(&self) -> crate::core::Result<usize> {
-        Ok(self.tools.len())
+        Ok(self.state.read().await.tools.len())
     }
 
     async fn find_by_category(&self, category: &str) -> crate::core::Result<Vec<Box<dyn Tool>>> {
-        let tool_ids = self
-            .category_index
-            .get(category)
-            .cloned()
-            .unwrap_or_default();
-
-        let mut results = Vec::new();
-        for tool_id in tool_ids {
-            if let Some(tool) = self.tools.get(&tool_id) {
-                results.push(dyn_clone::clone_box(tool.as_ref()));
-            }
-        }
-
-        Ok(results)
+        let state = self.state.read().await;
+        Ok(state.lookup(state.category_index.get(category)))
     }
 
     async fn find_by_capability(
         &self,
         capability: &str,
     ) -> crate::core::Result<Vec<Box<dyn Tool>>> {
-        let tool_ids = self
-            .capability_index
-            .get(capability)
-            .cloned()
-            .unwrap_or_default();
-
-        let mut results = Vec::new();
-        for tool_id in tool_ids {
-            if let Some(tool) = self.tools.get(&tool_id) {
-                results.push(dyn_clone::clone_box(tool.as_ref()));
-            }
-        }
-
-        Ok(results)
+        let state = self.state.read().await;
+        Ok(state.lookup(state.capability_index.get(capability)))
     }
 
-    async fn validate_dependencies(&self, _tool: &dyn Tool) -> crate::core::Result<()> {
-        // For now, tools don't have dependencies on other tools
-        // This could be extended in the future
-        Ok(())
+    async fn validate_dependencies(&self, tool: &dyn Tool) -> crate::core::Result<()> {
+        self.state.read().await.validate_dependencies(tool)
     }
 
     async fn get_metadata(
         &self,
         tool_id: &str,
     ) -> crate::core::Result<Option<crate::registry::ComponentMetadata>> {
-        if let Some(tool) = self.tools.get(tool_id) {
+        let state = self.state.read().await;
+        if let Some(tool) = state.tools.get(tool_id) {
             Ok(Some(crate::registry::ComponentMetadata {
                 id: tool.id().to_string(),
                 name: tool.name().to_string(),
                 version: "1.0.0".to_string(), // Tools don't have versions yet
                 description: tool.description().to_string(),
                 category: tool.category().to_string(),
-                dependencies: Vec::new(), // Tools don't have dependencies yet
-                capabilities: vec![tool.id().to_string()], // Tool provides its own capability
+                dependencies: tool.tool_dependencies().to_vec(),
+                capabilities: tool.capabilities().to_vec(),
             }))
         } else {
             Ok(None)
         }
     }
+
+    async fn is_enabled(&self, id: &str) -> crate::core::Result<Option<bool>> {
+        let state = self.state.read().await;
+        Ok(state
+            .tools
+            .contains_key(id)
+            .then(|| !state.disabled.contains(id)))
+    }
+
+    async fn set_enabled(&self, id: &str, enabled: bool) -> crate::core::Result<()> {
+        let changed = {
+            let mut state = self.state.write().await;
+            if !state.tools.contains_key(id) {
+                return Err(crate::core::AppError::Registry(format!(
+                    "Tool '{}' not found",
+                    id
+                )));
+            }
+            if enabled {
+                state.disabled.remove(id)
+            } else {
+                state.disabled.insert(id.to_string())
+            }
+        };
+
+        if changed {
+            let tool_id = id.to_string();
+            emit(
+                &self.events,
+                if enabled {
+                    RegistryEvent::ToolEnabled { tool_id }
+                } else {
+                    RegistryEvent::ToolDisabled { tool_id }
+                },
+            );
+        }
+        Ok(())
+    }
+
+    async fn configure(&self, id: &str, config: serde_json::Value) -> crate::core::Result<()> {
+        {
+            let mut state = self.state.write().await;
+            let tool = state.tools.get_mut(id).ok_or_else(|| {
+                crate::core::AppError::Registry(format!("Tool '{}' not found", id))
+            })?;
+            let schema = tool.config_schema().ok_or_else(|| {
+                crate::core::AppError::Tool(format!("Tool '{}' has no configuration", id))
+            })?;
+            let mut merged = tool.config().unwrap_or_else(|| serde_json::json!({}));
+            merge(&mut merged, config);
+            let config = merged;
+            let errors = crate::llm::structured::validate(&config, &schema);
+            if !errors.is_empty() {
+                return Err(crate::core::AppError::Tool(format!(
+                    "Invalid configuration for tool '{}': {}",
+                    id,
+                    errors.join("; ")
+                )));
+            }
+            tool.configure(&config)?;
+        }
+
+        emit(
+            &self.events,
+            RegistryEvent::ToolConfigured {
+                tool_id: id.to_string(),
+            },
+        );
+        Ok(())
+    }
 }
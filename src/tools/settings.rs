@@ -0,0 +1,174 @@
+//! Per-tool settings: whether a tool is offered to agents and its configuration block
+//!
+//! Settings live in `tools.toml`, keyed by tool ID:
+//!
+//! ```toml
+//! [keyboard_type]
+//! enabled = false
+//!
+//! [web_search.config]
+//! max_results = 5
+//! ```
+//!
+//! Each `config` block is validated against the schema the tool declares.
+
+use crate::core::{AppError, Result};
+use crate::tools::registry::ToolRegistry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the tool settings file
+pub const TOOL_SETTINGS_ENV: &str = "CF_AI_TOOL_SETTINGS";
+
+/// Settings of one tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSetting {
+    /// Offer the tool to agents
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Configuration block, checked against the tool's schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Settings of every tool that has any, keyed by tool ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ToolSettings {
+    pub tools: BTreeMap<String, ToolSetting>,
+}
+
+impl ToolSettings {
+    /// Path from `CF_AI_TOOL_SETTINGS`, or `tools.toml` in the user's config directory
+    pub fn default_path() -> PathBuf {
+        match std::env::var(TOOL_SETTINGS_ENV) {
+            Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => dirs::config_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cf_ai_local_tools")
+                .join("tools.toml"),
+        }
+    }
+
+    /// Read the default settings; a missing file means every tool keeps its defaults
+    pub fn from_env() -> Result<Self> {
+        Self::load(&Self::default_path())
+    }
+
+    /// Read a settings file; a missing file means every tool keeps its defaults
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&text).map_err(|e| {
+            AppError::Configuration(format!("Invalid tool settings {}: {}", path.display(), e))
+        })
+    }
+
+    /// Apply the settings to registered tools, returning the ones that could not be applied
+    pub async fn apply(&self, tools: &dyn ToolRegistry) -> Result<Vec<(String, String)>> {
+        let mut errors = Vec::new();
+        for (tool_id, setting) in &self.tools {
+            if tools.get(tool_id).await?.is_none() {
+                errors.push((tool_id.clone(), "tool is not registered".to_string()));
+                continue;
+            }
+            if let Some(config) = &setting.config {
+                if let Err(e) = tools.configure(tool_id, config.clone()).await {
+                    errors.push((tool_id.clone(), e.to_string()));
+                }
+            }
+            tools.set_enabled(tool_id, setting.enabled).await?;
+        }
+        Ok(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::registry::DefaultToolRegistry;
+    use crate::tools::{FetchUrl, TypeText, WebSearch};
+
+    #[tokio::test]
+    async fn test_apply_settings_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tools.toml");
+        std::fs::write(
+            &path,
+            r#"
+[keyboard_type]
+enabled = false
+
+[web_search.config]
+max_results = 5
+
+[missing_tool]
+enabled = false
+
+[fetch_url.config]
+max_content_length = "lots"
+"#,
+        )
+        .unwrap();
+        let settings = ToolSettings::load(&path).unwrap();
+
+        let tools = DefaultToolRegistry::new();
+        tools.register(Box::new(TypeText::new())).await.unwrap();
+        tools.register(Box::new(WebSearch::new())).await.unwrap();
+        tools.register(Box::new(FetchUrl::new())).await.unwrap();
+
+        let errors = settings.apply(&tools).await.unwrap();
+        let failed: Vec<&str> = errors.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(failed, vec!["fetch_url", "missing_tool"]);
+        assert!(errors[0].1.contains("$.max_content_length"));
+
+        assert_eq!(
+            tools.is_enabled("keyboard_type").await.unwrap(),
+            Some(false)
+        );
+        assert_eq!(tools.is_enabled("web_search").await.unwrap(), Some(true));
+        let search = tools.get("web_search").await.unwrap().unwrap();
+        assert_eq!(search.config().unwrap()["max_results"], 5);
+        assert_eq!(search.config().unwrap()["provider"], "duckduckgo");
+    }
+
+    #[tokio::test]
+    async fn test_configure_merges_keys() {
+        let tools = DefaultToolRegistry::new();
+        tools.register(Box::new(WebSearch::new())).await.unwrap();
+
+        tools
+            .configure("web_search", serde_json::json!({"max_results": 5}))
+            .await
+            .unwrap();
+        tools
+            .configure("web_search", serde_json::json!({"timeout_seconds": 3}))
+            .await
+            .unwrap();
+
+        let config = tools
+            .get("web_search")
+            .await
+            .unwrap()
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config["max_results"], 5);
+        assert_eq!(config["timeout_seconds"], 3);
+        assert_eq!(config["provider"], "duckduckgo");
+    }
+
+    #[test]
+    fn test_missing_settings_file_keeps_defaults() {
+        let settings = ToolSettings::load(Path::new("/nonexistent/tools.toml")).unwrap();
+        assert!(settings.tools.is_empty());
+    }
+}
@@ -8,6 +8,28 @@ pub struct FetchUrl {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub config: FetchUrlConfig,
+}
+
+/// Limits applied to every fetch, whatever the caller asks for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FetchUrlConfig {
+    /// Most characters of content returned
+    pub max_content_length: u32,
+    /// Longest request timeout in seconds
+    pub max_timeout_seconds: u32,
+}
+
+impl Default for FetchUrlConfig {
+    fn default() -> Self {
+        Self {
+            max_content_length: 50_000,
+            max_timeout_seconds: 60,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +47,8 @@ impl FetchUrl {
             name: "Fetch URL".to_string(),
             description: "Fetch content from a specific URL".to_string(),
             category: "web".to_string(),
+            capabilities: vec!["content_extraction".to_string()],
+            config: FetchUrlConfig::default(),
             parameters: vec![
                 ToolParameter {
                     name: "url".to_string(),
@@ -86,6 +110,39 @@ impl Tool for FetchUrl {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "max_content_length": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Most characters of content returned"
+                },
+                "max_timeout_seconds": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Longest request timeout in seconds"
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn config(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.config).ok()
+    }
+
+    fn configure(&mut self, config: &serde_json::Value) -> crate::core::Result<()> {
+        self.config = serde_json::from_value(config.clone())
+            .map_err(|e| crate::core::AppError::Tool(format!("Invalid configuration: {}", e)))?;
+        Ok(())
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -103,8 +160,14 @@ impl Tool for FetchUrl {
         }
 
         let include_html = args.include_html.unwrap_or(false);
-        let max_content_length = args.max_content_length.unwrap_or(5000) as usize;
-        let timeout_seconds = args.timeout_seconds.unwrap_or(30);
+        let max_content_length = args
+            .max_content_length
+            .unwrap_or(5000)
+            .min(self.config.max_content_length) as usize;
+        let timeout_seconds = args
+            .timeout_seconds
+            .unwrap_or(30)
+            .min(self.config.max_timeout_seconds);
 
         // Send progress update
         if let Some(ref manager) = context.conversation_manager {
@@ -8,6 +8,37 @@ pub struct WebSearch {
     pub description: String,
     pub category: String,
     pub parameters: Vec<ToolParameter>,
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub config: WebSearchConfig,
+}
+
+/// Search engine queried by `web_search`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchProvider {
+    #[serde(rename = "duckduckgo")]
+    DuckDuckGo,
+}
+
+/// Provider and limits applied to every search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSearchConfig {
+    pub provider: SearchProvider,
+    /// Most results returned, whatever the caller asks for
+    pub max_results: u32,
+    /// Seconds before a search is abandoned
+    pub timeout_seconds: u64,
+}
+
+impl Default for WebSearchConfig {
+    fn default() -> Self {
+        Self {
+            provider: SearchProvider::DuckDuckGo,
+            max_results: 20,
+            timeout_seconds: 15,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +55,8 @@ impl WebSearch {
             name: "Web Search".to_string(),
             description: "Search the web for information and return relevant results".to_string(),
             category: "web".to_string(),
+            capabilities: vec!["web_search".to_string()],
+            config: WebSearchConfig::default(),
             parameters: vec![
                 ToolParameter {
                     name: "query".to_string(),
@@ -76,6 +109,45 @@ impl Tool for WebSearch {
         &self.parameters
     }
 
+    fn capabilities(&self) -> &[String] {
+        &self.capabilities
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "provider": {
+                    "type": "string",
+                    "enum": ["duckduckgo"],
+                    "description": "Search engine to query"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": 50,
+                    "description": "Most results returned"
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Seconds before a search is abandoned"
+                }
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn config(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(&self.config).ok()
+    }
+
+    fn configure(&mut self, config: &serde_json::Value) -> crate::core::Result<()> {
+        self.config = serde_json::from_value(config.clone())
+            .map_err(|e| crate::core::AppError::Tool(format!("Invalid configuration: {}", e)))?;
+        Ok(())
+    }
+
     async fn execute(
         &self,
         args: &serde_json::Value,
@@ -90,7 +162,7 @@ impl Tool for WebSearch {
             ));
         }
 
-        let max_results = args.max_results.unwrap_or(10).min(20); // Cap at 20
+        let max_results = args.max_results.unwrap_or(10).min(self.config.max_results);
         let include_content = args.include_content.unwrap_or(false);
 
         // Send progress update
@@ -108,7 +180,9 @@ impl Tool for WebSearch {
         // Execute real web search using websearch crate
         let start = std::time::Instant::now();
 
-        let provider = websearch::providers::DuckDuckGoProvider::new();
+        let provider = match self.config.provider {
+            SearchProvider::DuckDuckGo => websearch::providers::DuckDuckGoProvider::new(),
+        };
         let options = websearch::SearchOptions {
             query: args.query.clone(),
             max_results: Some(max_results),
@@ -117,7 +191,7 @@ impl Tool for WebSearch {
         };
 
         // Execute search with timeout
-        let timeout_duration = std::time::Duration::from_secs(15);
+        let timeout_duration = std::time::Duration::from_secs(self.config.timeout_seconds);
         let search_result =
             tokio::time::timeout(timeout_duration, websearch::web_search(options)).await;
 
@@ -166,7 +240,10 @@ impl Tool for WebSearch {
             },
             Err(_) => ToolResult {
                 success: false,
-                message: "Search timed out after 15 seconds".to_string(),
+                message: format!(
+                    "Search timed out after {} seconds",
+                    self.config.timeout_seconds
+                ),
                 data: Some(serde_json::json!({
                     "status": "timeout",
                     "query": args.query,
@@ -0,0 +1,347 @@
+//! Authentication for the relay connection
+//!
+//! The desktop presents a bearer token when it connects, can pin the relay's TLS
+//! certificate, and only executes commands that carry a known viewer token.
+//! Without configured viewer tokens every command is refused, unless anonymous
+//! viewers are explicitly allowed.
+
+use crate::core::{AppError, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, HeaderValue};
+use tokio_tungstenite::Connector;
+
+/// Bearer token presented to the relay
+pub const RELAY_TOKEN_ENV: &str = "CF_AI_RELAY_TOKEN";
+/// Comma-separated tokens accepted on incoming commands
+pub const VIEWER_TOKENS_ENV: &str = "CF_AI_VIEWER_TOKENS";
+/// Comma-separated SHA-256 fingerprints of accepted relay certificates
+pub const PINNED_CERTS_ENV: &str = "CF_AI_RELAY_CERT_SHA256";
+/// Set to `1` or `true` to run commands from viewers that send no token
+pub const ALLOW_ANONYMOUS_ENV: &str = "CF_AI_ALLOW_ANONYMOUS_VIEWERS";
+
+/// Field of an incoming message that carries the viewer's token
+pub const VIEWER_TOKEN_FIELD: &str = "viewer_token";
+
+/// Credentials and TLS settings for the relay connection
+#[derive(Debug, Clone, Default)]
+pub struct RelayAuth {
+    /// Bearer token sent in the `Authorization` header on connect
+    pub token: Option<String>,
+    /// Tokens accepted on incoming commands
+    pub viewer_tokens: Vec<String>,
+    /// Accept every command when no viewer tokens are configured
+    pub allow_anonymous_viewers: bool,
+    /// Lowercase hex SHA-256 fingerprints of accepted leaf certificates;
+    /// empty uses normal CA validation
+    pub pinned_certs: Vec<String>,
+}
+
+impl RelayAuth {
+    /// Read settings from the environment
+    pub fn from_env() -> Result<Self> {
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        Ok(Self {
+            token: std::env::var(RELAY_TOKEN_ENV)
+                .ok()
+                .filter(|t| !t.trim().is_empty()),
+            viewer_tokens: list(VIEWER_TOKENS_ENV),
+            allow_anonymous_viewers: matches!(
+                std::env::var(ALLOW_ANONYMOUS_ENV)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+                    .as_str(),
+                "1" | "true" | "yes"
+            ),
+            pinned_certs: list(PINNED_CERTS_ENV)
+                .iter()
+                .map(|pin| parse_fingerprint(pin))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Whether incoming commands are checked for a viewer token
+    pub fn requires_viewer_token(&self) -> bool {
+        !self.viewer_tokens.is_empty() || !self.allow_anonymous_viewers
+    }
+
+    /// Refuse to serve viewers when no token could ever be accepted
+    pub fn check_viewer_tokens(&self) -> Result<()> {
+        if self.viewer_tokens.is_empty() && !self.allow_anonymous_viewers {
+            return Err(AppError::Configuration(format!(
+                "No viewer tokens configured; set {} or pass --allow-anonymous-viewers",
+                VIEWER_TOKENS_ENV
+            )));
+        }
+        Ok(())
+    }
+
+    /// Build the connection request, adding the bearer token if configured
+    pub fn client_request(&self, url: &str) -> Result<Request> {
+        let mut request = url
+            .into_client_request()
+            .map_err(|e| AppError::Configuration(format!("Invalid relay URL {}: {}", url, e)))?;
+
+        if let Some(token) = &self.token {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|_| {
+                AppError::Configuration("Relay token contains invalid characters".to_string())
+            })?;
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        Ok(request)
+    }
+
+    /// TLS connector that enforces the pinned certificates, if any are configured
+    pub fn connector(&self) -> Result<Option<Connector>> {
+        if self.pinned_certs.is_empty() {
+            return Ok(None);
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = PinnedCertVerifier {
+            pins: self.pinned_certs.clone(),
+            provider: provider.clone(),
+        };
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| AppError::Configuration(format!("TLS setup failed: {}", e)))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(Some(Connector::Rustls(Arc::new(config))))
+    }
+
+    /// Whether `token` is one of the configured viewer tokens
+    pub fn accepts_viewer_token(&self, token: &str) -> bool {
+        self.viewer_tokens
+            .iter()
+            .any(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+    }
+
+    /// Check and strip the viewer token of an incoming message
+    pub fn verify_viewer(&self, message: &mut Value) -> Result<()> {
+        let token = message
+            .as_object_mut()
+            .and_then(|fields| fields.remove(VIEWER_TOKEN_FIELD));
+
+        if !self.requires_viewer_token() {
+            return Ok(());
+        }
+
+        if self.viewer_tokens.is_empty() {
+            return Err(AppError::WebSocket(
+                "Unauthorized: no viewer tokens configured".to_string(),
+            ));
+        }
+
+        let token = token
+            .as_ref()
+            .and_then(Value::as_str)
+            .ok_or_else(|| AppError::WebSocket("Unauthorized: missing viewer token".to_string()))?;
+
+        if self.accepts_viewer_token(token) {
+            Ok(())
+        } else {
+            Err(AppError::WebSocket(
+                "Unauthorized: invalid viewer token".to_string(),
+            ))
+        }
+    }
+}
+
+/// Normalize a SHA-256 fingerprint given as hex, optionally colon-separated
+pub fn parse_fingerprint(pin: &str) -> Result<String> {
+    let hex: String = pin
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::Configuration(format!(
+            "Invalid certificate fingerprint '{}': expected 64 hex characters",
+            pin
+        )));
+    }
+    Ok(hex)
+}
+
+/// Lowercase hex SHA-256 of a DER-encoded certificate
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Accepts only leaf certificates whose fingerprint is pinned
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pins: Vec<String>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let actual = fingerprint(end_entity.as_ref());
+        if self.pins.iter().any(|pin| pin == &actual) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "relay certificate {} does not match any pinned fingerprint",
+                actual
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn with_viewers(tokens: &[&str]) -> RelayAuth {
+        RelayAuth {
+            viewer_tokens: tokens.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_viewer_token_checked_and_stripped() {
+        let auth = with_viewers(&["secret-a", "secret-b"]);
+
+        let mut msg = json!({"type": "get_presets", "viewer_token": "secret-b"});
+        auth.verify_viewer(&mut msg).unwrap();
+        assert!(msg.get(VIEWER_TOKEN_FIELD).is_none());
+
+        let mut wrong = json!({"type": "get_presets", "viewer_token": "secret-c"});
+        assert!(auth.verify_viewer(&mut wrong).is_err());
+
+        let mut missing = json!({"type": "get_presets"});
+        assert!(auth.verify_viewer(&mut missing).is_err());
+    }
+
+    #[test]
+    fn test_no_viewer_tokens_refuses_everything() {
+        let auth = RelayAuth::default();
+        assert!(auth.check_viewer_tokens().is_err());
+        let mut msg = json!({"type": "get_presets", "viewer_token": "anything"});
+        assert!(auth.verify_viewer(&mut msg).is_err());
+    }
+
+    #[test]
+    fn test_anonymous_viewers_only_when_allowed() {
+        let auth = RelayAuth {
+            allow_anonymous_viewers: true,
+            ..Default::default()
+        };
+        auth.check_viewer_tokens().unwrap();
+        let mut msg = json!({"type": "get_presets", "viewer_token": "anything"});
+        auth.verify_viewer(&mut msg).unwrap();
+        assert!(msg.get(VIEWER_TOKEN_FIELD).is_none());
+    }
+
+    #[test]
+    fn test_bearer_header_added() {
+        let auth = RelayAuth {
+            token: Some("abc123".to_string()),
+            ..Default::default()
+        };
+        let request = auth
+            .client_request("wss://relay.example.com/connect")
+            .unwrap();
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer abc123");
+
+        let anonymous = RelayAuth::default()
+            .client_request("ws://localhost:8787/connect")
+            .unwrap();
+        assert!(anonymous.headers().get(AUTHORIZATION).is_none());
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        assert_eq!(
+            fingerprint(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let colons = "BA:78:16:BF:8F:01:CF:EA:41:41:40:DE:5D:AE:22:23:\
+                      B0:03:61:A3:96:17:7A:9C:B4:10:FF:61:F2:00:15:AD";
+        assert_eq!(parse_fingerprint(colons).unwrap(), fingerprint(b"abc"));
+        assert!(parse_fingerprint("abcd").is_err());
+    }
+
+    #[test]
+    fn test_connector_only_when_pinned() {
+        assert!(RelayAuth::default().connector().unwrap().is_none());
+
+        let pinned = RelayAuth {
+            pinned_certs: vec![fingerprint(b"cert")],
+            ..Default::default()
+        };
+        assert!(pinned.connector().unwrap().is_some());
+    }
+}
@@ -1,21 +1,90 @@
-use crate::agents::conversation::{ConversationManager, ProgressType};
-use crate::core::{Agent, AgentContext, ExecutionStep, ToolContext};
-use crate::registry::{CentralRegistry, Registry as RegistryTrait};
-use crate::websocket::protocol::{
-    AgentConfig, IncomingMessage, OutgoingMessage, PresetAgent, PresetMetadata, ToolDefinition,
-    ToolReference,
-};
-use futures::{SinkExt, StreamExt};
+use crate::registry::CentralRegistry;
+use crate::websocket::auth::RelayAuth;
+use crate::websocket::handler::{Handshakes, MessageHandler, Outbox};
+use crate::websocket::protocol::{OutgoingEnvelope, OutgoingMessage};
+use futures::{Sink, SinkExt, StreamExt};
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{
+    connect_async_tls_with_config, tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream,
+};
 use url::Url;
 
+type RelayStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Reconnection and heartbeat settings for the relay connection
+#[derive(Debug, Clone)]
+pub struct ReconnectConfig {
+    /// Delay before the first reconnection attempt
+    pub initial_delay: Duration,
+    /// Upper bound for the backoff delay
+    pub max_delay: Duration,
+    /// Factor applied to the delay after each failed attempt
+    pub multiplier: f64,
+    /// Give up after this many consecutive failed attempts; `None` retries forever
+    pub max_attempts: Option<u32>,
+    /// How often a ping is sent while connected
+    pub heartbeat_interval: Duration,
+    /// Connection is considered dead when nothing arrives for this long
+    pub heartbeat_timeout: Duration,
+    /// Maximum number of step messages kept while disconnected
+    pub max_buffered_steps: usize,
+    /// A connection must stay up this long before the backoff starts over
+    pub min_stable_uptime: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            max_attempts: None,
+            heartbeat_interval: Duration::from_secs(20),
+            heartbeat_timeout: Duration::from_secs(45),
+            max_buffered_steps: 500,
+            min_stable_uptime: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Delay before reconnecting after `failures` consecutive failed attempts
+    pub fn backoff_delay(&self, failures: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(failures.min(32) as i32);
+        let delay = self.initial_delay.as_secs_f64() * factor;
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    /// Consecutive failures after a connection that stayed up for `uptime`
+    ///
+    /// A relay that accepts and then drops connections right away counts as
+    /// failing, so it doesn't get hammered at the initial delay.
+    pub fn failures_after_connection(&self, failures: u32, uptime: Duration) -> u32 {
+        if uptime >= self.min_stable_uptime {
+            0
+        } else {
+            failures.saturating_add(1)
+        }
+    }
+}
+
+/// How a relay connection ended
+enum ConnectionEnd {
+    Closed,
+    Lost(String),
+}
+
 /// Client that connects to the Cloudflare Worker Relay
 pub struct WebSocketRelayClient {
     url: String,
-    registry: Arc<CentralRegistry>,
-    llm: Arc<dyn crate::core::LLMClient>,
+    handler: MessageHandler,
+    reconnect: ReconnectConfig,
 }
 
 impl WebSocketRelayClient {
@@ -24,271 +93,316 @@ impl WebSocketRelayClient {
         registry: Arc<CentralRegistry>,
         llm: Arc<dyn crate::core::LLMClient>,
     ) -> Self {
-        Self { url, registry, llm }
+        Self {
+            url,
+            handler: MessageHandler::new(registry, llm),
+            reconnect: ReconnectConfig::default(),
+        }
     }
 
-    /// Connect and run the main event loop
-    pub async fn run(&self) -> crate::core::Result<()> {
-        println!("Connecting to relay at {}...", self.url);
+    /// Override the reconnection and heartbeat settings
+    pub fn with_reconnect_config(mut self, reconnect: ReconnectConfig) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
 
-        let (ws_stream, _) = connect_async(&self.url).await.map_err(|e| {
-            crate::core::AppError::Network(format!("WebSocket connection failed: {}", e))
-        })?;
+    /// Set the credentials and TLS pinning used for the relay connection
+    pub fn with_auth(mut self, auth: RelayAuth) -> Self {
+        self.handler = self.handler.with_auth(auth);
+        self
+    }
 
-        println!("Connected to WebSocket relay.");
+    /// Connect and run the main event loop, reconnecting whenever the connection drops
+    pub async fn run(&self) -> crate::core::Result<()> {
+        // The channel outlives individual connections so handlers keep streaming while offline
+        let (tx, mut rx) = mpsc::unbounded_channel::<OutgoingEnvelope>();
+        self.handler.forward_registry_events(tx.clone());
+        let mut backlog = VecDeque::new();
+        let mut failures: u32 = 0;
 
-        let (mut write, mut read) = ws_stream.split();
-        let (tx, mut rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+        if self.handler.auth().token.is_some()
+            && self.url.starts_with("ws://")
+            && !is_loopback(&self.url)
+        {
+            eprintln!(
+                "Warning: sending the relay token over an unencrypted connection; use wss://"
+            );
+        }
 
-        // Spawn writer task
-        let write_handle = tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                match serde_json::to_string(&msg) {
-                    Ok(text) => {
-                        if let Err(e) = write.send(Message::Text(text)).await {
-                            eprintln!("Failed to send message: {}", e);
-                            break;
+        loop {
+            println!("Connecting to relay at {}...", self.url);
+
+            let request = self.handler.auth().client_request(&self.url)?;
+            let connector = self.handler.auth().connector()?;
+            match connect_async_tls_with_config(request, None, false, connector).await {
+                Ok((ws_stream, _)) => {
+                    println!("Connected to WebSocket relay.");
+                    let connected_at = Instant::now();
+                    let end = self.serve(ws_stream, &tx, &mut rx, &mut backlog).await;
+                    failures = self
+                        .reconnect
+                        .failures_after_connection(failures, connected_at.elapsed());
+                    match end {
+                        ConnectionEnd::Closed => println!("WebSocket connection closed."),
+                        ConnectionEnd::Lost(reason) => {
+                            eprintln!("WebSocket connection lost: {}", reason)
                         }
                     }
-                    Err(e) => eprintln!("Failed to serialize message: {}", e),
+                }
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("WebSocket connection failed: {}", e);
+                    if self
+                        .reconnect
+                        .max_attempts
+                        .is_some_and(|max| failures >= max)
+                    {
+                        return Err(crate::core::AppError::Network(format!(
+                            "WebSocket connection failed after {} attempt(s): {}",
+                            failures, e
+                        )));
+                    }
                 }
             }
-        });
 
-        // Main read loop
-        let registry = self.registry.clone();
-        let llm = self.llm.clone();
-        let tx_clone = tx.clone(); // Keep for cloning into handlers
+            let delay = self.reconnect.backoff_delay(failures);
+            println!("Reconnecting in {:.1}s...", delay.as_secs_f64());
+            self.buffer_until(Instant::now() + delay, &mut rx, &mut backlog)
+                .await;
+        }
+    }
+
+    /// Run a single connection until it closes or stops answering heartbeats
+    async fn serve(
+        &self,
+        ws_stream: RelayStream,
+        tx: &Outbox,
+        rx: &mut mpsc::UnboundedReceiver<OutgoingEnvelope>,
+        backlog: &mut VecDeque<OutgoingEnvelope>,
+    ) -> ConnectionEnd {
+        let (mut write, mut read) = ws_stream.split();
+
+        // Viewers answer the status with a fresh hello, and resync before
+        // anything sent while offline is replayed
+        let status = OutgoingMessage::Status {
+            running_executions: self.handler.running.snapshot().await,
+        }
+        .into();
+        if let Err(e) = send_message(&mut write, &status).await {
+            return ConnectionEnd::Lost(e);
+        }
+        let handshakes = Handshakes::default();
+        if !backlog.is_empty() {
+            println!("Sending {} buffered message(s).", backlog.len());
+        }
+        while let Some(msg) = backlog.pop_front() {
+            if let Err(e) = send_message(&mut write, &msg).await {
+                backlog.push_front(msg);
+                return ConnectionEnd::Lost(e);
+            }
+        }
 
-        while let Some(msg_result) = read.next().await {
-            match msg_result {
-                Ok(Message::Text(text)) => {
-                    println!("DEBUG: Received RAW WebSocket message: {}", text);
-                    match serde_json::from_str::<IncomingMessage>(&text) {
-                        Ok(msg) => {
-                            let tx = tx_clone.clone();
-                            let registry = registry.clone();
-                            let llm = llm.clone();
+        let mut heartbeat = tokio::time::interval_at(
+            Instant::now() + self.reconnect.heartbeat_interval,
+            self.reconnect.heartbeat_interval,
+        );
+        let mut last_seen = Instant::now();
 
-                            tokio::spawn(async move {
-                                if let Err(e) = Self::handle_message(msg, tx, registry, llm).await {
-                                    eprintln!("Error handling message: {}", e);
-                                }
-                            });
+        loop {
+            tokio::select! {
+                incoming = read.next() => {
+                    last_seen = Instant::now();
+                    match incoming {
+                        Some(Ok(Message::Text(text))) => {
+                            self.handler.dispatch(&text, tx, &handshakes)
                         }
-                        Err(e) => eprintln!(
-                            "Failed to parse incoming message: {}\nRaw text: {}",
-                            e, text
-                        ),
+                        Some(Ok(Message::Close(_))) | None => return ConnectionEnd::Closed,
+                        Some(Err(e)) => return ConnectionEnd::Lost(e.to_string()),
+                        _ => {} // Pongs only refresh last_seen
                     }
                 }
-                Ok(Message::Close(_)) => {
-                    println!("WebSocket connection closed.");
-                    break;
+                outgoing = rx.recv() => {
+                    // run() holds a sender, so the channel never closes while connected
+                    let Some(msg) = outgoing else {
+                        return ConnectionEnd::Closed;
+                    };
+                    if let Err(e) = send_message(&mut write, &msg).await {
+                        backlog.push_back(msg);
+                        return ConnectionEnd::Lost(e);
+                    }
                 }
-                Err(e) => {
-                    eprintln!("WebSocket stream error: {}", e);
-                    break;
+                _ = heartbeat.tick() => {
+                    if last_seen.elapsed() > self.reconnect.heartbeat_timeout {
+                        return ConnectionEnd::Lost(format!(
+                            "no data received for {}s",
+                            last_seen.elapsed().as_secs()
+                        ));
+                    }
+                    if let Err(e) = write.send(Message::Ping(Vec::new())).await {
+                        return ConnectionEnd::Lost(e.to_string());
+                    }
                 }
-                _ => {} // Ignore
             }
         }
-
-        // Ensure writer closes
-        drop(tx_clone);
-        let _ = write_handle.await;
-
-        Ok(())
     }
 
-    async fn handle_message(
-        msg: IncomingMessage,
-        tx: mpsc::UnboundedSender<OutgoingMessage>,
-        registry: Arc<CentralRegistry>,
-        llm: Arc<dyn crate::core::LLMClient>,
-    ) -> crate::core::Result<()> {
-        match msg {
-            IncomingMessage::ChatRequest {
-                message,
-                agent: agent_config,
-            } => {
-                // Not using agent_config fully yet, ensuring we get the conversational agent
-                let agent = registry
-                    .agents
-                    .get("conversational-agent")
-                    .await
-                    .map_err(|e| crate::core::AppError::Registry(e.to_string()))?
-                    .ok_or(crate::core::AppError::Registry(
-                        "Default agent not found".to_string(),
-                    ))?;
-
-                // Create manager for streaming updates
-                let manager: Arc<dyn ConversationManager> =
-                    Arc::new(WebSocketConversationManager { tx: tx.clone() });
-
-                let mut context = AgentContext::new("conversational-agent".to_string());
-                context.messages.push(crate::core::ConversationMessage {
-                    role: "user".to_string(),
-                    content: message.clone(),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                });
-
-                let mut tools = Vec::new();
-                for tool_name in agent_config.tools {
-                    if let Some(tool) = registry.tools.get(&tool_name).await? {
-                        tools.push(tool);
+    /// Collect outgoing messages into the backlog until `deadline`
+    async fn buffer_until(
+        &self,
+        deadline: Instant,
+        rx: &mut mpsc::UnboundedReceiver<OutgoingEnvelope>,
+        backlog: &mut VecDeque<OutgoingEnvelope>,
+    ) {
+        let sleep = tokio::time::sleep_until(deadline);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return,
+                Some(msg) = rx.recv() => {
+                    backlog.push_back(msg);
+                    let dropped = trim_backlog(backlog, self.reconnect.max_buffered_steps);
+                    if dropped > 0 {
+                        eprintln!("Dropped {} buffered step message(s) while offline", dropped);
                     }
                 }
-
-                let result = agent
-                    .execute(&message, &context, llm.as_ref(), Some(manager), &tools)
-                    .await?;
-
-                // Steps are already sent incrementally by the agent via send_thinking_update
-                // Send final response only
-                let _ = tx.send(OutgoingMessage::ChatResponse {
-                    content: result.response,
-                });
             }
-            IncomingMessage::GetPresets | IncomingMessage::ResetPresets => {
-                // Collect Tools
-                let mut tools_def = Vec::new();
-                // list() returns Vec<Box<dyn Tool>>, so iterate directly
-                for tool in registry.tools.list().await.unwrap_or_default() {
-                    tools_def.push(crate::websocket::protocol::ToolDefinition {
-                        id: tool.id().to_string(),
-                        name: tool.name().to_string(),
-                        description: tool.description().to_string(),
-                        category: "utility".to_string(), // TODO: add category to Tool trait
-                        parameters: tool.parameters().to_vec(),
-                        returns_observation: true,
-                    });
-                }
+        }
+    }
+}
 
-                // Get default agent presets from presets module
-                let preset_agents = crate::registry::presets::get_default_presets();
+/// Serialize and send one message; serialization failures are logged and skipped
+pub(crate) async fn send_message<S>(write: &mut S, msg: &OutgoingEnvelope) -> Result<(), String>
+where
+    S: Sink<Message, Error = WsError> + Unpin,
+{
+    let text = match serde_json::to_string(msg) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to serialize message: {}", e);
+            return Ok(());
+        }
+    };
+    write
+        .send(Message::Text(text))
+        .await
+        .map_err(|e| format!("Failed to send message: {}", e))
+}
 
-                // Convert to protocol types
-                let agents_def: Vec<crate::websocket::protocol::PresetAgent> = preset_agents
-                    .into_iter()
-                    .map(|preset| crate::websocket::protocol::PresetAgent {
-                        id: preset.id,
-                        name: preset.name,
-                        purpose: preset.purpose,
-                        system_prompt: preset.system_prompt,
-                        tools: preset
-                            .tools
-                            .into_iter()
-                            .map(|tr| crate::websocket::protocol::ToolReference {
-                                tool_id: tr.tool_id,
-                                enabled: tr.enabled,
-                            })
-                            .collect(),
-                        model_id: preset.model_id,
-                        max_iterations: preset.max_iterations,
-                        metadata: crate::websocket::protocol::PresetMetadata {
-                            created_at: preset.metadata.created_at,
-                            updated_at: preset.metadata.updated_at,
-                            version: preset.metadata.version,
-                            author: preset.metadata.author,
-                        },
-                    })
-                    .collect();
+/// Whether the URL points at this machine
+fn is_loopback(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| {
+            u.host_str()
+                .map(|h| h == "localhost" || h == "127.0.0.1" || h == "[::1]")
+        })
+        .unwrap_or(false)
+}
 
-                let _ = tx.send(OutgoingMessage::PresetsList {
-                    agents: agents_def,
-                    prompts: vec![],
-                    tools: tools_def,
-                });
-            }
-            IncomingMessage::GetPrompts => {
-                // TODO: Implement prompts if needed
-                let _ = tx.send(OutgoingMessage::PresetsList {
-                    agents: vec![],
-                    prompts: vec![],
-                    tools: vec![],
-                });
-            }
+/// Drop the oldest step messages beyond `max_steps`; other messages are always kept
+fn trim_backlog(backlog: &mut VecDeque<OutgoingEnvelope>, max_steps: usize) -> usize {
+    let steps = backlog
+        .iter()
+        .filter(|msg| matches!(msg.message, OutgoingMessage::ExecutionStep { .. }))
+        .count();
+    let dropped = steps.saturating_sub(max_steps);
+    let mut remaining = dropped;
+    backlog.retain(|msg| {
+        if remaining > 0 && matches!(msg.message, OutgoingMessage::ExecutionStep { .. }) {
+            remaining -= 1;
+            false
+        } else {
+            true
         }
-        Ok(())
-    }
+    });
+    dropped
 }
 
-#[derive(Debug)]
-pub struct WebSocketConversationManager {
-    tx: mpsc::UnboundedSender<OutgoingMessage>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ExecutionStep;
 
-#[async_trait::async_trait]
-impl ConversationManager for WebSocketConversationManager {
-    async fn send_thinking_update(
-        &self,
-        _agent_id: &str,
-        step_number: usize,
-        thought: &str,
-    ) -> crate::core::Result<()> {
-        let step = ExecutionStep {
-            step_number,
-            step_type: crate::core::StepType::Thinking,
-            content: thought.to_string(),
-            tool_call: None,
-            tool_observation: None,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
-        let _ = self.tx.send(OutgoingMessage::ExecutionStep { step });
-        Ok(())
+    fn step(n: usize) -> OutgoingEnvelope {
+        OutgoingMessage::ExecutionStep {
+            step: ExecutionStep {
+                step_number: n,
+                step_type: crate::core::StepType::Thinking,
+                content: format!("step {}", n),
+                tool_call: None,
+                tool_observation: None,
+                timestamp: String::new(),
+            },
+        }
+        .into()
     }
 
-    async fn send_progress_update(
-        &self,
-        _agent_id: &str,
-        progress_type: ProgressType,
-        message: &str,
-        _progress: Option<f32>,
-    ) -> crate::core::Result<()> {
-        let step_type = match progress_type {
-            ProgressType::Thinking => crate::core::StepType::Thinking,
-            ProgressType::Planning => crate::core::StepType::Planning,
-            ProgressType::Executing => crate::core::StepType::Action,
-            ProgressType::Observing => crate::core::StepType::Observation,
-            ProgressType::Reflecting => crate::core::StepType::Reflection,
-            ProgressType::Completing => crate::core::StepType::Completion,
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let config = ReconnectConfig {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            ..Default::default()
         };
+        assert_eq!(config.backoff_delay(0), Duration::from_millis(500));
+        assert_eq!(config.backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(config.backoff_delay(3), Duration::from_secs(4));
+        assert_eq!(config.backoff_delay(10), Duration::from_secs(10));
+        assert_eq!(config.backoff_delay(u32::MAX), Duration::from_secs(10));
+    }
 
-        let step = ExecutionStep {
-            step_number: 0,
-            step_type,
-            content: message.to_string(),
-            tool_call: None,
-            tool_observation: None,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        };
+    #[test]
+    fn test_short_connections_keep_backing_off() {
+        let config = ReconnectConfig::default();
+        assert_eq!(
+            config.failures_after_connection(3, Duration::from_millis(200)),
+            4
+        );
+        assert_eq!(
+            config.failures_after_connection(3, config.min_stable_uptime),
+            0
+        );
+    }
 
-        let _ = self.tx.send(OutgoingMessage::ExecutionStep { step });
-        Ok(())
+    #[test]
+    fn test_trim_backlog_keeps_newest_steps_and_responses() {
+        let mut backlog: VecDeque<_> = (1..=5).map(step).collect();
+        backlog.push_back(
+            OutgoingMessage::ChatResponse {
+                content: "done".to_string(),
+                usage: None,
+            }
+            .into(),
+        );
+
+        assert_eq!(trim_backlog(&mut backlog, 2), 3);
+        assert_eq!(backlog.len(), 3);
+        assert!(matches!(
+            &backlog[0].message,
+            OutgoingMessage::ExecutionStep { step } if step.step_number == 4
+        ));
+        assert!(matches!(
+            backlog[2].message,
+            OutgoingMessage::ChatResponse { .. }
+        ));
+        assert_eq!(trim_backlog(&mut backlog, 2), 0);
     }
 
-    async fn send_error_update(
-        &self,
-        _agent_id: &str,
-        error: &str,
-        _recovery_suggestions: Vec<String>,
-    ) -> crate::core::Result<()> {
-        let _ = self.tx.send(OutgoingMessage::Error {
-            error: error.to_string(),
-        });
-        Ok(())
+    #[test]
+    fn test_is_loopback() {
+        assert!(is_loopback("ws://localhost:8787/connect?device=desktop"));
+        assert!(is_loopback("ws://127.0.0.1:8787/connect"));
+        assert!(!is_loopback("ws://relay.example.com/connect"));
     }
 
-    async fn send_completion_update(
-        &self,
-        _agent_id: &str,
-        final_response: &str,
-        _success: bool,
-    ) -> crate::core::Result<()> {
-        let _ = self.tx.send(OutgoingMessage::ChatResponse {
-            content: final_response.to_string(),
-        });
-        Ok(())
+    #[test]
+    fn test_status_message_shape() {
+        let msg = OutgoingMessage::Status {
+            running_executions: vec![],
+        };
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["type"], "status");
+        assert!(json["running_executions"].as_array().unwrap().is_empty());
     }
 }
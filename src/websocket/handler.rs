@@ -0,0 +1,878 @@
+//! Handling of protocol messages, shared by the relay client and the local server
+
+use crate::agents::conversation::{ConversationManager, ProgressType};
+use crate::agents::registry::Availability;
+use crate::core::{AgentContext, AgentResult, ConversationMessage, ExecutionStep, SamplingParams};
+use crate::llm::usage::UsageSummary;
+use crate::registry::CentralRegistry;
+use crate::websocket::auth::RelayAuth;
+use crate::websocket::protocol::{
+    IncomingEnvelope, IncomingMessage, OutgoingEnvelope, OutgoingMessage, RunningExecution,
+    ToolSettingsEntry, UsageReport, VIEWER_ID_FIELD,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+
+/// Session ID used when a chat request doesn't name one
+pub const DEFAULT_SESSION_ID: &str = "default";
+
+/// Agent that answers chat requests that don't name one
+pub const DEFAULT_AGENT_ID: &str = "conversational-agent";
+
+/// Sessions whose usage totals are kept in memory
+pub const DEFAULT_SESSIONS_RETAINED: usize = 200;
+
+/// Running usage totals keyed by session ID, least recently used first
+#[derive(Debug, Default)]
+struct SessionUsage {
+    totals: HashMap<String, UsageSummary>,
+    order: VecDeque<String>,
+}
+
+impl SessionUsage {
+    /// Add `usage` to a session's total, dropping the least recently used sessions beyond `max`
+    fn record(&mut self, session_id: &str, usage: &UsageSummary, max: usize) -> UsageSummary {
+        self.order.retain(|id| id != session_id);
+        self.order.push_back(session_id.to_string());
+        let total = self.totals.entry(session_id.to_string()).or_default();
+        total.merge(usage);
+        let total = total.clone();
+
+        while self.order.len() > max {
+            if let Some(oldest) = self.order.pop_front() {
+                self.totals.remove(&oldest);
+            }
+        }
+        total
+    }
+}
+
+/// Sender for messages going out to connected viewers
+pub(crate) type Outbox = mpsc::UnboundedSender<OutgoingEnvelope>;
+
+/// Result of a viewer's hello on one connection, keyed by the `viewer_id` the
+/// relay stamps on messages (empty for direct connections)
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Handshakes(Arc<Mutex<HashMap<String, bool>>>);
+
+impl Handshakes {
+    fn record(&self, viewer: &str, compatible: bool) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(viewer.to_string(), compatible);
+    }
+
+    fn is_compatible(&self, viewer: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(viewer)
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Executions in progress, reported to viewers after a reconnect
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RunningExecutions {
+    next_id: Arc<AtomicU64>,
+    executions: Arc<tokio::sync::Mutex<HashMap<String, RunningExecution>>>,
+}
+
+impl RunningExecutions {
+    async fn start(
+        &self,
+        request_id: Option<&str>,
+        agent_id: &str,
+        session_id: &str,
+        message: &str,
+    ) -> String {
+        let execution_id = format!("exec-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        self.executions.lock().await.insert(
+            execution_id.clone(),
+            RunningExecution {
+                execution_id: execution_id.clone(),
+                request_id: request_id.map(str::to_string),
+                agent_id: agent_id.to_string(),
+                session_id: session_id.to_string(),
+                message: message.to_string(),
+                started_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+        execution_id
+    }
+
+    async fn finish(&self, execution_id: &str) {
+        self.executions.lock().await.remove(execution_id);
+    }
+
+    pub(crate) async fn snapshot(&self) -> Vec<RunningExecution> {
+        let mut running: Vec<_> = self.executions.lock().await.values().cloned().collect();
+        running.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        running
+    }
+}
+
+/// An agent run requested by a viewer or API caller
+#[derive(Debug, Clone)]
+pub struct ChatExecution {
+    pub agent_id: String,
+    pub message: String,
+    /// IDs of the tools the agent may use
+    pub tools: Vec<String>,
+    /// Sampling overrides for this run
+    pub sampling: Option<SamplingParams>,
+    pub bypass_cache: bool,
+    pub session_id: String,
+    /// Earlier turns of the conversation, oldest first
+    pub history: Vec<ConversationMessage>,
+}
+
+/// Executes incoming protocol messages against the registry and LLM
+#[derive(Clone)]
+pub struct MessageHandler {
+    registry: Arc<CentralRegistry>,
+    llm: Arc<dyn crate::core::LLMClient>,
+    session_usage: Arc<tokio::sync::Mutex<SessionUsage>>,
+    max_sessions: usize,
+    pub(crate) running: RunningExecutions,
+    auth: RelayAuth,
+}
+
+impl MessageHandler {
+    pub fn new(registry: Arc<CentralRegistry>, llm: Arc<dyn crate::core::LLMClient>) -> Self {
+        Self {
+            registry,
+            llm,
+            session_usage: Arc::default(),
+            max_sessions: DEFAULT_SESSIONS_RETAINED,
+            running: RunningExecutions::default(),
+            auth: RelayAuth::default(),
+        }
+    }
+
+    /// Keep usage totals for only the `max_sessions` most recently active sessions
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// Set the viewer tokens accepted on incoming messages
+    pub fn with_auth(mut self, auth: RelayAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Credentials in use, including the relay token and pinned certificates
+    pub fn auth(&self) -> &RelayAuth {
+        &self.auth
+    }
+
+    pub fn registry(&self) -> &Arc<CentralRegistry> {
+        &self.registry
+    }
+
+    /// Running usage totals of a session, if it has run anything
+    pub async fn session_usage(&self, session_id: &str) -> Option<UsageSummary> {
+        self.session_usage
+            .lock()
+            .await
+            .totals
+            .get(session_id)
+            .cloned()
+    }
+
+    /// Run an agent and fold its usage into the session totals
+    pub async fn run_chat(
+        &self,
+        chat: &ChatExecution,
+        manager: Arc<dyn ConversationManager>,
+    ) -> crate::core::Result<(AgentResult, UsageSummary)> {
+        let agent = self
+            .registry
+            .agents
+            .get(&chat.agent_id)
+            .await
+            .map_err(|e| crate::core::AppError::Registry(e.to_string()))?
+            .ok_or_else(|| {
+                crate::core::AppError::Registry(format!("Agent not found: {}", chat.agent_id))
+            })?;
+        if let Some(Availability::Disabled { reason }) =
+            self.registry.agents.availability(&chat.agent_id).await?
+        {
+            return Err(crate::core::AppError::Registry(format!(
+                "Agent '{}' is disabled: {}",
+                chat.agent_id, reason
+            )));
+        }
+
+        let mut context = AgentContext::new(chat.agent_id.clone());
+        context.llm_options.bypass_cache = chat.bypass_cache;
+        if let Some(sampling) = &chat.sampling {
+            sampling.validate()?;
+            context.llm_options.sampling = Some(sampling.clone());
+        }
+        context.messages.extend(chat.history.iter().cloned());
+        context.messages.push(ConversationMessage {
+            role: "user".to_string(),
+            content: chat.message.clone(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+
+        // Disabled tools are kept from every agent, whatever the request names
+        let mut tools = Vec::new();
+        for tool_name in &chat.tools {
+            if self.registry.tools.is_enabled(tool_name).await? != Some(true) {
+                continue;
+            }
+            if let Some(tool) = self.registry.tools.get(tool_name).await? {
+                tools.push(tool);
+            }
+        }
+
+        let result = agent
+            .execute(
+                &chat.message,
+                &context,
+                self.llm.as_ref(),
+                Some(manager),
+                &tools,
+            )
+            .await?;
+
+        let session_total = self.session_usage.lock().await.record(
+            &chat.session_id,
+            &result.usage.total,
+            self.max_sessions,
+        );
+        Ok((result, session_total))
+    }
+
+    /// Enabled flag and configuration of one tool, or of every tool sorted by ID
+    async fn tool_settings(
+        &self,
+        tool_id: Option<&str>,
+    ) -> crate::core::Result<Vec<ToolSettingsEntry>> {
+        let tools = &self.registry.tools;
+        let mut listed = match tool_id {
+            Some(id) => tools.get(id).await?.into_iter().collect(),
+            None => tools.list().await?,
+        };
+        listed.sort_by(|a, b| a.id().cmp(b.id()));
+
+        let mut entries = Vec::new();
+        for tool in listed {
+            entries.push(ToolSettingsEntry {
+                tool_id: tool.id().to_string(),
+                enabled: tools.is_enabled(tool.id()).await? == Some(true),
+                config: tool.config(),
+                config_schema: tool.config_schema(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Send every registry change to viewers until the outbox is dropped
+    pub(crate) fn forward_registry_events(&self, tx: Outbox) -> tokio::task::JoinHandle<()> {
+        let mut events = self.registry.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        eprintln!("Registry events fell behind; dropped {}", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let message = OutgoingMessage::RegistryEvent { event };
+                if tx.send(OutgoingEnvelope::reply(None, message)).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Parse an incoming text frame and handle it in its own task
+    ///
+    /// Viewers get nothing but their hello answered until they complete a
+    /// compatible handshake on this connection.
+    pub(crate) fn dispatch(&self, text: &str, tx: &Outbox, handshakes: &Handshakes) {
+        let mut value = match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!(
+                    "Failed to parse incoming message: {}\nRaw text: {}",
+                    e, text
+                );
+                return;
+            }
+        };
+
+        let request_id = value
+            .get("request_id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string);
+        let reply_error = |error: String| {
+            let _ = tx.send(OutgoingEnvelope::reply(
+                request_id.clone(),
+                OutgoingMessage::Error { error },
+            ));
+        };
+
+        // Nothing runs unless the viewer is authorized
+        if let Err(e) = self.auth.verify_viewer(&mut value) {
+            eprintln!("Rejected incoming message: {}", e);
+            reply_error(e.to_string());
+            return;
+        }
+        let viewer = value
+            .as_object_mut()
+            .and_then(|fields| fields.remove(VIEWER_ID_FIELD))
+            .and_then(|id| id.as_str().map(str::to_string))
+            .unwrap_or_default();
+
+        let IncomingEnvelope {
+            request_id,
+            message: msg,
+        } = match serde_json::from_value::<IncomingEnvelope>(value) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                eprintln!("Failed to parse incoming message: {}", e);
+                reply_error(format!("Invalid message: {}", e));
+                return;
+            }
+        };
+
+        // Answered in order, so requests sent right after a hello see its result
+        if let IncomingMessage::Hello {
+            protocol_version,
+            client_version,
+            features,
+        } = &msg
+        {
+            let reply = hello_reply(*protocol_version, client_version.as_deref(), features);
+            let compatible = matches!(
+                reply,
+                OutgoingMessage::Hello {
+                    compatible: true,
+                    ..
+                }
+            );
+            handshakes.record(&viewer, compatible);
+            let _ = tx.send(OutgoingEnvelope::reply(request_id, reply));
+            return;
+        }
+        if !handshakes.is_compatible(&viewer) {
+            eprintln!("Rejected message from a viewer without a compatible hello");
+            let _ = tx.send(OutgoingEnvelope::reply(
+                request_id,
+                OutgoingMessage::Error {
+                    error:
+                        "Handshake required: send a hello with a supported protocol_version first"
+                            .to_string(),
+                },
+            ));
+            return;
+        }
+
+        let tx = tx.clone();
+        let handler = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handler
+                .handle_message(msg, request_id.clone(), tx.clone())
+                .await
+            {
+                eprintln!("Error handling message: {}", e);
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::Error {
+                        error: e.to_string(),
+                    },
+                ));
+            }
+        });
+    }
+
+    async fn handle_message(
+        &self,
+        msg: IncomingMessage,
+        request_id: Option<String>,
+        tx: Outbox,
+    ) -> crate::core::Result<()> {
+        let registry = &self.registry;
+        let running = &self.running;
+        match msg {
+            IncomingMessage::Hello {
+                protocol_version,
+                client_version,
+                features,
+            } => {
+                let reply = hello_reply(protocol_version, client_version.as_deref(), &features);
+                let _ = tx.send(OutgoingEnvelope::reply(request_id, reply));
+            }
+            IncomingMessage::ChatRequest {
+                message,
+                agent: agent_config,
+                session_id,
+                bypass_cache,
+            } => {
+                // Not using agent_config fully yet, ensuring we get the conversational agent
+                let chat = ChatExecution {
+                    agent_id: DEFAULT_AGENT_ID.to_string(),
+                    message,
+                    tools: agent_config.tools,
+                    sampling: agent_config.sampling,
+                    bypass_cache,
+                    session_id: session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string()),
+                    history: Vec::new(),
+                };
+
+                let execution_id = running
+                    .start(
+                        request_id.as_deref(),
+                        &chat.agent_id,
+                        &chat.session_id,
+                        &chat.message,
+                    )
+                    .await;
+
+                // Create manager for streaming updates tagged with this execution
+                let manager = Arc::new(WebSocketConversationManager {
+                    tx: tx.clone(),
+                    request_id: request_id.clone(),
+                    execution_id: Some(execution_id.clone()),
+                });
+
+                let outcome = self
+                    .run_chat(&chat, manager.clone() as Arc<dyn ConversationManager>)
+                    .await;
+                running.finish(&execution_id).await;
+                let (result, session_total) = outcome?;
+
+                // Steps are already sent incrementally by the agent via send_thinking_update
+                // Send final response only
+                manager.send(OutgoingMessage::ChatResponse {
+                    content: result.response,
+                    usage: Some(UsageReport {
+                        execution: result.usage,
+                        session: session_total,
+                    }),
+                });
+            }
+            IncomingMessage::GetPresets | IncomingMessage::ResetPresets => {
+                // Collect Tools; the viewer's agent editor offers exactly these
+                let mut tools = registry.tools.list().await.unwrap_or_default();
+                tools.sort_by(|a, b| a.id().cmp(b.id()));
+                let mut tools_def = Vec::new();
+                for tool in tools {
+                    if registry.tools.is_enabled(tool.id()).await? != Some(true) {
+                        continue;
+                    }
+                    tools_def.push(crate::websocket::protocol::ToolDefinition {
+                        id: tool.id().to_string(),
+                        name: tool.name().to_string(),
+                        description: tool.description().to_string(),
+                        category: tool.category().to_string(),
+                        parameters: tool.parameters().to_vec(),
+                        returns_observation: true,
+                    });
+                }
+
+                // Agents come from the live registry, so added, removed and
+                // disabled agents show up without a restart
+                let mut agents = registry.agents.list().await?;
+                agents.sort_by(|a, b| a.id().cmp(b.id()));
+                let mut agents_def = Vec::new();
+                for agent in agents {
+                    let available = registry
+                        .agents
+                        .availability(agent.id())
+                        .await?
+                        .is_some_and(|a| a.is_available());
+                    if available {
+                        agents_def.push(agent_preset(agent.as_ref()));
+                    }
+                }
+
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::PresetsList {
+                        agents: agents_def,
+                        prompts: vec![],
+                        tools: tools_def,
+                    },
+                ));
+            }
+            IncomingMessage::GetToolSettings => {
+                let tools = self.tool_settings(None).await?;
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::ToolSettings { tools },
+                ));
+            }
+            IncomingMessage::SetToolEnabled { tool_id, enabled } => {
+                registry.set_tool_enabled(&tool_id, enabled).await?;
+                let tools = self.tool_settings(Some(&tool_id)).await?;
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::ToolSettings { tools },
+                ));
+            }
+            IncomingMessage::ConfigureTool { tool_id, config } => {
+                registry.tools.configure(&tool_id, config).await?;
+                let tools = self.tool_settings(Some(&tool_id)).await?;
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::ToolSettings { tools },
+                ));
+            }
+            IncomingMessage::GetPrompts => {
+                // TODO: Implement prompts if needed
+                let _ = tx.send(OutgoingEnvelope::reply(
+                    request_id,
+                    OutgoingMessage::PresetsList {
+                        agents: vec![],
+                        prompts: vec![],
+                        tools: vec![],
+                    },
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How an agent in the registry is shown to viewers
+fn agent_preset(agent: &dyn crate::core::Agent) -> crate::websocket::protocol::PresetAgent {
+    let config = agent.reasoning_config();
+    let now = chrono::Utc::now().to_rfc3339();
+    crate::websocket::protocol::PresetAgent {
+        id: agent.id().to_string(),
+        name: agent.name().to_string(),
+        purpose: agent.description().to_string(),
+        system_prompt: agent.system_prompt().to_string(),
+        tools: agent
+            .tool_dependencies()
+            .iter()
+            .map(|tool_id| crate::websocket::protocol::ToolReference {
+                tool_id: tool_id.clone(),
+                enabled: true,
+            })
+            .collect(),
+        model_id: config.model_id.clone(),
+        max_iterations: config.max_iterations,
+        sampling: config.sampling.clone(),
+        metadata: crate::websocket::protocol::PresetMetadata {
+            created_at: now.clone(),
+            updated_at: now,
+            version: agent.version().to_string(),
+            author: None,
+        },
+    }
+}
+
+#[derive(Debug)]
+pub struct WebSocketConversationManager {
+    tx: Outbox,
+    request_id: Option<String>,
+    execution_id: Option<String>,
+}
+
+impl WebSocketConversationManager {
+    /// Send a message tagged with this manager's request and execution
+    fn send(&self, message: OutgoingMessage) {
+        let _ = self.tx.send(OutgoingEnvelope {
+            request_id: self.request_id.clone(),
+            execution_id: self.execution_id.clone(),
+            message,
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl ConversationManager for WebSocketConversationManager {
+    async fn send_thinking_update(
+        &self,
+        _agent_id: &str,
+        step_number: usize,
+        thought: &str,
+    ) -> crate::core::Result<()> {
+        let step = ExecutionStep {
+            step_number,
+            step_type: crate::core::StepType::Thinking,
+            content: thought.to_string(),
+            tool_call: None,
+            tool_observation: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        self.send(OutgoingMessage::ExecutionStep { step });
+        Ok(())
+    }
+
+    async fn send_progress_update(
+        &self,
+        _agent_id: &str,
+        progress_type: ProgressType,
+        message: &str,
+        _progress: Option<f32>,
+    ) -> crate::core::Result<()> {
+        let step_type = match progress_type {
+            ProgressType::Thinking => crate::core::StepType::Thinking,
+            ProgressType::Planning => crate::core::StepType::Planning,
+            ProgressType::Executing => crate::core::StepType::Action,
+            ProgressType::Observing => crate::core::StepType::Observation,
+            ProgressType::Reflecting => crate::core::StepType::Reflection,
+            ProgressType::Completing => crate::core::StepType::Completion,
+        };
+
+        let step = ExecutionStep {
+            step_number: 0,
+            step_type,
+            content: message.to_string(),
+            tool_call: None,
+            tool_observation: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+
+        self.send(OutgoingMessage::ExecutionStep { step });
+        Ok(())
+    }
+
+    async fn send_error_update(
+        &self,
+        _agent_id: &str,
+        error: &str,
+        _recovery_suggestions: Vec<String>,
+    ) -> crate::core::Result<()> {
+        self.send(OutgoingMessage::Error {
+            error: error.to_string(),
+        });
+        Ok(())
+    }
+
+    async fn send_completion_update(
+        &self,
+        _agent_id: &str,
+        final_response: &str,
+        _success: bool,
+    ) -> crate::core::Result<()> {
+        self.send(OutgoingMessage::ChatResponse {
+            content: final_response.to_string(),
+            usage: None,
+        });
+        Ok(())
+    }
+}
+
+/// Answer a hello, logging viewers whose protocol version is rejected
+fn hello_reply(
+    protocol_version: u32,
+    client_version: Option<&str>,
+    features: &[String],
+) -> OutgoingMessage {
+    let reply = crate::websocket::protocol::negotiate(protocol_version, features);
+    if let OutgoingMessage::Hello {
+        compatible: false,
+        reason: Some(reason),
+        ..
+    } = &reply
+    {
+        eprintln!(
+            "Rejected viewer {}: {}",
+            client_version.unwrap_or("(unknown version)"),
+            reason
+        );
+    }
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_usage_evicts_least_recent() {
+        let mut sessions = SessionUsage::default();
+        let call = UsageSummary {
+            llm_calls: 1,
+            total_tokens: 10,
+            ..Default::default()
+        };
+
+        sessions.record("a", &call, 2);
+        sessions.record("b", &call, 2);
+        assert_eq!(sessions.record("a", &call, 2).total_tokens, 20);
+        sessions.record("c", &call, 2);
+
+        assert!(sessions.totals.contains_key("a"));
+        assert!(!sessions.totals.contains_key("b"));
+        assert_eq!(sessions.totals["c"].llm_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_running_executions_snapshot() {
+        let running = RunningExecutions::default();
+        let first = running.start(Some("r1"), "agent", "s1", "hello").await;
+        let second = running.start(None, "agent", "s2", "world").await;
+        assert_ne!(first, second);
+        assert_eq!(running.snapshot().await.len(), 2);
+
+        running.finish(&first).await;
+        let snapshot = running.snapshot().await;
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].execution_id, second);
+        assert_eq!(snapshot[0].session_id, "s2");
+        assert!(snapshot[0].request_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_registry_events_reach_viewers() {
+        let registry = Arc::new(CentralRegistry::new());
+        registry.initialize().await.unwrap();
+        let handler = MessageHandler::new(
+            registry.clone(),
+            Arc::new(crate::llm::mock::MockLLMClient::new()),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        handler.forward_registry_events(tx);
+
+        registry.unregister_tool("fetch_url").await.unwrap();
+        let json = serde_json::to_value(rx.recv().await.unwrap()).unwrap();
+        assert_eq!(json["type"], "registry_event");
+        assert_eq!(json["event"]["kind"], "tool_removed");
+        assert_eq!(json["event"]["tool_id"], "fetch_url");
+        assert!(json.get("request_id").is_none());
+    }
+
+    async fn preset_agent_ids(
+        handler: &MessageHandler,
+        tx: &mpsc::UnboundedSender<OutgoingEnvelope>,
+        rx: &mut mpsc::UnboundedReceiver<OutgoingEnvelope>,
+    ) -> Vec<String> {
+        handler
+            .handle_message(IncomingMessage::GetPresets, None, tx.clone())
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap().message {
+            OutgoingMessage::PresetsList { agents, .. } => {
+                agents.into_iter().map(|agent| agent.id).collect()
+            }
+            other => panic!("unexpected reply: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_presets_list_live_agents() {
+        let registry = Arc::new(CentralRegistry::new());
+        registry.initialize().await.unwrap();
+        let handler = MessageHandler::new(
+            registry.clone(),
+            Arc::new(crate::llm::mock::MockLLMClient::new()),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let ids = preset_agent_ids(&handler, &tx, &mut rx).await;
+        assert!(ids.contains(&"web-research-agent".to_string()));
+
+        registry.unregister_tool("fetch_url").await.unwrap();
+        registry
+            .unregister_agent("desktop-automation-agent")
+            .await
+            .unwrap();
+        let ids = preset_agent_ids(&handler, &tx, &mut rx).await;
+        assert_eq!(ids, vec!["conversational-agent"]);
+    }
+
+    #[tokio::test]
+    async fn test_tool_settings_over_protocol() {
+        let registry = Arc::new(CentralRegistry::new());
+        registry.initialize().await.unwrap();
+        let handler = MessageHandler::new(
+            registry.clone(),
+            Arc::new(crate::llm::mock::MockLLMClient::new()),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let configure = IncomingMessage::ConfigureTool {
+            tool_id: "keyboard_type".to_string(),
+            config: serde_json::json!({"delay_ms": 10}),
+        };
+        handler
+            .handle_message(configure, Some("r1".to_string()), tx.clone())
+            .await
+            .unwrap();
+        let reply = rx.recv().await.unwrap();
+        assert_eq!(reply.request_id.as_deref(), Some("r1"));
+        match reply.message {
+            OutgoingMessage::ToolSettings { tools } => {
+                assert_eq!(tools.len(), 1);
+                assert_eq!(tools[0].config.as_ref().unwrap()["delay_ms"], 10);
+                assert!(tools[0].config_schema.is_some());
+            }
+            other => panic!("unexpected reply: {:?}", other),
+        }
+
+        let invalid = IncomingMessage::ConfigureTool {
+            tool_id: "keyboard_type".to_string(),
+            config: serde_json::json!({"delay_ms": -1, "speed": "fast"}),
+        };
+        let error = handler
+            .handle_message(invalid, None, tx.clone())
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("$.delay_ms"), "{}", error);
+        assert!(error.contains("speed"), "{}", error);
+
+        let disable = IncomingMessage::SetToolEnabled {
+            tool_id: "web_search".to_string(),
+            enabled: false,
+        };
+        handler.handle_message(disable, None, tx).await.unwrap();
+        match rx.recv().await.unwrap().message {
+            OutgoingMessage::ToolSettings { tools } => assert!(!tools[0].enabled),
+            other => panic!("unexpected reply: {:?}", other),
+        }
+        assert!(!registry
+            .agents
+            .availability("web-research-agent")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_available());
+    }
+
+    #[test]
+    fn test_envelopes_carry_correlation_ids() {
+        let incoming: IncomingEnvelope =
+            serde_json::from_str(r#"{"type": "get_presets", "request_id": "req-1"}"#).unwrap();
+        assert_eq!(incoming.request_id.as_deref(), Some("req-1"));
+        assert!(matches!(incoming.message, IncomingMessage::GetPresets));
+
+        let untagged: IncomingEnvelope =
+            serde_json::from_str(r#"{"type": "get_prompts"}"#).unwrap();
+        assert!(untagged.request_id.is_none());
+
+        let outgoing = OutgoingEnvelope {
+            request_id: Some("req-1".to_string()),
+            execution_id: Some("exec-1".to_string()),
+            message: OutgoingMessage::Error {
+                error: "boom".to_string(),
+            },
+        };
+        let json = serde_json::to_value(&outgoing).unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["request_id"], "req-1");
+        assert_eq!(json["execution_id"], "exec-1");
+        assert_eq!(json["error"], "boom");
+
+        let plain = serde_json::to_value(OutgoingEnvelope::from(OutgoingMessage::Error {
+            error: "x".to_string(),
+        }))
+        .unwrap();
+        assert!(plain.get("request_id").is_none());
+    }
+}
@@ -1,5 +1,11 @@
+pub mod auth;
 pub mod client;
+pub mod handler;
 pub mod protocol;
+pub mod server;
 
-pub use client::WebSocketRelayClient;
-pub use protocol::{IncomingMessage, OutgoingMessage};
+pub use auth::RelayAuth;
+pub use client::{ReconnectConfig, WebSocketRelayClient};
+pub use handler::{MessageHandler, WebSocketConversationManager};
+pub use protocol::{IncomingEnvelope, IncomingMessage, OutgoingEnvelope, OutgoingMessage};
+pub use server::{WebSocketServer, DEFAULT_SERVER_ADDR};
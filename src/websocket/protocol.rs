@@ -1,36 +1,120 @@
-use crate::core::ExecutionStep;
+use crate::core::{ExecutionStep, SamplingParams};
+use crate::llm::usage::{ExecutionUsage, UsageSummary};
+use crate::registry::RegistryEvent;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Version of the WebSocket protocol spoken by this backend
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest viewer protocol version this backend still understands
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional protocol features this backend supports
+pub const FEATURES: &[&str] = &[
+    "bypass_cache",
+    "correlation_ids",
+    "reconnect_status",
+    "registry_events",
+    "sampling",
+    "tool_settings",
+    "usage_reports",
+    "viewer_auth",
+];
+
+/// Field the relay adds to each viewer message to tell viewers apart
+pub const VIEWER_ID_FIELD: &str = "viewer_id";
 
 /// Messages received from the frontend (via relay)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IncomingMessage {
+    /// Opening handshake announcing the viewer's protocol version and features
+    Hello {
+        protocol_version: u32,
+        #[serde(default)]
+        client_version: Option<String>,
+        #[serde(default)]
+        features: Vec<String>,
+    },
     /// Request to start/continue a chat
-    ChatRequest { message: String, agent: AgentConfig },
+    ChatRequest {
+        message: String,
+        agent: AgentConfig,
+        /// Session the request belongs to, used to aggregate usage
+        #[serde(default)]
+        session_id: Option<String>,
+        /// Skip the LLM response cache for this request
+        #[serde(default)]
+        bypass_cache: bool,
+    },
     /// Request to get available presets
     GetPresets,
     /// Request to get available prompts
     GetPrompts,
     /// Request to reset presets to defaults
     ResetPresets,
+    /// Request the enabled flag and configuration of every tool
+    GetToolSettings,
+    /// Offer a tool to every agent, or to none; not saved to the settings file
+    SetToolEnabled { tool_id: String, enabled: bool },
+    /// Change the given keys of a tool's configuration block; not saved to the settings file
+    ConfigureTool {
+        tool_id: String,
+        config: serde_json::Value,
+    },
+}
+
+/// Incoming message with the client-generated ID used to correlate replies
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IncomingEnvelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: IncomingMessage,
 }
 
 /// Configuration for the agent sent with chat request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentConfig {
     pub system_prompt: String,
     pub model_id: String,
     pub max_iterations: usize,
     pub tools: Vec<String>,
+    /// Sampling overrides for this request; the agent's own values apply when absent
+    #[serde(default)]
+    pub sampling: Option<SamplingParams>,
 }
 
 /// Messages sent to the frontend (via relay)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum OutgoingMessage {
+    /// Reply to a viewer's hello describing what this backend speaks
+    Hello {
+        protocol_version: u32,
+        min_protocol_version: u32,
+        backend_version: String,
+        /// Message types accepted from viewers
+        incoming_types: Vec<String>,
+        /// Message types this backend may send
+        outgoing_types: Vec<String>,
+        /// Features supported by both sides
+        features: Vec<String>,
+        /// False when the viewer's protocol version is not supported
+        compatible: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
     /// Final response from the agent
-    ChatResponse { content: String },
+    ChatResponse {
+        content: String,
+        /// Token usage and estimated cost of the execution and session
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<UsageReport>,
+    },
     /// Intermediate execution step (thought, tool call, observation)
     ExecutionStep { step: ExecutionStep },
     /// List of available presets
@@ -42,9 +126,69 @@ pub enum OutgoingMessage {
     },
     /// Error message
     Error { error: String },
+    /// Sent after reconnecting to the relay so viewers can resync in-flight work
+    Status {
+        running_executions: Vec<RunningExecution>,
+    },
+    /// An agent or tool was added, removed, enabled, disabled or reconfigured
+    RegistryEvent { event: RegistryEvent },
+    /// Enabled flag and configuration of the requested tools
+    ToolSettings { tools: Vec<ToolSettingsEntry> },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Outgoing message tagged with the request and execution it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OutgoingEnvelope {
+    /// Echo of the ID sent with the originating request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Execution that produced this message, for steps and chat responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    #[serde(flatten)]
+    pub message: OutgoingMessage,
+}
+
+impl OutgoingEnvelope {
+    /// Reply to a request outside of any execution
+    pub fn reply(request_id: Option<String>, message: OutgoingMessage) -> Self {
+        Self {
+            request_id,
+            execution_id: None,
+            message,
+        }
+    }
+}
+
+impl From<OutgoingMessage> for OutgoingEnvelope {
+    fn from(message: OutgoingMessage) -> Self {
+        Self::reply(None, message)
+    }
+}
+
+/// An execution still in progress on this client
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningExecution {
+    pub execution_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub agent_id: String,
+    pub session_id: String,
+    pub message: String,
+    pub started_at: String,
+}
+
+/// Usage summary attached to the final chat response
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageReport {
+    /// Per-call and total usage of this execution
+    pub execution: ExecutionUsage,
+    /// Running totals for the session
+    pub session: UsageSummary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ToolDefinition {
     pub id: String,
     pub name: String,
@@ -55,7 +199,20 @@ pub struct ToolDefinition {
     pub returns_observation: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A tool's enabled flag and configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSettingsEntry {
+    pub tool_id: String,
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+    /// JSON Schema a new configuration block must match; absent if the tool has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PresetAgent {
     pub id: String,
@@ -65,17 +222,19 @@ pub struct PresetAgent {
     pub tools: Vec<ToolReference>,
     pub model_id: String,
     pub max_iterations: usize,
+    #[serde(default)]
+    pub sampling: SamplingParams,
     pub metadata: PresetMetadata,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolReference {
     pub tool_id: String,
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PresetMetadata {
     pub created_at: String,
@@ -85,7 +244,7 @@ pub struct PresetMetadata {
     pub author: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PresetPrompt {
     pub id: String,
@@ -97,3 +256,176 @@ pub struct PresetPrompt {
     pub content: String,
     pub metadata: PresetMetadata,
 }
+
+/// Answer a viewer's hello, rejecting protocol versions outside the supported range
+pub fn negotiate(protocol_version: u32, requested_features: &[String]) -> OutgoingMessage {
+    let reason = if protocol_version < MIN_PROTOCOL_VERSION {
+        Some(format!(
+            "Viewer protocol version {} is too old; this backend requires {} to {}",
+            protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        ))
+    } else if protocol_version > PROTOCOL_VERSION {
+        Some(format!(
+            "Viewer protocol version {} is newer than this backend's {}; update the desktop app",
+            protocol_version, PROTOCOL_VERSION
+        ))
+    } else {
+        None
+    };
+
+    // An empty request means the viewer takes whatever the backend offers
+    let features = FEATURES
+        .iter()
+        .filter(|f| requested_features.is_empty() || requested_features.iter().any(|r| r == *f))
+        .map(|f| f.to_string())
+        .collect();
+
+    OutgoingMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        min_protocol_version: MIN_PROTOCOL_VERSION,
+        backend_version: env!("CARGO_PKG_VERSION").to_string(),
+        incoming_types: message_types(&schema_value::<IncomingMessage>()),
+        outgoing_types: message_types(&schema_value::<OutgoingMessage>()),
+        features,
+        compatible: reason.is_none(),
+        reason,
+    }
+}
+
+/// JSON Schema of both message directions, for checking the viewer's types against
+pub fn json_schema() -> Value {
+    json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "incoming": schema_value::<IncomingEnvelope>(),
+        "outgoing": schema_value::<OutgoingEnvelope>(),
+    })
+}
+
+fn schema_value<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default()
+}
+
+/// Collect the `type` tags of every variant in a tagged-enum schema
+fn message_types(schema: &Value) -> Vec<String> {
+    let mut types = Vec::new();
+    for variant in schema
+        .get("oneOf")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let tags = variant
+            .pointer("/properties/type/enum")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str);
+        types.extend(tags.map(str::to_string));
+    }
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_versions() {
+        match negotiate(PROTOCOL_VERSION, &[]) {
+            OutgoingMessage::Hello {
+                compatible,
+                reason,
+                features,
+                ..
+            } => {
+                assert!(compatible);
+                assert!(reason.is_none());
+                assert_eq!(features.len(), FEATURES.len());
+            }
+            other => panic!("unexpected reply: {:?}", other),
+        }
+
+        match negotiate(PROTOCOL_VERSION + 1, &[]) {
+            OutgoingMessage::Hello {
+                compatible, reason, ..
+            } => {
+                assert!(!compatible);
+                assert!(reason.unwrap().contains("newer"));
+            }
+            other => panic!("unexpected reply: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_features_intersection() {
+        let requested = vec!["sampling".to_string(), "time_travel".to_string()];
+        match negotiate(PROTOCOL_VERSION, &requested) {
+            OutgoingMessage::Hello { features, .. } => assert_eq!(features, vec!["sampling"]),
+            other => panic!("unexpected reply: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_message_types_from_schema() {
+        let incoming = message_types(&schema_value::<IncomingMessage>());
+        for expected in [
+            "hello",
+            "chat_request",
+            "get_presets",
+            "get_prompts",
+            "reset_presets",
+            "get_tool_settings",
+            "set_tool_enabled",
+            "configure_tool",
+        ] {
+            assert!(
+                incoming.iter().any(|t| t == expected),
+                "missing {}",
+                expected
+            );
+        }
+
+        let outgoing = message_types(&schema_value::<OutgoingMessage>());
+        for expected in [
+            "hello",
+            "chat_response",
+            "execution_step",
+            "presets",
+            "error",
+            "status",
+            "registry_event",
+            "tool_settings",
+        ] {
+            assert!(
+                outgoing.iter().any(|t| t == expected),
+                "missing {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_schema_covers_envelopes() {
+        let schema = json_schema();
+        assert_eq!(schema["protocolVersion"], PROTOCOL_VERSION);
+        let incoming = schema["incoming"].to_string();
+        assert!(incoming.contains("request_id"));
+        assert!(incoming.contains("chat_request"));
+        let outgoing = schema["outgoing"].to_string();
+        assert!(outgoing.contains("execution_id"));
+        assert!(outgoing.contains("running_executions"));
+    }
+
+    #[test]
+    fn test_committed_schema_is_current() {
+        let committed: Value = serde_json::from_str(include_str!(
+            "../../web-viewer/src/types/protocol.schema.json"
+        ))
+        .expect("committed schema is valid JSON");
+        assert!(
+            committed == json_schema(),
+            "web-viewer/src/types/protocol.schema.json is out of date; \
+             run `npm run protocol:schema` in web-viewer"
+        );
+    }
+}
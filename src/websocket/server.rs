@@ -0,0 +1,393 @@
+//! Local WebSocket server speaking the relay protocol
+//!
+//! Lets the web viewer connect straight to this app, without the Cloudflare
+//! relay, for offline work and CI. Viewer tokens are always required, and
+//! browsers may only connect from localhost or an allowed origin.
+
+use crate::core::{AppError, Result};
+use crate::registry::CentralRegistry;
+use crate::websocket::auth::RelayAuth;
+use crate::websocket::handler::{Handshakes, MessageHandler};
+use crate::websocket::protocol::OutgoingEnvelope;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::http::{header::ORIGIN, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// Address the server binds to unless told otherwise
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:8788";
+
+/// Comma-separated origins, besides localhost, allowed to open a browser connection
+pub const VIEWER_ORIGINS_ENV: &str = "CF_AI_VIEWER_ORIGINS";
+
+/// Senders for each connected viewer, keyed by connection number
+type Viewers = Arc<Mutex<HashMap<u64, mpsc::UnboundedSender<Message>>>>;
+
+/// WebSocket server that viewers connect to directly
+pub struct WebSocketServer {
+    addr: String,
+    handler: MessageHandler,
+    allowed_origins: Arc<Vec<String>>,
+}
+
+impl WebSocketServer {
+    pub fn new(
+        addr: impl Into<String>,
+        registry: Arc<CentralRegistry>,
+        llm: Arc<dyn crate::core::LLMClient>,
+    ) -> Self {
+        Self {
+            addr: addr.into(),
+            handler: MessageHandler::new(registry, llm),
+            allowed_origins: Arc::new(Vec::new()),
+        }
+    }
+
+    /// Require viewer tokens on incoming messages
+    pub fn with_auth(mut self, auth: RelayAuth) -> Self {
+        self.handler = self.handler.with_auth(auth);
+        self
+    }
+
+    /// Origins, besides localhost, that browsers may connect from
+    pub fn with_allowed_origins(mut self, origins: Vec<String>) -> Self {
+        self.allowed_origins = Arc::new(
+            origins
+                .iter()
+                .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+        );
+        self
+    }
+
+    /// Origins from `CF_AI_VIEWER_ORIGINS`
+    pub fn allowed_origins_from_env() -> Vec<String> {
+        std::env::var(VIEWER_ORIGINS_ENV)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Bind the configured address and serve until the listener fails
+    pub async fn run(&self) -> Result<()> {
+        let listener = TcpListener::bind(&self.addr)
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to bind {}: {}", self.addr, e)))?;
+        self.serve(listener).await
+    }
+
+    /// Serve viewers on an already bound listener
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        // Any web page could otherwise drive this server through the user's browser
+        if self.handler.auth().viewer_tokens.is_empty() {
+            return Err(AppError::Configuration(format!(
+                "The local server requires viewer tokens; set {}",
+                crate::websocket::auth::VIEWER_TOKENS_ENV
+            )));
+        }
+        let local = listener.local_addr()?;
+        println!("Listening for viewers on ws://{}", local);
+
+        // Like the relay, every viewer sees every reply; request IDs tell them apart
+        let viewers: Viewers = Arc::new(Mutex::new(HashMap::new()));
+        let (tx, rx) = mpsc::unbounded_channel::<OutgoingEnvelope>();
+        tokio::spawn(broadcast(rx, viewers.clone()));
+        self.handler.forward_registry_events(tx.clone());
+
+        let next_id = AtomicU64::new(0);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let id = next_id.fetch_add(1, Ordering::Relaxed);
+            let handler = self.handler.clone();
+            let viewers = viewers.clone();
+            let tx = tx.clone();
+            let origins = self.allowed_origins.clone();
+            tokio::spawn(async move {
+                let viewer = Viewer { peer, id, origins };
+                if let Err(e) = serve_viewer(stream, viewer, handler, viewers, tx).await {
+                    eprintln!("Viewer {} disconnected with error: {}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+/// Serialize each outgoing message once and fan it out to every viewer
+async fn broadcast(mut rx: mpsc::UnboundedReceiver<OutgoingEnvelope>, viewers: Viewers) {
+    while let Some(msg) = rx.recv().await {
+        let text = match serde_json::to_string(&msg) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Failed to serialize message: {}", e);
+                continue;
+            }
+        };
+        let viewers = viewers.lock().unwrap_or_else(|e| e.into_inner());
+        for viewer in viewers.values() {
+            let _ = viewer.send(Message::Text(text.clone()));
+        }
+    }
+}
+
+/// Whether a browser on `origin` may connect: localhost, or listed in `allowed`
+fn origin_allowed(origin: &str, allowed: &[String]) -> bool {
+    if allowed.iter().any(|known| known == origin) {
+        return true;
+    }
+    let Ok(url) = url::Url::parse(origin) else {
+        return false;
+    };
+    matches!(url.scheme(), "http" | "https")
+        && matches!(
+            url.host_str(),
+            Some("localhost") | Some("127.0.0.1") | Some("[::1]")
+        )
+}
+
+/// Refuses browser handshakes from origins not in the allowlist. Browsers
+/// always send Origin; other clients can't be driven by a web page.
+struct OriginCheck(Arc<Vec<String>>);
+
+impl Callback for OriginCheck {
+    fn on_request(
+        self,
+        request: &Request,
+        response: Response,
+    ) -> std::result::Result<Response, ErrorResponse> {
+        match request
+            .headers()
+            .get(ORIGIN)
+            .map(|o| o.to_str().unwrap_or(""))
+        {
+            Some(origin) if !origin_allowed(origin, &self.0) => {
+                let mut reject =
+                    ErrorResponse::new(Some(format!("Origin {} is not allowed", origin)));
+                *reject.status_mut() = StatusCode::FORBIDDEN;
+                Err(reject)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+/// One incoming connection
+struct Viewer {
+    peer: SocketAddr,
+    id: u64,
+    origins: Arc<Vec<String>>,
+}
+
+async fn serve_viewer(
+    stream: TcpStream,
+    viewer: Viewer,
+    handler: MessageHandler,
+    viewers: Viewers,
+    tx: mpsc::UnboundedSender<OutgoingEnvelope>,
+) -> Result<()> {
+    let Viewer { peer, id, origins } = viewer;
+    let ws_stream = tokio_tungstenite::accept_hdr_async(stream, OriginCheck(origins))
+        .await
+        .map_err(|e| AppError::WebSocket(format!("Handshake with {} failed: {}", peer, e)))?;
+    println!("Viewer connected from {}", peer);
+
+    let (mut write, mut read) = ws_stream.split();
+    let (viewer_tx, mut viewer_rx) = mpsc::unbounded_channel::<Message>();
+    viewers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, viewer_tx);
+
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = viewer_rx.recv().await {
+            if write.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let handshakes = Handshakes::default();
+    let mut result = Ok(());
+    while let Some(msg) = read.next().await {
+        match msg {
+            Ok(Message::Text(text)) => handler.dispatch(&text, &tx, &handshakes),
+            Ok(Message::Close(_)) => break,
+            Err(e) => {
+                result = Err(AppError::WebSocket(e.to_string()));
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    viewers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    writer.abort();
+    println!("Viewer {} disconnected", peer);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock::MockLLMClient;
+    use crate::websocket::auth::VIEWER_TOKEN_FIELD;
+    use crate::websocket::protocol::PROTOCOL_VERSION;
+    use serde_json::{json, Value};
+    use tokio_tungstenite::connect_async;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+    const TOKEN: &str = "secret";
+
+    async fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = WebSocketServer::new(
+            addr.to_string(),
+            Arc::new(CentralRegistry::new()),
+            Arc::new(MockLLMClient::new()),
+        )
+        .with_auth(RelayAuth {
+            viewer_tokens: vec![TOKEN.to_string()],
+            ..Default::default()
+        })
+        .with_allowed_origins(vec!["https://viewer.example.com/".to_string()]);
+        tokio::spawn(async move { server.serve(listener).await });
+        addr
+    }
+
+    type Viewer = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    async fn connect(addr: SocketAddr) -> Viewer {
+        let (ws, _) = connect_async(format!("ws://{}/connect?device=web-viewer", addr))
+            .await
+            .unwrap();
+        ws
+    }
+
+    async fn request(ws: &mut Viewer, mut request: Value) -> Value {
+        request[VIEWER_TOKEN_FIELD] = json!(TOKEN);
+        ws.send(Message::Text(request.to_string())).await.unwrap();
+        loop {
+            if let Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    async fn exchange(addr: SocketAddr, message: Value) -> Value {
+        request(&mut connect(addr).await, message).await
+    }
+
+    async fn connect_from(addr: SocketAddr, origin: &str) -> Result<Viewer> {
+        let mut req = format!("ws://{}/connect?device=web-viewer", addr)
+            .into_client_request()
+            .unwrap();
+        req.headers_mut()
+            .insert(ORIGIN, HeaderValue::from_str(origin).unwrap());
+        connect_async(req)
+            .await
+            .map(|(ws, _)| ws)
+            .map_err(|e| AppError::WebSocket(e.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_viewer_handshake() {
+        let addr = start_server().await;
+        let reply = exchange(
+            addr,
+            json!({"type": "hello", "request_id": "h1", "protocol_version": PROTOCOL_VERSION}),
+        )
+        .await;
+
+        assert_eq!(reply["type"], "hello");
+        assert_eq!(reply["request_id"], "h1");
+        assert_eq!(reply["compatible"], true);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_viewer_rejected() {
+        let addr = start_server().await;
+        let mut ws = connect(addr).await;
+        let message = json!({"type": "get_presets", "request_id": "p1"});
+        ws.send(Message::Text(message.to_string())).await.unwrap();
+        let reply: Value = match ws.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            other => panic!("unexpected message: {:?}", other),
+        };
+
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["request_id"], "p1");
+        assert!(reply["error"].as_str().unwrap().contains("Unauthorized"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_need_a_compatible_hello() {
+        let addr = start_server().await;
+        let mut ws = connect(addr).await;
+        let presets = |id: &str| json!({"type": "get_presets", "request_id": id});
+        let hello =
+            |version: u32| json!({"type": "hello", "request_id": "h", "protocol_version": version});
+
+        let reply = request(&mut ws, presets("p1")).await;
+        assert_eq!(reply["type"], "error");
+        assert!(reply["error"]
+            .as_str()
+            .unwrap()
+            .contains("Handshake required"));
+
+        let reply = request(&mut ws, hello(PROTOCOL_VERSION + 1)).await;
+        assert_eq!(reply["compatible"], false);
+        let reply = request(&mut ws, presets("p2")).await;
+        assert_eq!(reply["type"], "error");
+
+        let reply = request(&mut ws, hello(PROTOCOL_VERSION)).await;
+        assert_eq!(reply["compatible"], true);
+        let reply = request(&mut ws, presets("p3")).await;
+        assert_eq!(reply["request_id"], "p3");
+        assert_ne!(reply["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_foreign_origins_rejected() {
+        let addr = start_server().await;
+
+        assert!(connect_from(addr, "https://evil.example.com")
+            .await
+            .is_err());
+        assert!(connect_from(addr, "http://localhost:5173").await.is_ok());
+        assert!(connect_from(addr, "https://viewer.example.com")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_refuses_to_serve_without_tokens() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = WebSocketServer::new(
+            "127.0.0.1:0",
+            Arc::new(CentralRegistry::new()),
+            Arc::new(MockLLMClient::new()),
+        )
+        .with_auth(RelayAuth {
+            allow_anonymous_viewers: true,
+            ..Default::default()
+        });
+
+        assert!(server.serve(listener).await.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Minimal MCP server over stdio used by the client tests.
+//!
+//! Tools: echo (pings the client first), add (structured result), fail (tool
+//! error) and crash (exits without answering). tools/list is paginated.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+fn tools() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "echo",
+            "description": "Echo the text back",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"text": {"type": "string", "description": "Text to echo"}},
+                "required": ["text"]
+            }
+        }),
+        json!({
+            "name": "add",
+            "description": "Add two numbers",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "a": {"type": "number", "description": "First number"},
+                    "b": {"type": ["number", "null"], "default": 0}
+                },
+                "required": ["a"]
+            }
+        }),
+        json!({"name": "fail", "description": "Always reports an error"}),
+        json!({"name": "crash", "description": "Exits without answering"}),
+    ]
+}
+
+fn send(message: Value) {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", message).unwrap();
+    stdout.flush().unwrap();
+}
+
+fn text(value: &str, is_error: bool) -> Value {
+    json!({"content": [{"type": "text", "text": value}], "isError": is_error})
+}
+
+fn add(a: &Value, b: &Value) -> Value {
+    match (a.as_i64(), b.as_i64().or(b.is_null().then_some(0))) {
+        (Some(a), Some(b)) => json!(a + b),
+        _ => json!(a.as_f64().unwrap_or(0.0) + b.as_f64().unwrap_or(0.0)),
+    }
+}
+
+fn call(name: &str, args: &Value, lines: &mut impl Iterator<Item = String>) -> Option<Value> {
+    match name {
+        "echo" => {
+            send(json!({"jsonrpc": "2.0", "id": "server-ping", "method": "ping"}));
+            let reply: Value = serde_json::from_str(&lines.next()?).unwrap();
+            assert_eq!(reply["id"], "server-ping", "{}", reply);
+            Some(text(args["text"].as_str().unwrap_or_default(), false))
+        }
+        "add" => {
+            let total = add(&args["a"], &args["b"]);
+            let mut result = text(&total.to_string(), false);
+            result["structuredContent"] = json!({"sum": total});
+            Some(result)
+        }
+        "fail" => Some(text("something went wrong", true)),
+        "crash" => std::process::exit(3),
+        _ => None,
+    }
+}
+
+fn main() {
+    let mut lines = std::io::stdin().lock().lines().map_while(Result::ok);
+    while let Some(line) = lines.next() {
+        let message: Value = serde_json::from_str(&line).unwrap();
+        let Some(id) = message.get("id").cloned() else {
+            continue; // notifications need no answer
+        };
+        let params = &message["params"];
+        let result = match message["method"].as_str() {
+            Some("initialize") => Some(json!({
+                "protocolVersion": params["protocolVersion"],
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "fixture", "version": "1.0"}
+            })),
+            Some("tools/list") => {
+                let tools = tools();
+                let start: usize = params["cursor"].as_str().unwrap_or("0").parse().unwrap();
+                let end = (start + 2).min(tools.len());
+                let mut result = json!({"tools": tools[start..end]});
+                if end < tools.len() {
+                    result["nextCursor"] = json!(end.to_string());
+                }
+                Some(result)
+            }
+            Some("tools/call") => {
+                send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/message",
+                    "params": {"level": "info", "data": "calling"}
+                }));
+                let name = params["name"].as_str().unwrap_or_default();
+                call(name, &params["arguments"], &mut lines)
+            }
+            _ => None,
+        };
+
+        match result {
+            Some(result) => send(json!({"jsonrpc": "2.0", "id": id, "result": result})),
+            None => send(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": "unknown"}
+            })),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use cf_ai_local_tools::agents::conversational::ConversationalAgent;
+use cf_ai_local_tools::agents::ConfigAgent;
 use cf_ai_local_tools::core::{Agent, AgentContext, LLMToolCall, Tool};
 use cf_ai_local_tools::llm::MockLLMClient;
 use cf_ai_local_tools::tools::delegation::DelegateToAgent;
@@ -6,7 +6,7 @@ use cf_ai_local_tools::tools::delegation::DelegateToAgent;
 #[tokio::test]
 async fn test_single_delegation() {
     // 1. Setup Orchestrator Agent
-    let orchestrator = ConversationalAgent::new();
+    let orchestrator = ConfigAgent::conversational();
     let context = AgentContext::new(orchestrator.id().to_string());
 
     // 2. Setup Tools
@@ -67,7 +67,7 @@ async fn test_single_delegation() {
 #[tokio::test]
 async fn test_multi_agent_delegation() {
     // 1. Setup Orchestrator Agent
-    let orchestrator = ConversationalAgent::new();
+    let orchestrator = ConfigAgent::conversational();
     let context = AgentContext::new(orchestrator.id().to_string());
 
     // 2. Setup Tools
@@ -157,3 +157,85 @@ async fn test_multi_agent_delegation() {
             .success
     );
 }
+
+#[tokio::test]
+async fn test_scripted_multi_turn_run() {
+    use cf_ai_local_tools::llm::ScriptedResponse;
+
+    let agent = ConfigAgent::web_research();
+    let context = AgentContext::new(agent.id().to_string());
+    let tools: Vec<Box<dyn Tool>> = vec![Box::new(DelegateToAgent::new())];
+
+    // Call the tool twice with different tasks, then answer
+    let mock_llm = MockLLMClient::new();
+    for task in ["Look up the docs", "Summarize the docs"] {
+        mock_llm.push(ScriptedResponse::tool_calls(
+            "",
+            vec![LLMToolCall {
+                name: "delegate_to_agent".to_string(),
+                arguments: serde_json::json!({
+                    "target_agent": "conversational-agent",
+                    "task": task
+                }),
+                id: None,
+            }],
+        ));
+    }
+    mock_llm.push(ScriptedResponse::text("Here is the summary."));
+
+    let result = agent
+        .execute("Research the docs", &context, &mock_llm, None, &tools)
+        .await
+        .expect("Agent execution failed");
+
+    assert!(result.success);
+    assert_eq!(result.response, "Here is the summary.");
+    assert_eq!(mock_llm.remaining(), 0);
+    assert_eq!(result.usage.total.llm_calls, 3);
+
+    // Each follow-up request carries the previous observation
+    let requests = mock_llm.requests();
+    assert_eq!(requests.len(), 3);
+    let last_message = requests[1].last_message().unwrap();
+    assert_eq!(last_message.role, "tool");
+    assert!(last_message.content.as_text().contains("Look up the docs"));
+    assert_eq!(
+        requests[2].tools.as_ref().unwrap()[0].name,
+        "delegate_to_agent"
+    );
+}
+
+#[tokio::test]
+async fn test_structured_final_answer() {
+    use cf_ai_local_tools::llm::ScriptedResponse;
+
+    let mut agent = ConfigAgent::web_research();
+    agent.reasoning_config.output_schema = Some(serde_json::json!({
+        "type": "object",
+        "properties": {
+            "summary": {"type": "string"},
+            "sources": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["summary", "sources"]
+    }));
+    let context = AgentContext::new(agent.id().to_string());
+
+    let mock_llm = MockLLMClient::new();
+    mock_llm.push(ScriptedResponse::text("Rust is a systems language."));
+    // First attempt is missing a field, so it is retried with the error
+    mock_llm.push(ScriptedResponse::text(r#"{"summary": "Rust"}"#));
+    mock_llm.push(ScriptedResponse::text(
+        r#"{"summary": "Rust", "sources": ["https://rust-lang.org"]}"#,
+    ));
+
+    let result = agent
+        .execute("Research Rust", &context, &mock_llm, None, &[])
+        .await
+        .expect("Agent execution failed");
+
+    assert!(result.success);
+    assert_eq!(result.response, "Rust is a systems language.");
+    let structured = result.structured_output.expect("structured output");
+    assert_eq!(structured["sources"][0], "https://rust-lang.org");
+    assert_eq!(result.usage.total.llm_calls, 3);
+}
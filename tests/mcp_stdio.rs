@@ -0,0 +1,128 @@
+//! MCP client tests against the fixture server in `tests/fixtures/mcp_server.rs`
+
+use cf_ai_local_tools::core::{Tool, ToolContext};
+use cf_ai_local_tools::mcp::{import_tools, McpClient, McpConfig, McpServerConfig, McpTool};
+use cf_ai_local_tools::tools::registry::{DefaultToolRegistry, ToolRegistry};
+use serde_json::json;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+/// The fixture server example, built first when only this test target was built
+fn fixture_server() -> &'static str {
+    static PATH: OnceLock<String> = OnceLock::new();
+    PATH.get_or_init(|| {
+        // Test binaries live in target/<profile>/deps, examples in target/<profile>/examples
+        let profile_dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .and_then(Path::parent)
+            .unwrap()
+            .to_path_buf();
+        let path = profile_dir.join("examples").join(format!(
+            "mcp_fixture_server{}",
+            std::env::consts::EXE_SUFFIX
+        ));
+        if !path.exists() {
+            let status = Command::new(env!("CARGO"))
+                .args(["build", "--quiet", "--example", "mcp_fixture_server"])
+                .current_dir(env!("CARGO_MANIFEST_DIR"))
+                .status()
+                .unwrap();
+            assert!(status.success(), "building the MCP fixture server failed");
+        }
+        path.to_string_lossy().into_owned()
+    })
+}
+
+fn fixture_config() -> McpServerConfig {
+    McpServerConfig::stdio("fixture", fixture_server(), &[])
+}
+
+#[tokio::test]
+async fn test_stdio_tools_and_restart() {
+    let client = McpClient::new(fixture_config());
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<_> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["echo", "add", "fail", "crash"]);
+
+    let echoed = client
+        .call_tool("echo", &json!({"text": "hi"}))
+        .await
+        .unwrap();
+    assert_eq!(echoed.text(), "hi");
+
+    let sum = client
+        .call_tool("add", &json!({"a": 2, "b": 3}))
+        .await
+        .unwrap();
+    assert_eq!(sum.structured_content.unwrap()["sum"], 5);
+    assert!(client.call_tool("fail", &json!({})).await.unwrap().is_error);
+    assert!(client.call_tool("missing", &json!({})).await.is_err());
+
+    // The server dies mid-call; the call fails and the next one restarts it
+    assert!(client.call_tool("crash", &json!({})).await.is_err());
+    assert_eq!(client.restarts(), 0);
+    let echoed = client
+        .call_tool("echo", &json!({"text": "again"}))
+        .await
+        .unwrap();
+    assert_eq!(echoed.text(), "again");
+    assert_eq!(client.restarts(), 1);
+}
+
+#[tokio::test]
+async fn test_import_registers_tools_under_server_category() {
+    let config = McpConfig {
+        servers: vec![
+            fixture_config(),
+            McpServerConfig::stdio("absent", "cf-ai-no-such-mcp-server", &[]),
+        ],
+    };
+    let registry = DefaultToolRegistry::new();
+
+    let import = import_tools(&config, &registry).await.unwrap();
+    assert_eq!(import.loaded.len(), 4);
+    assert_eq!(import.errors.len(), 1);
+    assert_eq!(import.errors[0].0, "absent");
+    assert_eq!(
+        registry
+            .find_by_category("mcp:fixture")
+            .await
+            .unwrap()
+            .len(),
+        4
+    );
+    assert!(registry.get("fixture__echo").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_execute_forwards_to_server() {
+    let client = Arc::new(McpClient::new(fixture_config()));
+    let info = client
+        .list_tools()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|t| t.name == "add")
+        .unwrap();
+    let tool = McpTool::new(client, info);
+    assert_eq!(tool.id(), "fixture__add");
+    assert_eq!(tool.name(), "fixture__add");
+    assert_eq!(tool.category(), "mcp:fixture");
+    assert!(tool.validate_args(&json!({})).is_err());
+
+    let context = ToolContext {
+        agent_id: "test".to_string(),
+        conversation_manager: None,
+        execution_state: Default::default(),
+    };
+    let result = tool
+        .execute(&json!({"a": 1, "b": 2}), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.message, "3");
+    assert_eq!(result.data.unwrap()["sum"], 3);
+}
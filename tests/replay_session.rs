@@ -0,0 +1,141 @@
+//! Re-runs a recorded web research session through the ReAct loop, offline.
+//!
+//! The LLM's side of the session is served from `fixtures/sessions/web_research.json`,
+//! so a change to what the agent sends the model shows up as a missing recorded
+//! response. After an intended change, record the fixture again with the Worker
+//! running (`wrangler dev`):
+//!
+//! ```text
+//! CF_AI_LLM_REPLAY=record:tests/fixtures/sessions/web_research.json \
+//!     cargo test --test replay_session
+//! ```
+
+use async_trait::async_trait;
+use cf_ai_local_tools::agents::ConfigAgent;
+use cf_ai_local_tools::core::{
+    Agent, AgentContext, Result, StepType, Tool, ToolContext, ToolParameter, ToolResult,
+};
+use cf_ai_local_tools::llm::{HttpClient, RecordReplayClient, ReplayMode};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/sessions/web_research.json"
+);
+
+/// Where `main` sends LLM calls; only used when recording
+const WORKER_URL: &str = "http://localhost:8787";
+
+const RELEASE_NOTES: &str = "Tokio 1.40.0 (August 30th, 2024). Added: \
+    io::Join and io::join, task::AbortHandle::is_finished. Fixed: a panic in \
+    the multi-thread runtime when shutting down with blocked tasks.";
+
+/// Stands in for `fetch_url` so the session doesn't depend on the network
+#[derive(Clone)]
+struct CannedPage {
+    parameters: Vec<ToolParameter>,
+}
+
+impl CannedPage {
+    fn new() -> Self {
+        Self {
+            parameters: vec![ToolParameter {
+                name: "url".to_string(),
+                param_type: "string".to_string(),
+                description: "URL of the page to fetch".to_string(),
+                required: true,
+                default: None,
+                enum_values: None,
+            }],
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for CannedPage {
+    fn id(&self) -> &str {
+        "fetch_url"
+    }
+
+    fn name(&self) -> &str {
+        "fetch_url"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a web page and return its text"
+    }
+
+    fn category(&self) -> &str {
+        "web"
+    }
+
+    fn parameters(&self) -> &[ToolParameter] {
+        &self.parameters
+    }
+
+    async fn execute(&self, args: &Value, _context: &ToolContext) -> Result<ToolResult> {
+        Ok(ToolResult {
+            success: true,
+            message: format!("Fetched {}", args["url"].as_str().unwrap_or_default()),
+            data: Some(json!({ "text": RELEASE_NOTES })),
+            execution_time: std::time::Duration::from_millis(0),
+        })
+    }
+
+    fn validate_args(&self, args: &Value) -> Result<()> {
+        match args["url"].as_str() {
+            Some(_) => Ok(()),
+            None => Err(cf_ai_local_tools::core::AppError::Tool(
+                "url is required".to_string(),
+            )),
+        }
+    }
+}
+
+/// The fixture, or a recorder in front of the Worker when `CF_AI_LLM_REPLAY=record:<path>`
+fn llm() -> RecordReplayClient {
+    match ReplayMode::from_env().unwrap() {
+        Some(ReplayMode::Record(path)) => {
+            RecordReplayClient::record(Arc::new(HttpClient::new(WORKER_URL.to_string())), path)
+        }
+        _ => RecordReplayClient::replay(FIXTURE).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_replayed_web_research_session() {
+    let agent = ConfigAgent::web_research();
+    let context = AgentContext::new(agent.id().to_string());
+    let tools: Vec<Box<dyn Tool>> = vec![Box::new(CannedPage::new())];
+
+    let result = agent
+        .execute(
+            "When was Tokio 1.40 released?",
+            &context,
+            &llm(),
+            None,
+            &tools,
+        )
+        .await
+        .expect("every request should have a recorded response");
+
+    assert!(result.success);
+    assert!(result.response.contains("30 August 2024"));
+    assert_eq!(result.usage.total.llm_calls, 2);
+
+    let action = result
+        .steps
+        .iter()
+        .find(|s| matches!(s.step_type, StepType::Action))
+        .and_then(|s| s.tool_call.as_ref())
+        .expect("the agent should fetch a page");
+    assert_eq!(action.tool_name, "fetch_url");
+
+    let observation = result
+        .steps
+        .iter()
+        .find_map(|s| s.tool_observation.as_ref())
+        .expect("the fetched page should be observed");
+    assert!(observation.success);
+}
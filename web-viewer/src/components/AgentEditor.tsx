@@ -185,9 +185,117 @@ export const AgentEditor: React.FC = () => {
               </div>
             </div>
 
+            {/* Sampling */}
+            <div>
+              <label className="block text-sm font-medium text-gray-700 mb-1">
+                Temperature: {currentAgent.sampling?.temperature ?? 0.7}
+              </label>
+              <input
+                type="range"
+                min="0"
+                max="2"
+                step="0.1"
+                value={currentAgent.sampling?.temperature ?? 0.7}
+                onChange={(e) =>
+                  updateCurrentAgent({
+                    sampling: {
+                      ...currentAgent.sampling,
+                      temperature: parseFloat(e.target.value),
+                    },
+                  })
+                }
+                className="w-full"
+              />
+              <div className="flex justify-between text-xs text-gray-500 mt-1">
+                <span>0 (deterministic)</span>
+                <span>2</span>
+              </div>
+
+              <div className="grid grid-cols-3 gap-2 mt-3">
+                <div>
+                  <label className="block text-xs text-gray-600 mb-1">Top P</label>
+                  <input
+                    type="number"
+                    min="0.01"
+                    max="1"
+                    step="0.05"
+                    value={currentAgent.sampling?.topP ?? ''}
+                    placeholder="default"
+                    onChange={(e) =>
+                      updateCurrentAgent({
+                        sampling: {
+                          ...currentAgent.sampling,
+                          topP: e.target.value === '' ? undefined : parseFloat(e.target.value),
+                        },
+                      })
+                    }
+                    className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+                  />
+                </div>
+                <div>
+                  <label className="block text-xs text-gray-600 mb-1">Max Tokens</label>
+                  <input
+                    type="number"
+                    min="1"
+                    value={currentAgent.sampling?.maxTokens ?? ''}
+                    placeholder="4096"
+                    onChange={(e) =>
+                      updateCurrentAgent({
+                        sampling: {
+                          ...currentAgent.sampling,
+                          maxTokens: e.target.value === '' ? undefined : parseInt(e.target.value),
+                        },
+                      })
+                    }
+                    className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+                  />
+                </div>
+                <div>
+                  <label className="block text-xs text-gray-600 mb-1">Seed</label>
+                  <input
+                    type="number"
+                    min="0"
+                    value={currentAgent.sampling?.seed ?? ''}
+                    placeholder="random"
+                    onChange={(e) =>
+                      updateCurrentAgent({
+                        sampling: {
+                          ...currentAgent.sampling,
+                          seed: e.target.value === '' ? undefined : parseInt(e.target.value),
+                        },
+                      })
+                    }
+                    className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+                  />
+                </div>
+              </div>
+
+              <label className="block text-xs text-gray-600 mt-3 mb-1">
+                Stop Sequences (comma-separated, max 4)
+              </label>
+              <input
+                type="text"
+                value={(currentAgent.sampling?.stop || []).join(', ')}
+                onChange={(e) =>
+                  updateCurrentAgent({
+                    sampling: {
+                      ...currentAgent.sampling,
+                      stop: e.target.value
+                        .split(',')
+                        .map((s) => s.trim())
+                        .filter((s) => s.length > 0)
+                        .slice(0, 4),
+                    },
+                  })
+                }
+                className="w-full px-2 py-1 border border-gray-300 rounded-md text-sm"
+              />
+            </div>
+
             {/* Tools */}
             <div>
               <ToolSelector
+                tools={tools}
                 selectedTools={currentAgent.tools}
                 onChange={(tools) => updateCurrentAgent({ tools })}
                 disabled={false}
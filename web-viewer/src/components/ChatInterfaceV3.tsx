@@ -11,9 +11,13 @@ import React, { useState, useRef, useEffect } from 'react';
 import { useAgentStore } from '../store/agentStore';
 import { usePromptStore, handlePromptMessage } from '../store/promptStore';
 import { useWebSocketStore } from '../store/webSocketStore';
-import { fetchBackendPresets, hasPresetsLoaded } from '../utils/backendPresets';
+import { clearCachedPresets, fetchBackendPresets, hasPresetsLoaded } from '../utils/backendPresets';
+import { getViewerToken, setViewerToken } from '../utils/viewerAuth';
 import type { ExecutionStep } from '../store/executionStore';
 
+// Registry changes often come in bursts (a tool removed, then its agents disabled)
+const PRESET_REFRESH_DEBOUNCE_MS = 300;
+
 // Note: WebSocket connection is managed by webSocketStore in App.tsx
 
 interface ChatMessage {
@@ -197,6 +201,41 @@ const ExecutionSteps: React.FC<{ steps: ExecutionStep[] }> = ({ steps }) => {
     );
 };
 
+// Token the desktop expects on every command; kept for this browser session only
+const ViewerTokenInput: React.FC<{ onSaved: () => void }> = ({ onSaved }) => {
+    const [token, setToken] = useState(() => getViewerToken() || '');
+    const [saved, setSaved] = useState(() => Boolean(getViewerToken()));
+
+    const save = () => {
+        setViewerToken(token);
+        setSaved(Boolean(token.trim()));
+        onSaved();
+    };
+
+    return (
+        <div className="flex items-center gap-2">
+            <input
+                type="password"
+                value={token}
+                onChange={(e) => {
+                    setToken(e.target.value);
+                    setSaved(false);
+                }}
+                onKeyDown={(e) => e.key === 'Enter' && save()}
+                placeholder="Viewer token"
+                autoComplete="off"
+                className="w-40 px-2 py-1 text-sm border border-gray-300 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
+            />
+            <button
+                onClick={save}
+                className="px-2 py-1 text-sm bg-gray-100 border border-gray-300 rounded hover:bg-gray-200"
+            >
+                {saved ? 'Saved' : 'Use token'}
+            </button>
+        </div>
+    );
+};
+
 export const ChatInterfaceV3: React.FC = () => {
     const { currentAgent, setBackendAgents } = useAgentStore();
     const { setWebSocket: setPromptWebSocket } = usePromptStore();
@@ -206,6 +245,9 @@ export const ChatInterfaceV3: React.FC = () => {
     const [isWaitingForResponse, setIsWaitingForResponse] = useState(false);
     const messagesEndRef = useRef<HTMLDivElement>(null);
     const currentAssistantIndex = useRef<number | null>(null);
+    // ID of the chat request awaiting a response; replies to other requests are ignored
+    const currentRequestId = useRef<string | null>(null);
+    const presetRefreshTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
 
     const scrollToBottom = () => {
         messagesEndRef.current?.scrollIntoView({ behavior: 'smooth' });
@@ -251,6 +293,11 @@ export const ChatInterfaceV3: React.FC = () => {
             try {
                 const data = JSON.parse(event.data);
 
+                // Skip steps and responses that belong to another viewer's request
+                if (data.request_id && data.request_id !== currentRequestId.current) {
+                    return;
+                }
+
                 // Handle prompt-related messages
                 if (
                     data.type === 'prompts' ||
@@ -261,6 +308,25 @@ export const ChatInterfaceV3: React.FC = () => {
                     data.type === 'prompt_error'
                 ) {
                     handlePromptMessage(data);
+                } else if (data.type === 'status') {
+                    // Sent after the backend reconnects; buffered steps and responses follow it
+                    console.log('[Chat] Backend reconnected, running executions:', data.running_executions);
+                } else if (data.type === 'registry_event') {
+                    // An agent or tool changed on the backend; reload the presets it serves
+                    // once the burst of events settles
+                    console.log('[Chat] Registry changed:', data.event);
+                    if (presetRefreshTimer.current) {
+                        clearTimeout(presetRefreshTimer.current);
+                    }
+                    presetRefreshTimer.current = setTimeout(() => {
+                        presetRefreshTimer.current = null;
+                        clearCachedPresets();
+                        fetchBackendPresets()
+                            .then((presets) => setBackendAgents(presets.agents || []))
+                            .catch((error) => {
+                                console.error('[Chat] Error refreshing backend presets:', error);
+                            });
+                    }, PRESET_REFRESH_DEBOUNCE_MS);
                 } else if (data.type === 'execution_step') {
                     console.log('[Chat] Received execution step:', data.step);
                     const currentIndex = currentAssistantIndex.current;
@@ -307,6 +373,27 @@ export const ChatInterfaceV3: React.FC = () => {
 
                     setIsWaitingForResponse(false);
                     currentAssistantIndex.current = null;
+                    currentRequestId.current = null;
+                } else if (data.type === 'error' && data.request_id) {
+                    console.error('[Chat] Request failed:', data.error);
+                    const currentIndex = currentAssistantIndex.current;
+                    if (currentIndex !== null) {
+                        setConversationHistory((prev) => {
+                            const updated = [...prev];
+                            if (updated[currentIndex]) {
+                                updated[currentIndex] = {
+                                    ...updated[currentIndex],
+                                    content: `Error: ${data.error}`,
+                                    isStreaming: false,
+                                };
+                            }
+                            return updated;
+                        });
+                    }
+
+                    setIsWaitingForResponse(false);
+                    currentAssistantIndex.current = null;
+                    currentRequestId.current = null;
                 }
             } catch (error) {
                 console.error('[Chat] Error parsing message:', error);
@@ -318,7 +405,16 @@ export const ChatInterfaceV3: React.FC = () => {
         return () => {
             ws.removeEventListener('message', handleMessage);
         };
-    }, [ws, currentAgent, message]);
+    }, [ws, currentAgent, message, setBackendAgents]);
+
+    // Drop a pending preset refresh when the chat goes away
+    useEffect(() => {
+        return () => {
+            if (presetRefreshTimer.current) {
+                clearTimeout(presetRefreshTimer.current);
+            }
+        };
+    }, []);
 
     const handleSend = async () => {
         if (!message.trim() || !currentAgent || isWaitingForResponse || !connected) {
@@ -341,13 +437,18 @@ export const ChatInterfaceV3: React.FC = () => {
         // Send chat request via WebSocket
         setIsWaitingForResponse(true);
 
+        const requestId = crypto.randomUUID();
+        currentRequestId.current = requestId;
+
         send({
             type: 'chat_request',
+            request_id: requestId,
             message: userMessage,
             agent: {
                 systemPrompt: currentAgent.systemPrompt,
                 modelId: currentAgent.modelId,
                 maxIterations: currentAgent.maxIterations,
+                sampling: currentAgent.sampling,
                 tools: currentAgent.tools
                     .filter((t) => t.enabled)
                     .map((t) => t.toolId),
@@ -365,7 +466,7 @@ export const ChatInterfaceV3: React.FC = () => {
     return (
         <div className="flex flex-col h-full bg-gray-50">
             {/* Header */}
-            <div className="px-6 py-4 bg-white border-b border-gray-200">
+            <div className="px-6 py-4 bg-white border-b border-gray-200 flex items-start justify-between">
                 <div>
                     <h2 className="text-lg font-semibold text-gray-900">
                         {currentAgent?.name || 'Select an agent'}
@@ -376,6 +477,19 @@ export const ChatInterfaceV3: React.FC = () => {
                         </p>
                     )}
                 </div>
+                <ViewerTokenInput
+                    onSaved={() => {
+                        // Presets requested without a token were refused; ask again
+                        clearCachedPresets();
+                        if (connected) {
+                            fetchBackendPresets()
+                                .then((presets) => setBackendAgents(presets.agents || []))
+                                .catch((error) => {
+                                    console.error('[Chat] Error fetching backend presets:', error);
+                                });
+                        }
+                    }}
+                />
             </div>
 
             {/* Messages */}
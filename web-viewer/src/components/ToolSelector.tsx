@@ -5,27 +5,18 @@
 
 import React from 'react';
 import { ToolReference } from '../types/agent';
-
-const AVAILABLE_TOOLS = [
-  { id: 'mouse_move', name: 'Move Mouse', description: 'Move cursor to position', category: 'mouse' },
-  { id: 'mouse_click', name: 'Click Mouse', description: 'Click mouse button', category: 'mouse' },
-  { id: 'mouse_scroll', name: 'Scroll Mouse', description: 'Scroll in direction', category: 'mouse' },
-  { id: 'keyboard_input', name: 'Type Text', description: 'Type text on keyboard', category: 'keyboard' },
-  { id: 'keyboard_command', name: 'Key Command', description: 'Execute keyboard command', category: 'keyboard' },
-  { id: 'get_mouse_position', name: 'Get Mouse Position', description: 'Get current cursor position', category: 'system' },
-  { id: 'take_screenshot', name: 'Screenshot', description: 'Capture screenshot', category: 'system' },
-  { id: 'web_search', name: 'Web Search', description: 'Search the web using SearXNG', category: 'search' },
-  { id: 'fetch_url', name: 'Fetch URL', description: 'Fetch and parse webpage content', category: 'search' },
-  { id: 'delegate_to_agent', name: 'Delegate to Agent', description: 'Delegate task to another agent', category: 'utility' },
-];
+import { ToolDefinition } from '../types/tool';
 
 interface ToolSelectorProps {
+  /** Tools that can be selected, as listed by the backend */
+  tools: ToolDefinition[];
   selectedTools: ToolReference[];
   onChange: (tools: ToolReference[]) => void;
   disabled?: boolean;
 }
 
 export const ToolSelector: React.FC<ToolSelectorProps> = ({
+  tools,
   selectedTools,
   onChange,
   disabled = false,
@@ -51,7 +42,7 @@ export const ToolSelector: React.FC<ToolSelectorProps> = ({
     }
   };
 
-  const categories = Array.from(new Set(AVAILABLE_TOOLS.map((t) => t.category)));
+  const categories = Array.from(new Set(tools.map((t) => t.category)));
 
   return (
     <div className="tool-selector">
@@ -65,7 +56,7 @@ export const ToolSelector: React.FC<ToolSelectorProps> = ({
             {category}
           </h4>
           <div className="space-y-2">
-            {AVAILABLE_TOOLS.filter((t) => t.category === category).map((tool) => (
+            {tools.filter((t) => t.category === category).map((tool) => (
               <label
                 key={tool.id}
                 className="flex items-start gap-3 p-2 hover:bg-gray-50 rounded cursor-pointer"
@@ -73,6 +73,7 @@ export const useAgentStore = create<AgentState>((set, get) => ({
             tools: agent.tools || [],
             modelId: agent.model_id,
             maxIterations: agent.max_iterations,
+            sampling: agent.sampling,
             metadata: agent.metadata || {
               createdAt: new Date().toISOString(),
               updatedAt: new Date().toISOString(),
@@ -192,6 +193,7 @@ export const useAgentStore = create<AgentState>((set, get) => ({
       tools: config.tools || [],
       modelId: config.modelId || '@cf/meta/llama-3.3-70b-instruct-fp8-fast',
       maxIterations: config.maxIterations || 5,
+      sampling: config.sampling,
       metadata: {
         createdAt: new Date().toISOString(),
         updatedAt: new Date().toISOString(),
@@ -4,6 +4,8 @@
  */
 
 import { create } from 'zustand';
+import { withViewerToken } from '../utils/viewerAuth';
+import { helloMessage, type HelloResponse } from '../types/protocol';
 
 interface WebSocketState {
   // WebSocket instance
@@ -15,6 +17,9 @@ interface WebSocketState {
   // Connection URL
   url: string | null;
 
+  // Backend's reply to our hello; null until the handshake completes
+  backend: HelloResponse | null;
+
   // Actions
   connect: (url: string) => void;
   disconnect: () => void;
@@ -26,6 +31,7 @@ export const useWebSocketStore = create<WebSocketState>((set, get) => ({
   ws: null,
   connected: false,
   url: null,
+  backend: null,
 
   connect: (url: string) => {
     // Don't reconnect if already connected to same URL
@@ -43,11 +49,32 @@ export const useWebSocketStore = create<WebSocketState>((set, get) => ({
       socket.onopen = () => {
         console.log('[WebSocketStore] Connected');
         set({ ws: socket, connected: true, url });
+        socket.send(JSON.stringify(withViewerToken(helloMessage())));
       };
 
+      socket.addEventListener('message', (event) => {
+        try {
+          const data = JSON.parse(event.data);
+          // The desktop (re)connected to the relay and forgot earlier handshakes
+          if (data.type === 'status') {
+            socket.send(JSON.stringify(withViewerToken(helloMessage())));
+            return;
+          }
+          if (data.type !== 'hello') return;
+          if (!data.compatible) {
+            console.error('[WebSocketStore] Backend rejected protocol:', data.reason);
+          } else {
+            console.log('[WebSocketStore] Backend', data.backend_version, 'features:', data.features);
+          }
+          set({ backend: data as HelloResponse });
+        } catch {
+          // Non-JSON frames are handled elsewhere
+        }
+      });
+
       socket.onclose = (event) => {
         console.log('[WebSocketStore] Disconnected', event.code, event.reason);
-        set({ ws: null, connected: false });
+        set({ ws: null, connected: false, backend: null });
 
         // Auto-reconnect after 3 seconds
         setTimeout(() => {
@@ -84,7 +111,7 @@ export const useWebSocketStore = create<WebSocketState>((set, get) => ({
     const { ws, connected } = get();
     if (ws && connected && ws.readyState === WebSocket.OPEN) {
       try {
-        ws.send(JSON.stringify(data));
+        ws.send(JSON.stringify(withViewerToken(data)));
       } catch (error) {
         console.error('[WebSocketStore] Send error:', error);
       }
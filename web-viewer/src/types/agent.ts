@@ -16,6 +16,14 @@ export interface ToolReference {
   enabled: boolean;
 }
 
+export interface SamplingParams {
+  temperature?: number; // 0 - 2, 0 = deterministic
+  topP?: number; // (0, 1]
+  maxTokens?: number;
+  stop?: string[]; // at most 4
+  seed?: number;
+}
+
 export interface Agent {
   id: string;
   name: string;
@@ -24,6 +32,7 @@ export interface Agent {
   tools: ToolReference[];
   modelId: string;
   maxIterations: number;
+  sampling?: SamplingParams;
   metadata: Metadata;
   isDefault?: boolean;
   isPinned?: boolean;
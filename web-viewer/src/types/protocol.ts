@@ -0,0 +1,45 @@
+/**
+ * WebSocket Protocol Handshake
+ * Must match PROTOCOL_VERSION in src/websocket/protocol.rs; check message shapes
+ * against protocol.schema.json (npm run protocol:schema)
+ */
+
+export const PROTOCOL_VERSION = 1;
+
+export const CLIENT_FEATURES = [
+  'bypass_cache',
+  'correlation_ids',
+  'reconnect_status',
+  'registry_events',
+  'sampling',
+  'usage_reports',
+  'viewer_auth',
+];
+
+export interface HelloRequest {
+  type: 'hello';
+  protocol_version: number;
+  client_version?: string;
+  features: string[];
+}
+
+export interface HelloResponse {
+  type: 'hello';
+  protocol_version: number;
+  min_protocol_version: number;
+  backend_version: string;
+  incoming_types: string[];
+  outgoing_types: string[];
+  features: string[];
+  compatible: boolean;
+  reason?: string;
+}
+
+export function helloMessage(): HelloRequest {
+  return {
+    type: 'hello',
+    protocol_version: PROTOCOL_VERSION,
+    client_version: '0.1.0',
+    features: CLIENT_FEATURES,
+  };
+}
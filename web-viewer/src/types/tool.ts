@@ -15,7 +15,8 @@ export interface ToolDefinition {
   id: string;
   name: string;
   description: string;
-  category: 'mouse' | 'keyboard' | 'system' | 'search' | 'utility';
+  // Set by the backend, e.g. 'desktop_automation', 'web' or an imported API's name
+  category: string;
   parameters: ToolParameter[];
   returnsObservation: boolean;
 }
@@ -5,6 +5,7 @@
 import { Agent } from '../types/agent';
 import { ToolDefinition } from '../types/tool';
 import { useWebSocketStore } from '../store/webSocketStore';
+import { withViewerToken } from './viewerAuth';
 
 export interface BackendPresetsResponse {
   tools: ToolDefinition[];
@@ -92,7 +93,7 @@ export async function fetchBackendPresets(): Promise<BackendPresetsResponse> {
 
     // Send request to backend
     try {
-      ws.send(JSON.stringify({ type: 'get_presets' }));
+      ws.send(JSON.stringify(withViewerToken({ type: 'get_presets' })));
     } catch (e) {
       console.error('Error sending get_presets request:', e);
       ws.removeEventListener('message', handler);
@@ -4,7 +4,9 @@
  */
 
 import { ToolDefinition } from '../types/tool';
+import { getCachedPresets } from './backendPresets';
 
+// Built-in tools, shown until the backend's tool list has been fetched
 const AVAILABLE_TOOLS: ToolDefinition[] = [
   {
     id: 'mouse_move',
@@ -41,7 +43,7 @@ const AVAILABLE_TOOLS: ToolDefinition[] = [
     returnsObservation: true,
   },
   {
-    id: 'keyboard_input',
+    id: 'keyboard_type',
     name: 'Type Text',
     description: 'Type text on keyboard',
     category: 'keyboard',
@@ -52,7 +54,7 @@ const AVAILABLE_TOOLS: ToolDefinition[] = [
     returnsObservation: true,
   },
   {
-    id: 'keyboard_command',
+    id: 'keyboard_hotkey',
     name: 'Key Command',
     description: 'Execute keyboard command',
     category: 'keyboard',
@@ -62,7 +64,7 @@ const AVAILABLE_TOOLS: ToolDefinition[] = [
     returnsObservation: true,
   },
   {
-    id: 'get_mouse_position',
+    id: 'screen_get_position',
     name: 'Get Mouse Position',
     description: 'Get current cursor position',
     category: 'system',
@@ -70,7 +72,7 @@ const AVAILABLE_TOOLS: ToolDefinition[] = [
     returnsObservation: true,
   },
   {
-    id: 'take_screenshot',
+    id: 'screen_screenshot',
     name: 'Screenshot',
     description: 'Capture screenshot',
     category: 'system',
@@ -116,14 +118,18 @@ const AVAILABLE_TOOLS: ToolDefinition[] = [
   },
 ];
 
+/**
+ * Tools registered and enabled on the backend, or the built-in list before it has answered
+ */
 export function getAllTools(): ToolDefinition[] {
-  return AVAILABLE_TOOLS;
+  const backendTools = getCachedPresets()?.tools;
+  return backendTools && backendTools.length > 0 ? backendTools : AVAILABLE_TOOLS;
 }
 
 export function getToolById(toolId: string): ToolDefinition | undefined {
-  return AVAILABLE_TOOLS.find((t) => t.id === toolId);
+  return getAllTools().find((t) => t.id === toolId);
 }
 
 export function getToolsByCategory(category: string): ToolDefinition[] {
-  return AVAILABLE_TOOLS.filter((t) => t.category === category);
+  return getAllTools().filter((t) => t.category === category);
 }
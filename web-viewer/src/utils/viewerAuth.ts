@@ -0,0 +1,25 @@
+/**
+ * Viewer Authentication
+ * The desktop only runs commands that carry a token it knows (CF_AI_VIEWER_TOKENS).
+ * The token is entered by the user and kept for the browser session only, so it
+ * never ends up in the built bundle.
+ */
+
+const STORAGE_KEY = 'cf_ai_viewer_token';
+
+export function getViewerToken(): string | null {
+  return sessionStorage.getItem(STORAGE_KEY);
+}
+
+export function setViewerToken(token: string): void {
+  if (token.trim()) {
+    sessionStorage.setItem(STORAGE_KEY, token.trim());
+  } else {
+    sessionStorage.removeItem(STORAGE_KEY);
+  }
+}
+
+export function withViewerToken<T extends object>(message: T): T & { viewer_token?: string } {
+  const token = getViewerToken();
+  return token ? { ...message, viewer_token: token } : message;
+}